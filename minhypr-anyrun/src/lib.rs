@@ -0,0 +1,67 @@
+/*
+ * anyrun plugin for minhypr: lists the minimized windows matching the query
+ * and restores the chosen one through the minhypr library.
+ */
+
+use abi_stable::std_types::{ROption, RString, RVec};
+use anyrun_plugin::{get_matches, handler, info, init, HandleResult, Match, PluginInfo};
+use minhypr::{window::windows_by_recency, Manager};
+
+// Hyprland addresses are hex pointers ("0x55d3a8f0e1b0"), so they fit the
+// numeric match id and round-trip without keeping any state
+fn address_to_id(address: &str) -> Option<u64> {
+    u64::from_str_radix(address.trim_start_matches("0x"), 16).ok()
+}
+
+fn id_to_address(id: u64) -> String {
+    format!("0x{:x}", id)
+}
+
+#[init]
+fn init(_config_dir: RString) -> Manager {
+    // Informational output would end up in anyrun's log
+    minhypr::set_quiet(true);
+    Manager::new()
+}
+
+#[info]
+fn info() -> PluginInfo {
+    PluginInfo {
+        name: "Minimized windows".into(),
+        icon: "window-restore".into(),
+    }
+}
+
+#[get_matches]
+fn get_matches(input: RString, manager: &Manager) -> RVec<Match> {
+    let Ok(windows) = manager.windows() else {
+        return RVec::new();
+    };
+
+    let query = input.trim().to_lowercase();
+
+    windows_by_recency(&windows)
+        .into_iter()
+        .filter(|w| query.is_empty() || format!("{} {}", w.class, w.original_title).to_lowercase().contains(&query))
+        .filter_map(|w| {
+            let id = address_to_id(&w.address)?;
+            let icon = w.icon_path.clone().unwrap_or_else(|| w.class.to_lowercase());
+            Some(Match {
+                title: w.original_title.clone().into(),
+                description: ROption::RSome(format!("{} · workspace {}", w.class, w.workspace).into()),
+                use_pango: false,
+                icon: ROption::RSome(icon.into()),
+                id: ROption::RSome(id),
+            })
+        })
+        .collect()
+}
+
+#[handler]
+fn handler(selection: Match, manager: &Manager) -> HandleResult {
+    if let ROption::RSome(id) = selection.id {
+        manager.restore(&id_to_address(id)).ok();
+    }
+
+    HandleResult::Close
+}
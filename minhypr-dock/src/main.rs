@@ -0,0 +1,145 @@
+/*
+ * minhypr-dock - Layer-shell dock showing the windows minimized by minhypr
+ *
+ * Started by `minhypr dock`. The list is reloaded whenever the cache file
+ * changes, which the event daemon keeps up to date.
+ */
+
+use std::{cell::RefCell, env, fs, rc::Rc, time::Duration, time::SystemTime};
+
+use gtk4::{glib, pango, prelude::*, Application, ApplicationWindow, GestureClick, Image, Label, Orientation, Picture};
+use gtk4_layer_shell::{Edge, Layer, LayerShell};
+use minhypr::{window::windows_by_recency, Manager};
+
+const THUMB_WIDTH: i32 = 160;
+const THUMB_HEIGHT: i32 = 120;
+
+fn parse_edge(name: &str) -> Edge {
+    match name {
+        "top" => Edge::Top,
+        "left" => Edge::Left,
+        "right" => Edge::Right,
+        _ => Edge::Bottom,
+    }
+}
+
+fn cache_modified(manager: &Manager) -> Option<SystemTime> {
+    fs::metadata(manager.store.cache_file()).and_then(|m| m.modified()).ok()
+}
+
+// One dock entry: thumbnail (or icon), title; left click restores, middle click closes
+fn window_tile(manager: &Rc<Manager>, window: &minhypr::MinimizedWindow) -> gtk4::Box {
+    let tile = gtk4::Box::new(Orientation::Vertical, 4);
+    tile.add_css_class("minhypr-tile");
+    tile.set_tooltip_text(Some(&format!("{} - {}", window.class, window.original_title)));
+
+    match &window.preview_path {
+        Some(preview) => {
+            let picture = Picture::for_filename(preview);
+            picture.set_size_request(THUMB_WIDTH, THUMB_HEIGHT);
+            picture.set_can_shrink(true);
+            tile.append(&picture);
+        }
+        None => {
+            let image = match &window.icon_path {
+                Some(icon) => Image::from_file(icon),
+                None => Image::from_icon_name(&window.class.to_lowercase()),
+            };
+            image.set_pixel_size(64);
+            image.set_size_request(THUMB_WIDTH, THUMB_HEIGHT);
+            tile.append(&image);
+        }
+    }
+
+    let label = Label::new(Some(&format!("{} {}", window.icon, window.original_title)));
+    label.set_ellipsize(pango::EllipsizeMode::End);
+    label.set_max_width_chars(20);
+    tile.append(&label);
+
+    let click = GestureClick::new();
+    // Listen to every button
+    click.set_button(0);
+    let manager = Rc::clone(manager);
+    let address = window.address.clone();
+    click.connect_released(move |gesture, _, _, _| {
+        match gesture.current_button() {
+            1 => {
+                manager.restore(&address).ok();
+            }
+            2 => {
+                manager.close_minimized(&address).ok();
+            }
+            _ => {}
+        }
+    });
+    tile.add_controller(click);
+
+    tile
+}
+
+fn refresh(window: &ApplicationWindow, row: &gtk4::Box, manager: &Rc<Manager>) {
+    while let Some(child) = row.first_child() {
+        row.remove(&child);
+    }
+
+    let windows = manager.windows().unwrap_or_default();
+    for minimized in windows_by_recency(&windows) {
+        row.append(&window_tile(manager, minimized));
+    }
+
+    // Nothing minimized: get out of the way
+    window.set_visible(!windows.is_empty());
+}
+
+fn build_dock(app: &Application, edge: Edge) {
+    let window = ApplicationWindow::new(app);
+    window.init_layer_shell();
+    window.set_layer(Layer::Top);
+    window.set_namespace("minhypr-dock");
+    window.set_anchor(edge, true);
+    window.set_margin(edge, 8);
+    window.auto_exclusive_zone_enable();
+
+    let orientation = match edge {
+        Edge::Left | Edge::Right => Orientation::Vertical,
+        _ => Orientation::Horizontal,
+    };
+    let row = gtk4::Box::new(orientation, 8);
+    row.set_margin_top(8);
+    row.set_margin_bottom(8);
+    row.set_margin_start(8);
+    row.set_margin_end(8);
+    window.set_child(Some(&row));
+
+    let manager = Rc::new(Manager::new());
+    let last_modified = Rc::new(RefCell::new(cache_modified(&manager)));
+    refresh(&window, &row, &manager);
+
+    // Cheap mtime check; the daemon rewrites the cache on every change
+    glib::timeout_add_local(Duration::from_millis(500), move || {
+        let modified = cache_modified(&manager);
+        if modified != *last_modified.borrow() {
+            *last_modified.borrow_mut() = modified;
+            refresh(&window, &row, &manager);
+        }
+        glib::ControlFlow::Continue
+    });
+}
+
+fn main() -> glib::ExitCode {
+    minhypr::set_quiet(true);
+
+    // minhypr-dock [--edge top|bottom|left|right]
+    let args: Vec<String> = env::args().collect();
+    let edge = args
+        .iter()
+        .position(|arg| arg == "--edge")
+        .and_then(|i| args.get(i + 1))
+        .map_or(Edge::Bottom, |name| parse_edge(name));
+
+    let app = Application::builder().application_id("org.minhypr.Dock").build();
+    app.connect_activate(move |app| build_dock(app, edge));
+
+    // Our arguments are not GTK's
+    app.run_with_args::<&str>(&[])
+}
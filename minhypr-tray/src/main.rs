@@ -0,0 +1,164 @@
+/*
+ * minhypr-tray - StatusNotifierItem tray icon for the windows minimized by minhypr
+ *
+ * Started by `minhypr tray`. The menu lists the minimized windows, each with
+ * restore and close actions; the title and tooltip carry the count. The list
+ * is reloaded whenever the cache file changes.
+ */
+
+use std::{fs, thread, time::Duration, time::SystemTime};
+
+use ksni::{
+    menu::{StandardItem, SubMenu},
+    MenuItem, Status, ToolTip, Tray, TrayService,
+};
+use minhypr::{window::windows_by_recency, Manager, MinimizedWindow};
+
+struct MinhyprTray {
+    manager: Manager,
+    windows: Vec<MinimizedWindow>,
+}
+
+impl MinhyprTray {
+    fn reload(&mut self) {
+        self.windows = self.manager.windows().unwrap_or_default();
+    }
+}
+
+fn count_label(count: usize) -> String {
+    match count {
+        0 => "No minimized windows".to_string(),
+        1 => "1 minimized window".to_string(),
+        n => format!("{} minimized windows", n),
+    }
+}
+
+impl Tray for MinhyprTray {
+    fn id(&self) -> String {
+        "minhypr".into()
+    }
+
+    fn title(&self) -> String {
+        count_label(self.windows.len())
+    }
+
+    fn icon_name(&self) -> String {
+        "window-restore".into()
+    }
+
+    // Hosts that honour the status (waybar's tray among them) hide a passive item
+    fn status(&self) -> Status {
+        if self.windows.is_empty() {
+            Status::Passive
+        } else {
+            Status::Active
+        }
+    }
+
+    fn tool_tip(&self) -> ToolTip {
+        let description = windows_by_recency(&self.windows)
+            .iter()
+            .map(|w| format!("{} {} - {}", w.icon, w.class, w.original_title))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        ToolTip {
+            title: count_label(self.windows.len()),
+            description,
+            ..Default::default()
+        }
+    }
+
+    // Left click restores the most recently minimized window
+    fn activate(&mut self, _x: i32, _y: i32) {
+        if let Some(window) = windows_by_recency(&self.windows).first() {
+            self.manager.restore(&window.address).ok();
+        }
+        self.reload();
+    }
+
+    fn menu(&self) -> Vec<MenuItem<Self>> {
+        let mut items: Vec<MenuItem<Self>> = windows_by_recency(&self.windows)
+            .into_iter()
+            .map(|window| {
+                let restore_address = window.address.clone();
+                let close_address = window.address.clone();
+
+                SubMenu {
+                    label: format!("{} {} - {}", window.icon, window.class, window.original_title),
+                    icon_name: window.icon_path.clone().unwrap_or_default(),
+                    submenu: vec![
+                        StandardItem {
+                            label: "Restore".into(),
+                            icon_name: "window-restore".into(),
+                            activate: Box::new(move |tray: &mut Self| {
+                                tray.manager.restore(&restore_address).ok();
+                                tray.reload();
+                            }),
+                            ..Default::default()
+                        }
+                        .into(),
+                        StandardItem {
+                            label: "Close".into(),
+                            icon_name: "window-close".into(),
+                            activate: Box::new(move |tray: &mut Self| {
+                                tray.manager.close_minimized(&close_address).ok();
+                                tray.reload();
+                            }),
+                            ..Default::default()
+                        }
+                        .into(),
+                    ],
+                    ..Default::default()
+                }
+                .into()
+            })
+            .collect();
+
+        if !items.is_empty() {
+            items.push(MenuItem::Separator);
+            items.push(
+                StandardItem {
+                    label: "Restore all".into(),
+                    activate: Box::new(|tray: &mut Self| {
+                        tray.manager.restore_all().ok();
+                        tray.reload();
+                    }),
+                    ..Default::default()
+                }
+                .into(),
+            );
+        }
+
+        items
+    }
+}
+
+fn cache_modified(manager: &Manager) -> Option<SystemTime> {
+    fs::metadata(manager.store.cache_file()).and_then(|m| m.modified()).ok()
+}
+
+fn main() {
+    minhypr::set_quiet(true);
+
+    let mut tray = MinhyprTray { manager: Manager::new(), windows: Vec::new() };
+    tray.reload();
+
+    let watcher = Manager::new();
+    let mut last_modified = cache_modified(&watcher);
+
+    let service = TrayService::new(tray);
+    let handle = service.handle();
+    service.spawn();
+
+    // Cheap mtime check; the daemon rewrites the cache on every change
+    loop {
+        thread::sleep(Duration::from_millis(500));
+
+        let modified = cache_modified(&watcher);
+        if modified != last_modified {
+            last_modified = modified;
+            handle.update(MinhyprTray::reload);
+        }
+    }
+}
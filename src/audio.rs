@@ -0,0 +1,68 @@
+use std::{fs, process::Command};
+
+use serde_json::Value;
+
+// Parent PID from /proc/<pid>/stat; the command name may contain spaces, so
+// the fields are read after its closing parenthesis
+fn parent_pid(pid: i32) -> Option<i32> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(1)?.parse().ok()
+}
+
+// Browsers and Electron apps play audio from helper processes, so a stream
+// belongs to the window's process if it comes from it or any descendant
+fn is_descendant(mut pid: i32, ancestor: i32) -> bool {
+    for _ in 0..32 {
+        if pid == ancestor {
+            return true;
+        }
+        match parent_pid(pid) {
+            Some(parent) if parent > 1 => pid = parent,
+            _ => return false,
+        }
+    }
+    false
+}
+
+// PipeWire ids of the playback streams (sink inputs) of a process, from pw-dump
+fn output_streams(pid: i32) -> Vec<u64> {
+    let Ok(output) = Command::new("pw-dump").output() else {
+        return Vec::new();
+    };
+    let Ok(Value::Array(objects)) = serde_json::from_slice(&output.stdout) else {
+        return Vec::new();
+    };
+
+    objects
+        .iter()
+        .filter(|object| object["info"]["props"]["media.class"] == "Stream/Output/Audio")
+        .filter(|object| {
+            let props = &object["info"]["props"];
+            // Numeric in pw-dump, but some clients set it as a string
+            let stream_pid = props["application.process.id"]
+                .as_i64()
+                .or_else(|| props["application.process.id"].as_str().and_then(|s| s.parse().ok()));
+            stream_pid.is_some_and(|stream_pid| is_descendant(stream_pid as i32, pid))
+        })
+        .filter_map(|object| object["id"].as_u64())
+        .collect()
+}
+
+/// Mute or unmute the playback streams of a process with wpctl. Returns
+/// whether any stream was changed.
+pub fn set_muted(pid: i32, muted: bool) -> bool {
+    if pid <= 0 {
+        return false;
+    }
+
+    let mute = if muted { "1" } else { "0" };
+    let mut changed = false;
+    for id in output_streams(pid) {
+        changed |= Command::new("wpctl")
+            .args(["set-mute", &id.to_string(), mute])
+            .status()
+            .is_ok_and(|status| status.success());
+    }
+    changed
+}
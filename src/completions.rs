@@ -0,0 +1,298 @@
+use std::collections::BTreeMap;
+
+use clap::{Arg, ArgAction, Command, ValueEnum};
+
+use crate::window::MinimizedWindow;
+
+/// Shells `minhypr completions` writes scripts for.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Nushell,
+}
+
+// Positional arguments with these names take minimized windows, completed by
+// `minhypr complete-windows`
+fn takes_window(arg: &Arg) -> bool {
+    arg.is_positional() && matches!(arg.get_id().as_str(), "address" | "addresses")
+}
+
+fn visible_subcommands(command: &Command) -> Vec<&Command> {
+    command.get_subcommands().filter(|sub| !sub.is_hide_set()).collect()
+}
+
+fn options(command: &Command) -> Vec<&Arg> {
+    command
+        .get_arguments()
+        .filter(|arg| !arg.is_positional() && !arg.is_hide_set() && arg.get_long().is_some())
+        .collect()
+}
+
+fn takes_value(arg: &Arg) -> bool {
+    arg.get_action().takes_values()
+}
+
+// Choices of an option's value (switches have none, even with a bool parser)
+fn possible_values(arg: &Arg) -> Vec<String> {
+    if !takes_value(arg) {
+        return Vec::new();
+    }
+    arg.get_possible_values().iter().filter(|v| !v.is_hide_set()).map(|v| v.get_name().to_string()).collect()
+}
+
+// First line of the help text
+fn about(help: Option<&clap::builder::StyledStr>) -> String {
+    help.map(|help| help.to_string().lines().next().unwrap_or_default().to_string()).unwrap_or_default()
+}
+
+/// Completion candidates for window arguments: addresses, described by class
+/// and title, then classes (`restore` also takes a class).
+pub fn window_candidates(windows: &[MinimizedWindow]) -> Vec<(String, String)> {
+    let mut candidates: Vec<(String, String)> = windows
+        .iter()
+        .map(|w| (w.address.clone(), format!("{} - {}", w.class, w.original_title.replace(['\n', '\t'], " "))))
+        .collect();
+
+    let mut classes: BTreeMap<&str, usize> = BTreeMap::new();
+    for window in windows {
+        *classes.entry(&window.class).or_default() += 1;
+    }
+    for (class, count) in classes {
+        candidates.push((class.to_string(), format!("{} minimized", count)));
+    }
+    candidates
+}
+
+/// The completion script of `command` for `shell`.
+pub fn generate(shell: Shell, command: &Command) -> String {
+    match shell {
+        Shell::Bash => bash(command),
+        Shell::Zsh => zsh(command),
+        Shell::Fish => fish(command),
+        Shell::Nushell => nushell(command),
+    }
+}
+
+fn bash(command: &Command) -> String {
+    let name = command.get_name();
+    let global: Vec<String> = options(command).iter().map(|arg| format!("--{}", arg.get_long().unwrap_or_default())).collect();
+    let subcommands: Vec<&str> = visible_subcommands(command).iter().map(|sub| sub.get_name()).collect();
+
+    let mut script = format!(
+        "_{name}() {{\n    local cur=${{COMP_WORDS[COMP_CWORD]}}\n    local prev=${{COMP_WORDS[COMP_CWORD-1]}}\n\n    if [ \"$COMP_CWORD\" -eq 1 ]; then\n        COMPREPLY=($(compgen -W \"{} {}\" -- \"$cur\"))\n        return\n    fi\n\n    case \"${{COMP_WORDS[1]}}\" in\n",
+        subcommands.join(" "),
+        global.join(" "),
+    );
+
+    for sub in visible_subcommands(command) {
+        let flags: Vec<String> = options(sub)
+            .iter()
+            .map(|arg| format!("--{}", arg.get_long().unwrap_or_default()))
+            .chain(global.iter().cloned())
+            .collect();
+        script.push_str(&format!("        {})\n", sub.get_name()));
+
+        // Values of the option just typed
+        let valued: Vec<(&Arg, Vec<String>)> =
+            options(sub).into_iter().map(|arg| (arg, possible_values(arg))).filter(|(_, values)| !values.is_empty()).collect();
+        if !valued.is_empty() {
+            script.push_str("            case \"$prev\" in\n");
+            for (arg, values) in valued {
+                script.push_str(&format!(
+                    "                --{}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;\n",
+                    arg.get_long().unwrap_or_default(),
+                    values.join(" ")
+                ));
+            }
+            script.push_str("            esac\n");
+        }
+
+        let words = if sub.get_arguments().any(takes_window) {
+            format!("$({} complete-windows 2>/dev/null | cut -f1)", name)
+        } else {
+            visible_subcommands(sub).iter().map(|nested| nested.get_name()).collect::<Vec<_>>().join(" ")
+        };
+        script.push_str(&format!(
+            "            if [[ $cur == -* ]]; then\n                COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n            else\n                COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n            fi\n            ;;\n",
+            flags.join(" "),
+            words
+        ));
+    }
+
+    script.push_str(&format!("    esac\n}}\n\ncomplete -F _{name} {name}\n"));
+    script
+}
+
+// Quote text for a single-quoted zsh _arguments spec
+fn zsh_escape(text: &str) -> String {
+    text.replace('\'', "'\\''").replace('[', "\\[").replace(']', "\\]").replace(':', "\\:")
+}
+
+fn zsh_option(arg: &Arg) -> String {
+    let long = arg.get_long().unwrap_or_default();
+    let help = zsh_escape(&about(arg.get_help()));
+    if !takes_value(arg) {
+        return format!("'--{}[{}]'", long, help);
+    }
+    let values = possible_values(arg);
+    if values.is_empty() {
+        format!("'--{}[{}]:value:'", long, help)
+    } else {
+        format!("'--{}[{}]:value:({})'", long, help, values.join(" "))
+    }
+}
+
+fn zsh(command: &Command) -> String {
+    let name = command.get_name();
+    let mut script = format!(
+        "#compdef {name}\n\n_{name}_windows() {{\n    local -a windows\n    windows=(${{(f)\"$({name} complete-windows 2>/dev/null | sed 's/:/\\\\:/g; s/\\t/:/')\"}})\n    _describe 'minimized window' windows\n}}\n\n_{name}() {{\n    local -a commands\n    commands=(\n"
+    );
+    for sub in visible_subcommands(command) {
+        script.push_str(&format!("        '{}:{}'\n", sub.get_name(), about(sub.get_about()).replace('\'', "'\\''")));
+    }
+    script.push_str("    )\n\n    if (( CURRENT == 2 )); then\n        _describe 'command' commands\n        return\n    fi\n\n    shift words\n    (( CURRENT-- ))\n    case $words[1] in\n");
+
+    let global: Vec<String> = options(command).into_iter().map(zsh_option).collect();
+    for sub in visible_subcommands(command) {
+        let mut specs: Vec<String> = options(sub).into_iter().map(zsh_option).chain(global.iter().cloned()).collect();
+        let nested: Vec<&str> = visible_subcommands(sub).iter().map(|nested| nested.get_name()).collect();
+        if sub.get_arguments().any(takes_window) {
+            specs.push(format!("'*:window:_{}_windows'", name));
+        } else if !nested.is_empty() {
+            specs.push(format!("'1:command:({})'", nested.join(" ")));
+        }
+        script.push_str(&format!("        {})\n            _arguments \\\n", sub.get_name()));
+        for spec in &specs {
+            script.push_str(&format!("                {} \\\n", spec));
+        }
+        script.push_str("            ;;\n");
+    }
+
+    script.push_str(&format!("    esac\n}}\n\n_{name} \"$@\"\n"));
+    script
+}
+
+// Quote text for a single-quoted fish string
+fn fish_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn fish_option(name: &str, condition: &str, arg: &Arg) -> String {
+    let mut line = format!("complete -c {} -n '{}' -l {}", name, condition, arg.get_long().unwrap_or_default());
+    if takes_value(arg) {
+        line.push_str(" -r");
+        let values = possible_values(arg);
+        if !values.is_empty() {
+            line.push_str(&format!(" -a '{}'", values.join(" ")));
+        }
+    }
+    line.push_str(&format!(" -d '{}'\n", fish_escape(&about(arg.get_help()))));
+    line
+}
+
+fn fish(command: &Command) -> String {
+    let name = command.get_name();
+    let mut script = format!("complete -c {} -f\n", name);
+
+    for arg in options(command) {
+        script.push_str(&fish_option(name, "true", arg));
+    }
+    for sub in visible_subcommands(command) {
+        script.push_str(&format!(
+            "complete -c {} -n __fish_use_subcommand -a {} -d '{}'\n",
+            name,
+            sub.get_name(),
+            fish_escape(&about(sub.get_about()))
+        ));
+    }
+
+    for sub in visible_subcommands(command) {
+        let condition = format!("__fish_seen_subcommand_from {}", sub.get_name());
+        for arg in options(sub) {
+            script.push_str(&fish_option(name, &condition, arg));
+        }
+        if sub.get_arguments().any(takes_window) {
+            script.push_str(&format!("complete -c {} -n '{}' -a '({} complete-windows 2>/dev/null)'\n", name, condition, name));
+        }
+        for nested in visible_subcommands(sub) {
+            script.push_str(&format!(
+                "complete -c {} -n '{}' -a {} -d '{}'\n",
+                name,
+                condition,
+                nested.get_name(),
+                fish_escape(&about(nested.get_about()))
+            ));
+        }
+    }
+
+    script
+}
+
+fn nushell_params(name: &str, path: &str, command: &Command, global: &[&Arg]) -> (String, String) {
+    let mut helpers = String::new();
+    let mut params = String::new();
+
+    for arg in command.get_arguments().filter(|arg| arg.is_positional() && !arg.is_hide_set()) {
+        let id = arg.get_id().as_str().replace('_', "-");
+        let completer = if takes_window(arg) { format!("@\"nu-complete {} windows\"", name) } else { String::new() };
+        let param = if matches!(arg.get_action(), ArgAction::Append) {
+            format!("...{}: string{}", id, completer)
+        } else if arg.is_required_set() {
+            format!("{}: string{}", id, completer)
+        } else {
+            format!("{}?: string{}", id, completer)
+        };
+        params.push_str(&format!("    {}  # {}\n", param, about(arg.get_help())));
+    }
+
+    for arg in options(command).into_iter().chain(global.iter().copied()) {
+        let long = arg.get_long().unwrap_or_default();
+        let short = arg.get_short().map(|c| format!("(-{})", c)).unwrap_or_default();
+        let kind = if !takes_value(arg) {
+            String::new()
+        } else if possible_values(arg).is_empty() {
+            String::from(": string")
+        } else {
+            let helper = ["nu-complete", name, path, long].iter().filter(|part| !part.is_empty()).copied().collect::<Vec<_>>().join(" ");
+            let values: Vec<String> = possible_values(arg).iter().map(|v| format!("\"{}\"", v)).collect();
+            helpers.push_str(&format!("def \"{}\" [] {{\n    [{}]\n}}\n\n", helper, values.join(" ")));
+            format!(": string@\"{}\"", helper)
+        };
+        params.push_str(&format!("    --{}{}{}  # {}\n", long, short, kind, about(arg.get_help())));
+    }
+
+    (helpers, params)
+}
+
+fn nushell(command: &Command) -> String {
+    let name = command.get_name();
+    let global = options(command);
+    let mut script = format!(
+        "def \"nu-complete {name} windows\" [] {{\n    ^{name} complete-windows | lines | parse \"{{value}}\\t{{description}}\"\n}}\n\n"
+    );
+
+    let (helpers, params) = nushell_params(name, "", command, &[]);
+    script.push_str(&helpers);
+    script.push_str(&format!("# {}\nexport extern \"{}\" [\n{}]\n\n", about(command.get_about()), name, params));
+
+    let mut commands: Vec<(String, &Command)> =
+        visible_subcommands(command).into_iter().map(|sub| (sub.get_name().to_string(), sub)).collect();
+    let mut i = 0;
+    while i < commands.len() {
+        let (path, sub) = commands[i].clone();
+        for nested in visible_subcommands(sub) {
+            commands.push((format!("{} {}", path, nested.get_name()), nested));
+        }
+        i += 1;
+    }
+
+    for (path, sub) in commands {
+        let (helpers, params) = nushell_params(name, &path, sub, &global);
+        script.push_str(&helpers);
+        script.push_str(&format!("# {}\nexport extern \"{} {}\" [\n{}]\n\n", about(sub.get_about()), name, path, params));
+    }
+
+    script
+}
@@ -0,0 +1,139 @@
+use std::io::Result;
+
+use crate::hyprland::{Client, Monitor, Workspace};
+
+/// A window operation, so several can be sent to the compositor at once.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Move a window to a workspace (id, name or `special:name`); unless
+    /// `silent`, focus follows it.
+    MoveToWorkspace { workspace: String, address: String, silent: bool },
+    FocusWindow(String),
+    FocusMonitor(String),
+    SetFloating(String),
+    MoveWindowPixel { address: String, position: (i32, i32) },
+    ResizeWindowPixel { address: String, size: (i32, i32) },
+    /// Toggle fullscreen on the active window (mode 0 = fullscreen, 1 = maximize).
+    Fullscreen(u8),
+    /// Add or remove a window tag.
+    Tag { address: String, tag: String, add: bool },
+    CloseWindow(String),
+    /// Take a window out of its group.
+    MoveOutOfGroup(String),
+    /// Move the active window into the group in a direction (`l`, `r`, `u`, `d`).
+    MoveIntoGroup(char),
+    /// Launch a shell command from the compositor, so the app isn't minhypr's child.
+    Exec(String),
+}
+
+impl Action {
+    /// Hyprland dispatcher and its argument.
+    pub fn dispatch_args(&self) -> [String; 2] {
+        match self {
+            Action::MoveToWorkspace { workspace, address, silent } => [
+                String::from(if *silent { "movetoworkspacesilent" } else { "movetoworkspace" }),
+                format!("{},address:{}", workspace, address),
+            ],
+            Action::FocusWindow(address) => [String::from("focuswindow"), format!("address:{}", address)],
+            Action::FocusMonitor(monitor) => [String::from("focusmonitor"), monitor.clone()],
+            Action::SetFloating(address) => [String::from("setfloating"), format!("address:{}", address)],
+            Action::MoveWindowPixel { address, position: (x, y) } => {
+                [String::from("movewindowpixel"), format!("exact {} {},address:{}", x, y, address)]
+            }
+            Action::ResizeWindowPixel { address, size: (width, height) } => {
+                [String::from("resizewindowpixel"), format!("exact {} {},address:{}", width, height, address)]
+            }
+            Action::Fullscreen(mode) => [String::from("fullscreen"), mode.to_string()],
+            Action::Tag { address, tag, add } => [
+                String::from("tagwindow"),
+                format!("{}{} address:{}", if *add { '+' } else { '-' }, tag, address),
+            ],
+            Action::CloseWindow(address) => [String::from("closewindow"), format!("address:{}", address)],
+            Action::MoveOutOfGroup(address) => [String::from("moveoutofgroup"), format!("address:{}", address)],
+            Action::MoveIntoGroup(direction) => [String::from("moveintogroup"), direction.to_string()],
+            Action::Exec(command) => [String::from("exec"), command.clone()],
+        }
+    }
+}
+
+/// What minhypr needs from the compositor: window, workspace and monitor
+/// queries, and running [`Action`]s. Implemented by [`Hyprland`] and, for
+/// tests, by the in-memory [`MockCompositor`].
+///
+/// [`Hyprland`]: crate::hyprland::Hyprland
+/// [`MockCompositor`]: crate::mock::MockCompositor
+pub trait Compositor {
+    fn clients(&self) -> Result<Vec<Client>>;
+
+    /// The focused window, if any.
+    fn active_window(&self) -> Result<Option<Client>>;
+
+    fn active_workspace(&self) -> Result<Workspace>;
+
+    fn workspaces(&self) -> Result<Vec<Workspace>>;
+
+    fn monitors(&self) -> Result<Vec<Monitor>>;
+
+    /// Run the actions in order, in a single request where the compositor
+    /// allows it. Returns whether all of them succeeded.
+    fn run(&self, actions: &[Action]) -> Result<bool>;
+
+    fn client(&self, address: &str) -> Result<Option<Client>> {
+        Ok(self.clients()?.into_iter().find(|c| c.address == address))
+    }
+
+    /// Names of the connected monitors.
+    fn monitor_names(&self) -> Result<Vec<String>> {
+        Ok(self.monitors()?.into_iter().map(|m| m.name).collect())
+    }
+
+    fn monitor_name(&self, id: i64) -> Result<Option<String>> {
+        Ok(self.monitors()?.into_iter().find(|m| m.id == id).map(|m| m.name))
+    }
+
+    fn move_to_workspace(&self, workspace: &str, address: &str) -> Result<bool> {
+        self.run(&[Action::MoveToWorkspace { workspace: workspace.to_string(), address: address.to_string(), silent: false }])
+    }
+
+    fn move_to_workspace_silent(&self, workspace: &str, address: &str) -> Result<bool> {
+        self.run(&[Action::MoveToWorkspace { workspace: workspace.to_string(), address: address.to_string(), silent: true }])
+    }
+
+    fn focus_monitor(&self, monitor: &str) -> Result<bool> {
+        self.run(&[Action::FocusMonitor(monitor.to_string())])
+    }
+
+    fn focus_window(&self, address: &str) -> Result<bool> {
+        self.run(&[Action::FocusWindow(address.to_string())])
+    }
+
+    fn set_floating(&self, address: &str) -> Result<bool> {
+        self.run(&[Action::SetFloating(address.to_string())])
+    }
+
+    fn move_window_pixel(&self, address: &str, position: (i32, i32)) -> Result<bool> {
+        self.run(&[Action::MoveWindowPixel { address: address.to_string(), position }])
+    }
+
+    fn resize_window_pixel(&self, address: &str, size: (i32, i32)) -> Result<bool> {
+        self.run(&[Action::ResizeWindowPixel { address: address.to_string(), size }])
+    }
+
+    /// Toggle fullscreen on the active window (mode 0 = fullscreen, 1 = maximize).
+    fn fullscreen(&self, mode: u8) -> Result<bool> {
+        self.run(&[Action::Fullscreen(mode)])
+    }
+
+    /// Add or remove a window tag.
+    fn tag_window(&self, address: &str, tag: &str, add: bool) -> Result<bool> {
+        self.run(&[Action::Tag { address: address.to_string(), tag: tag.to_string(), add }])
+    }
+
+    fn close_window(&self, address: &str) -> Result<bool> {
+        self.run(&[Action::CloseWindow(address.to_string())])
+    }
+
+    fn exec(&self, command: &str) -> Result<bool> {
+        self.run(&[Action::Exec(command.to_string())])
+    }
+}
@@ -0,0 +1,1053 @@
+use std::{collections::BTreeMap, env, fs, path::PathBuf};
+
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::{
+    log::Level,
+    window::{format_window, MinimizedWindow},
+};
+
+/// Default `title_format`; the address keeps entries unique.
+pub const DEFAULT_TITLE_FORMAT: &str = "{icon} {class} - {title} [{short_address}]";
+
+/// What happens when a minimized window asks for attention.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UrgentAction {
+    /// Nothing.
+    Ignore,
+    /// Restore the window.
+    Restore,
+    /// Mark it, giving the waybar module the `urgent` class.
+    #[default]
+    Highlight,
+    /// Mark it and show a notification with a "Restore" action.
+    Notify,
+}
+
+/// What happens to a minimize past `max_minimized`.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitAction {
+    /// Don't minimize, and say so with a notification.
+    #[default]
+    Refuse,
+    /// Restore the least recently minimized windows to make room.
+    Restore,
+    /// Close the least recently minimized windows to make room.
+    Close,
+}
+
+/// Where restored windows go.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RestoreTarget {
+    /// The workspace and monitor the window was minimized from.
+    #[default]
+    Origin,
+    /// The active workspace.
+    Current,
+}
+
+/// How window previews are captured.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureMethod {
+    /// The window itself when grim can (grim-hyprland), else its screen area.
+    #[default]
+    Auto,
+    /// Only the window itself, through hyprland-toplevel-export (`grim -w`).
+    Toplevel,
+    /// The screen area under the window, overlapping windows included.
+    Geometry,
+}
+
+/// Menu used by `restore` without arguments.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Launcher {
+    #[default]
+    Rofi,
+    Bemenu,
+}
+
+/// Colors of the Rofi menus.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum RofiTheme {
+    #[default]
+    Nord,
+    Catppuccin,
+    Gruvbox,
+    Dracula,
+    /// pywal's `~/.cache/wal/colors.json`.
+    Wal,
+    /// matugen's `--json hex` output, saved as `~/.cache/matugen/colors.json`.
+    Matugen,
+}
+
+/// Arrangement of the Rofi restore menu entries.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum RofiLayout {
+    /// One column of titles with small thumbnails.
+    #[default]
+    List,
+    /// Big thumbnails in several columns, like a task switcher.
+    Grid,
+}
+
+/// Order of the Rofi restore menu entries.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MenuSort {
+    /// In the order they were minimized, oldest first.
+    #[default]
+    Minimized,
+    /// Most recently minimized first.
+    Recency,
+    /// By the workspace they were minimized from.
+    Workspace,
+    /// By class, alphabetically.
+    Class,
+}
+
+/// Sections of the Rofi restore menu, each under a header.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MenuGroup {
+    #[default]
+    None,
+    Workspace,
+    Class,
+}
+
+/// The `[menu]` table: how the Rofi restore menu lists windows.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct MenuConfig {
+    pub sort: MenuSort,
+    /// Groups follow each other by workspace number or class name, and are
+    /// sorted by `sort` inside.
+    pub group_by: MenuGroup,
+}
+
+/// Encoding of the preview thumbnails.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PreviewFormat {
+    #[default]
+    Png,
+    Jpeg,
+    Webp,
+}
+
+impl PreviewFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            PreviewFormat::Png => "png",
+            PreviewFormat::Jpeg => "jpg",
+            PreviewFormat::Webp => "webp",
+        }
+    }
+}
+
+/// The `[preview]` table: size, look and encoding of the thumbnails.
+#[derive(Clone, Hash, Deserialize)]
+#[serde(default)]
+pub struct PreviewConfig {
+    /// Thumbnail size in pixels; captures are scaled to fill it and center-cropped.
+    pub width: u32,
+    pub height: u32,
+    /// Side of the square icon, in pixels.
+    pub icon_size: u32,
+    /// `"png"`, `"jpeg"` or `"webp"` (lossless).
+    pub format: PreviewFormat,
+    /// JPEG quality, 1 to 100.
+    pub quality: u8,
+    /// Put the app icon in the bottom right corner of the thumbnail.
+    pub badge: bool,
+    /// Radius of the thumbnail's rounded corners in pixels; 0 keeps them square.
+    pub corner_radius: u32,
+    /// Width of the border drawn around the thumbnail in pixels; 0 draws none.
+    pub border_width: u32,
+    /// Border color, as `#rrggbb`.
+    pub border_color: String,
+    /// Have the daemon delete previews older than this many hours; 0 keeps
+    /// them while their window is minimized.
+    pub max_age_hours: u64,
+}
+
+impl Default for PreviewConfig {
+    fn default() -> Self {
+        PreviewConfig {
+            width: 200,
+            height: 150,
+            icon_size: 64,
+            format: PreviewFormat::Png,
+            quality: 80,
+            badge: true,
+            corner_radius: 0,
+            border_width: 0,
+            border_color: String::from("#4C566A"),
+            max_age_hours: 0,
+        }
+    }
+}
+
+/// The `[waybar]` table: the module's text and classes, and the signal that
+/// refreshes it.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct WaybarConfig {
+    /// Real-time signal sent to Waybar on changes (`SIGRTMIN+signal`), the
+    /// module's `signal`; 0 sends none (for `minhypr watch`).
+    pub signal: u8,
+    /// Module text, with `{count}`, `{classes}` and `{icons}`.
+    pub format: String,
+    /// Module text while nothing is minimized.
+    pub format_empty: String,
+    /// CSS class while windows are minimized (`urgent` is added next to it).
+    pub class: String,
+    /// CSS class while nothing is minimized.
+    pub class_when_empty: String,
+}
+
+impl Default for WaybarConfig {
+    fn default() -> Self {
+        WaybarConfig {
+            signal: 8,
+            format: String::from("󰘸 {count}"),
+            format_empty: String::from("󰘸"),
+            class: String::from("has-windows"),
+            class_when_empty: String::from("empty"),
+        }
+    }
+}
+
+/// A way of telling bars that the minimized set changed.
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RefreshMethod {
+    /// Send Waybar the `[waybar]` signal.
+    Signal,
+    /// Write the time to `file`, for inotify-based widgets.
+    File,
+    /// Have the daemon push an event to `minhypr events` subscribers.
+    Socket,
+    /// Run `command`.
+    Command,
+}
+
+/// The `[refresh]` table: how bars are told about changes.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct RefreshConfig {
+    pub methods: Vec<RefreshMethod>,
+    /// File written by the `file` method; defaults to `$XDG_RUNTIME_DIR/minhypr/changed`.
+    pub file: Option<String>,
+    /// Shell command run by the `command` method, e.g. `eww update minimized="$(minhypr eww)"`.
+    pub command: Option<String>,
+}
+
+impl Default for RefreshConfig {
+    fn default() -> Self {
+        RefreshConfig { methods: vec![RefreshMethod::Signal], file: None, command: None }
+    }
+}
+
+/// A `[rules.<name>]` entry: what to do with windows matching `class`
+/// and/or `title` (regexes; a rule without either matches nothing).
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Rule {
+    /// Class name or regex, matched against the whole class, ignoring case.
+    pub class: Option<String>,
+    /// Regex searched in the title, ignoring case.
+    pub title: Option<String>,
+    /// Never minimize these windows.
+    pub ignore: bool,
+    /// Don't take a screenshot when minimizing them.
+    pub never_preview: bool,
+    /// Minimize them as soon as they open (needs the daemon).
+    pub auto_minimize_on_launch: bool,
+    /// Store a placeholder instead of the title and never take a screenshot.
+    pub private: bool,
+    /// Replace the `title` matches with this where titles are shown (`$1` etc.
+    /// refer to capture groups), e.g. to strip " - Mozilla Firefox".
+    pub rewrite: Option<String>,
+    /// List them first in the restore menus, whatever the `[menu]` sort.
+    pub menu_top: bool,
+}
+
+impl Rule {
+    fn matches(&self, class_name: &str, title: &str) -> bool {
+        if self.class.is_none() && self.title.is_none() {
+            return false;
+        }
+
+        self.class.as_deref().is_none_or(|pattern| class_matches(pattern, class_name))
+            && self.title.as_deref().is_none_or(|pattern| {
+                Regex::new(&format!("(?i){}", pattern)).is_ok_and(|re| re.is_match(title))
+            })
+    }
+}
+
+/// A `[scratchpads.<name>]` entry: an app `minhypr scratch <name>` shows as
+/// a floating dropdown and minimizes again.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Scratchpad {
+    /// Launches the app when no window of `class` is open.
+    pub command: String,
+    /// Class of its window, ignoring case.
+    pub class: String,
+    /// Width and height, in pixels or percent of the monitor: "75% 60%".
+    pub size: String,
+    /// Top left corner on the monitor, in the same units; default: centered
+    /// at the top.
+    pub position: Option<String>,
+}
+
+impl Default for Scratchpad {
+    fn default() -> Self {
+        Scratchpad { command: String::new(), class: String::new(), size: String::from("75% 60%"), position: None }
+    }
+}
+
+// "W H", each in pixels ("800", "800px") or percent of `monitor` ("75%")
+fn parse_dimensions(spec: &str, monitor: (i32, i32)) -> Option<(i32, i32)> {
+    let parse = |part: &str, total: i32| match part.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().ok().map(|p| (total as f64 * p / 100.0).round() as i32),
+        None => part.trim_end_matches("px").parse::<i32>().ok(),
+    };
+    match spec.split_whitespace().collect::<Vec<_>>()[..] {
+        [width, height] => Some((parse(width, monitor.0)?, parse(height, monitor.1)?)),
+        _ => None,
+    }
+}
+
+impl Scratchpad {
+    /// Size and position (relative to the monitor) on a monitor of this
+    /// logical size; invalid values fall back to the defaults.
+    pub fn geometry(&self, monitor: (i32, i32)) -> ((i32, i32), (i32, i32)) {
+        let size = parse_dimensions(&self.size, monitor).unwrap_or_else(|| {
+            warn!("Invalid scratchpad size {:?}, using 75% 60%", self.size);
+            (monitor.0 * 3 / 4, monitor.1 * 3 / 5)
+        });
+        let centered = ((monitor.0 - size.0) / 2, monitor.1 / 20);
+        let position = match &self.position {
+            Some(position) => parse_dimensions(position, monitor).unwrap_or_else(|| {
+                warn!("Invalid scratchpad position {:?}, centering it", position);
+                centered
+            }),
+            None => centered,
+        };
+        (size, position)
+    }
+}
+
+/// User settings from `~/.config/minhypr/config.toml`.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Icon per window class (or class regex): a glyph, or a path to an image.
+    pub icons: BTreeMap<String, String>,
+    /// Class regex -> class name used for icon lookup (e.g. `'steam_app_\d+' = "steam"`).
+    pub aliases: BTreeMap<String, String>,
+    /// Default target of `restore` (`"origin"` or `"current"`).
+    pub restore_target: RestoreTarget,
+    /// Focus windows when restoring them; off restores in the background.
+    pub restore_focus: bool,
+    /// Where windows go when the monitor they were minimized from is gone.
+    pub missing_monitor_target: RestoreTarget,
+    /// Menu used by `restore` without arguments.
+    pub launcher: Launcher,
+    /// How previews are captured (`"auto"`, `"toplevel"` or `"geometry"`).
+    pub capture: CaptureMethod,
+    /// Size and encoding of the preview thumbnails.
+    pub preview: PreviewConfig,
+    /// Have the daemon capture the previews of minimized windows again every
+    /// this many minutes; 0 never does.
+    pub preview_refresh_minutes: u64,
+    /// Sorting and grouping of the Rofi restore menu.
+    pub menu: MenuConfig,
+    /// Colors of the Rofi menus; `wal` and `matugen` follow the wallpaper.
+    pub rofi_theme: RofiTheme,
+    /// Arrangement of the Rofi restore menu (`"list"` or `"grid"`).
+    pub rofi_layout: RofiLayout,
+    /// Minimize a window's whole Hyprland group (tabs) with it, as one entry;
+    /// off takes the window out of its group.
+    pub minimize_groups: bool,
+    /// Put windows taken out of their group back into it when restored.
+    pub rejoin_groups: bool,
+    /// Special workspace minimized windows go to (without the `special:` prefix).
+    pub special_workspace: String,
+    /// Minimize each class to its own special workspace instead of one shared one.
+    pub class_workspaces: bool,
+    /// Name of the per-class special workspaces; `{class}` is the window class.
+    pub class_workspace_template: String,
+    /// Per-window rules by name; the built-in `wofi` rule ignores wofi menus.
+    pub rules: BTreeMap<String, Rule>,
+    /// Dropdown apps toggled by `minhypr scratch <name>`.
+    pub scratchpads: BTreeMap<String, Scratchpad>,
+    /// Text, classes and refresh signal of the Waybar module.
+    pub waybar: WaybarConfig,
+    /// How bars are told that the minimized set changed.
+    pub refresh: RefreshConfig,
+    /// Title stored for private windows; `{class}` is the window class.
+    pub private_title: String,
+    /// Classes (or class regexes) minimized by `focus on`.
+    pub distractions: Vec<String>,
+    /// Classes (or class regexes) minimized while the session is idle (`idle timeout`).
+    pub idle_classes: Vec<String>,
+    /// Have the daemon minimize windows while the session is locked.
+    pub minimize_on_lock: bool,
+    /// Classes (or class regexes) minimized on lock; empty means every window.
+    pub lock_classes: Vec<String>,
+    /// Show a notification with a "Restore" action when a window is minimized.
+    pub notifications: bool,
+    /// Class (or class regex) -> notifications on or off, overriding `notifications`.
+    pub notify: BTreeMap<String, bool>,
+    /// Stop (SIGSTOP) the processes whose windows are all minimized.
+    pub suspend: bool,
+    /// Class (or class regex) -> suspension on or off, overriding `suspend`.
+    pub suspend_classes: BTreeMap<String, bool>,
+    /// Mute the audio streams of the processes whose windows are all minimized.
+    pub mute_audio: bool,
+    /// Class (or class regex) -> muting on or off, overriding `mute_audio`.
+    pub mute_audio_classes: BTreeMap<String, bool>,
+    /// Restore a minimized window when something focuses it (e.g. a link opened
+    /// in a minimized browser); defaults to on unless `class_workspaces` is set.
+    pub restore_on_activate: Option<bool>,
+    /// What to do when a minimized window becomes urgent (daemon).
+    pub on_urgent: UrgentAction,
+    /// Most windows kept minimized at once; 0 means no limit.
+    pub max_minimized: usize,
+    /// What to do when a minimize would go past `max_minimized`.
+    pub on_limit: LimitAction,
+    /// Shell command run after a window is minimized.
+    pub on_minimize: Option<String>,
+    /// Shell command run after a window is restored.
+    pub on_restore: Option<String>,
+    /// Append messages to `$XDG_STATE_HOME/minhypr/minhypr.log` (also `--log-file`).
+    pub log_file: bool,
+    /// Most verbose level written to the log file: error, warn, info, debug or trace.
+    pub log_level: Level,
+    /// Record minimizes and restores in `$XDG_STATE_HOME/minhypr/history.jsonl`
+    /// for `minhypr stats`.
+    pub history: bool,
+    /// How Rofi entries and `list` show the time since a window was minimized
+    /// (`{age}` is e.g. `12m`); empty to leave it out.
+    pub age_format: String,
+    /// How windows are named in menus, `list` and the waybar tooltip; see
+    /// `window::format_window` for the placeholders.
+    pub title_format: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            icons: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+            restore_target: RestoreTarget::Origin,
+            restore_focus: true,
+            missing_monitor_target: RestoreTarget::Current,
+            launcher: Launcher::Rofi,
+            capture: CaptureMethod::Auto,
+            preview: PreviewConfig::default(),
+            preview_refresh_minutes: 0,
+            menu: MenuConfig::default(),
+            rofi_theme: RofiTheme::Nord,
+            rofi_layout: RofiLayout::List,
+            minimize_groups: true,
+            rejoin_groups: true,
+            special_workspace: String::from("minimized"),
+            class_workspaces: false,
+            class_workspace_template: String::from("min-{class}"),
+            rules: BTreeMap::from([(
+                String::from("wofi"),
+                Rule { class: Some(String::from("wofi")), ignore: true, ..Default::default() },
+            )]),
+            scratchpads: BTreeMap::new(),
+            waybar: WaybarConfig::default(),
+            refresh: RefreshConfig::default(),
+            private_title: String::from("{class} — hidden"),
+            distractions: Vec::new(),
+            idle_classes: Vec::new(),
+            minimize_on_lock: false,
+            lock_classes: Vec::new(),
+            notifications: false,
+            notify: BTreeMap::new(),
+            suspend: false,
+            suspend_classes: BTreeMap::new(),
+            mute_audio: false,
+            mute_audio_classes: BTreeMap::new(),
+            on_minimize: None,
+            on_restore: None,
+            on_urgent: UrgentAction::Highlight,
+            max_minimized: 0,
+            on_limit: LimitAction::Refuse,
+            restore_on_activate: None,
+            log_file: false,
+            log_level: Level::Info,
+            history: true,
+            age_format: String::from("{age} ago"),
+            title_format: String::from(DEFAULT_TITLE_FORMAT),
+        }
+    }
+}
+
+pub fn config_dir() -> PathBuf {
+    match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir).join("minhypr"),
+        _ => PathBuf::from(env::var("HOME").unwrap_or_default()).join(".config/minhypr"),
+    }
+}
+
+pub fn config_file() -> PathBuf {
+    config_dir().join("config.toml")
+}
+
+// Whole-string, case-insensitive match of a class pattern
+fn class_matches(pattern: &str, class_name: &str) -> bool {
+    if pattern.eq_ignore_ascii_case(class_name) {
+        return true;
+    }
+
+    match Regex::new(&format!("(?i)^(?:{})$", pattern)) {
+        Ok(re) => re.is_match(class_name),
+        Err(_) => false,
+    }
+}
+
+// Per-class on/off override: exact names win over patterns
+fn class_switch(overrides: &BTreeMap<String, bool>, class_name: &str, default: bool) -> bool {
+    overrides
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(class_name))
+        .or_else(|| overrides.iter().find(|(pattern, _)| class_matches(pattern, class_name)))
+        .map_or(default, |(_, enabled)| *enabled)
+}
+
+impl Config {
+    /// Load the config file; a missing file gives the defaults, a broken one
+    /// is reported and ignored.
+    pub fn load() -> Self {
+        let path = config_file();
+        let Ok(content) = fs::read_to_string(&path) else {
+            return Config::default();
+        };
+
+        match parse_toml(&content).and_then(|value| serde_json::from_value::<Config>(value).map_err(|e| e.to_string())) {
+            Ok(mut config) => {
+                // User rules add to the defaults; a rule with the same name replaces one
+                for (name, rule) in Config::default().rules {
+                    config.rules.entry(name).or_insert(rule);
+                }
+                config
+            }
+            Err(e) => {
+                warn!("Ignoring invalid config {}: {}", path.display(), e);
+                Config::default()
+            }
+        }
+    }
+
+    /// Class name to use for icon lookup, after applying `[aliases]`.
+    pub fn resolve_class<'a>(&'a self, class_name: &'a str) -> &'a str {
+        self.aliases
+            .iter()
+            .find(|(pattern, _)| class_matches(pattern, class_name))
+            .map_or(class_name, |(_, class)| class.as_str())
+    }
+
+    /// Configured icon for a class: exact names win over patterns.
+    pub fn icon_for(&self, class_name: &str) -> Option<&str> {
+        self.icons
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(class_name))
+            .or_else(|| self.icons.iter().find(|(pattern, _)| class_matches(pattern, class_name)))
+            .map(|(_, icon)| icon.as_str())
+    }
+
+    /// Special workspace windows of this class are minimized to.
+    pub fn minimized_workspace_for(&self, class_name: &str) -> String {
+        if !self.class_workspaces {
+            return format!("special:{}", self.special_workspace);
+        }
+
+        // Workspace names can't hold every character a class can
+        let class_name: String = class_name
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '.' || c == '_' { c.to_ascii_lowercase() } else { '-' })
+            .collect();
+        format!("special:{}", self.class_workspace_template.replace("{class}", &class_name))
+    }
+
+    /// Whether a workspace is one minimized windows are moved to. Per-class
+    /// workspaces are recognised even with `class_workspaces` off, so windows
+    /// minimized before the setting changed are still found.
+    pub fn is_minimized_workspace(&self, name: &str) -> bool {
+        let Some(name) = name.strip_prefix("special:") else {
+            return false;
+        };
+        if name == self.special_workspace {
+            return true;
+        }
+
+        match self.class_workspace_template.split_once("{class}") {
+            Some((prefix, suffix)) => {
+                name.len() > prefix.len() + suffix.len() && name.starts_with(prefix) && name.ends_with(suffix)
+            }
+            None => name == self.class_workspace_template,
+        }
+    }
+
+    // Whether any rule matching the window sets the flag
+    fn rule_flag(&self, class_name: &str, title: &str, flag: impl Fn(&Rule) -> bool) -> bool {
+        self.rules.values().any(|rule| flag(rule) && rule.matches(class_name, title))
+    }
+
+    /// Whether rules forbid minimizing this window.
+    pub fn ignores(&self, class_name: &str, title: &str) -> bool {
+        self.rule_flag(class_name, title, |rule| rule.ignore)
+    }
+
+    /// Whether rules forbid screenshots of this window.
+    pub fn never_preview(&self, class_name: &str, title: &str) -> bool {
+        self.rule_flag(class_name, title, |rule| rule.never_preview || rule.private)
+    }
+
+    /// Whether rules pin this window to the top of the restore menu.
+    pub fn menu_top(&self, class_name: &str, title: &str) -> bool {
+        self.rule_flag(class_name, title, |rule| rule.menu_top)
+    }
+
+    /// Title as shown, after the rules' rewrites (in rule name order).
+    pub fn rewrite_title(&self, class_name: &str, title: &str) -> String {
+        let mut title = title.to_string();
+        for rule in self.rules.values().filter(|rule| rule.rewrite.is_some()) {
+            let (Some(pattern), Some(rewrite)) = (&rule.title, &rule.rewrite) else {
+                continue;
+            };
+            if !rule.matches(class_name, &title) {
+                continue;
+            }
+            if let Ok(re) = Regex::new(&format!("(?i){}", pattern)) {
+                title = re.replace_all(&title, rewrite.as_str()).into_owned();
+            }
+        }
+        title
+    }
+
+    /// `window::format_window` with the title rewritten by the rules.
+    pub fn format_window(&self, template: &str, window: &MinimizedWindow) -> String {
+        if !self.rules.values().any(|rule| rule.rewrite.is_some()) {
+            return format_window(template, window);
+        }
+
+        let mut window = window.clone();
+        window.original_title = self.rewrite_title(&window.class, &window.original_title);
+        format_window(template, &window)
+    }
+
+    /// A window as named by `title_format`.
+    pub fn display_title(&self, window: &MinimizedWindow) -> String {
+        self.format_window(&self.title_format, window)
+    }
+
+    /// Template of a menu or tooltip line: `title_format`, then `age_format`.
+    pub fn entry_format(&self) -> String {
+        if self.age_format.is_empty() {
+            self.title_format.clone()
+        } else {
+            format!("{}  ·  {}", self.title_format, self.age_format)
+        }
+    }
+
+    /// A menu line for a window: `entry_format` rendered on one line.
+    pub fn menu_entry(&self, window: &MinimizedWindow) -> String {
+        self.format_window(&self.entry_format(), window).replace('\n', " ")
+    }
+
+    /// Title to store for a window: the placeholder for private windows.
+    pub fn stored_title(&self, class_name: &str, title: &str) -> String {
+        if self.rule_flag(class_name, title, |rule| rule.private) {
+            self.private_title.replace("{class}", class_name)
+        } else {
+            title.to_string()
+        }
+    }
+
+    /// Whether rules minimize this window when it opens.
+    pub fn auto_minimizes(&self, class_name: &str, title: &str) -> bool {
+        self.rule_flag(class_name, title, |rule| rule.auto_minimize_on_launch)
+    }
+
+    /// Whether focus mode hides windows of this class.
+    pub fn is_distraction(&self, class_name: &str) -> bool {
+        self.distractions.iter().any(|pattern| class_matches(pattern, class_name))
+    }
+
+    /// Whether focusing a minimized window restores it. Per-class workspaces
+    /// are meant to be opened with togglespecialworkspace, which focuses the
+    /// windows inside, so it is off for them unless asked for.
+    pub fn restores_on_activate(&self) -> bool {
+        self.restore_on_activate.unwrap_or(!self.class_workspaces)
+    }
+
+    /// Whether a class is hidden while the session is locked.
+    pub fn hides_on_lock(&self, class_name: &str) -> bool {
+        self.lock_classes.is_empty() || self.lock_classes.iter().any(|pattern| class_matches(pattern, class_name))
+    }
+
+    /// Whether a class is hidden while the session is idle.
+    pub fn is_idle_class(&self, class_name: &str) -> bool {
+        self.idle_classes.iter().any(|pattern| class_matches(pattern, class_name))
+    }
+
+    /// Whether minimizing a window of this class shows a notification.
+    pub fn notifications_for(&self, class_name: &str) -> bool {
+        class_switch(&self.notify, class_name, self.notifications)
+    }
+
+    /// Whether the process of a minimized window of this class may be stopped.
+    pub fn suspend_for(&self, class_name: &str) -> bool {
+        class_switch(&self.suspend_classes, class_name, self.suspend)
+    }
+
+    /// Whether the audio of a minimized window of this class may be muted.
+    pub fn mute_audio_for(&self, class_name: &str) -> bool {
+        class_switch(&self.mute_audio_classes, class_name, self.mute_audio)
+    }
+}
+
+// Minimal TOML reader for the config file: `[table]` headers, `key = value`
+// with strings, integers, floats, booleans and arrays. Tables become nested
+// JSON objects so the config can be deserialized with serde.
+pub fn parse_toml(content: &str) -> Result<Value, String> {
+    let mut root = Map::new();
+    let mut table: Vec<String> = Vec::new();
+    let mut lines = content.lines().enumerate();
+
+    while let Some((index, raw_line)) = lines.next() {
+        let line_number = index + 1;
+        let line = strip_comment(raw_line).trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with("[[") {
+            return Err(format!("line {}: arrays of tables ([[...]]) are not supported", line_number));
+        }
+        if let Some(header) = line.strip_prefix('[') {
+            let header = header
+                .strip_suffix(']')
+                .ok_or_else(|| format!("line {}: unterminated table header", line_number))?;
+            let parts = split_unquoted(header, '.');
+            if parts.iter().any(|part| part.trim().is_empty()) {
+                return Err(format!("line {}: empty table name in [{}]", line_number, header));
+            }
+            table = parts.iter().map(|part| unquote_key(part.trim())).collect();
+            continue;
+        }
+
+        let (key, value) = match split_unquoted(line, '=').as_slice() {
+            [key, ..] if key.len() < line.len() => (*key, &line[key.len() + 1..]),
+            _ => return Err(format!("line {}: expected key = value", line_number)),
+        };
+        let key = unquote_key(key.trim());
+        if key.is_empty() {
+            return Err(format!("line {}: missing key", line_number));
+        }
+
+        // Arrays may span several lines
+        let mut value = value.trim().to_string();
+        while value.starts_with('[') && open_brackets(&value) > 0 {
+            let (_, next) = lines.next().ok_or_else(|| format!("line {}: unterminated array", line_number))?;
+            value.push(' ');
+            value.push_str(strip_comment(next).trim());
+        }
+        let value = parse_value(&value).map_err(|e| format!("line {}: {}", line_number, e))?;
+
+        let mut target = &mut root;
+        for name in &table {
+            let entry = target.entry(name.clone()).or_insert_with(|| Value::Object(Map::new()));
+            target = entry
+                .as_object_mut()
+                .ok_or_else(|| format!("line {}: {} is not a table", line_number, name))?;
+        }
+        target.insert(key, value);
+    }
+
+    Ok(Value::Object(root))
+}
+
+// Drop a trailing comment, ignoring '#' inside strings
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' => return &line[..i],
+            None => {}
+        }
+    }
+
+    line
+}
+
+// Brackets opened and not yet closed, ignoring those inside strings
+fn open_brackets(text: &str) -> i32 {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+
+    for c in text.chars() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '[' => depth += 1,
+            None if c == ']' => depth -= 1,
+            None => {}
+        }
+    }
+
+    depth
+}
+
+fn unquote_key(key: &str) -> String {
+    parse_string(key).unwrap_or_else(|| key.to_string())
+}
+
+// Basic ("...", with escapes) or literal ('...') string
+fn parse_string(value: &str) -> Option<String> {
+    if let Some(inner) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        return Some(inner.to_string());
+    }
+
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+    let mut result = String::new();
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => result.push('\n'),
+            't' => result.push('\t'),
+            '"' => result.push('"'),
+            '\\' => result.push('\\'),
+            other => {
+                // Keep unknown escapes (e.g. regex "\d") as written
+                result.push('\\');
+                result.push(other);
+            }
+        }
+    }
+
+    Some(result)
+}
+
+fn parse_value(value: &str) -> Result<Value, String> {
+    if let Some(string) = parse_string(value) {
+        return Ok(Value::String(string));
+    }
+
+    if let Some(items) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        return split_array(items)
+            .iter()
+            .map(|item| parse_value(item.trim()))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array);
+    }
+
+    match value {
+        "true" => return Ok(Value::Bool(true)),
+        "false" => return Ok(Value::Bool(false)),
+        _ => {}
+    }
+
+    let number = value.replace('_', "");
+    if let Ok(int) = number.parse::<i64>() {
+        return Ok(Value::from(int));
+    }
+    if let Ok(float) = number.parse::<f64>() {
+        return Ok(Value::from(float));
+    }
+
+    Err(format!("invalid value: {}", value))
+}
+
+// Split array items on commas outside of strings, allowing a trailing comma
+fn split_array(items: &str) -> Vec<&str> {
+    let mut parts = split_unquoted(items, ',');
+    parts.retain(|part| !part.trim().is_empty());
+    parts
+}
+
+// Split on a separator outside of strings
+fn split_unquoted(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quote = None;
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == separator => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            None => {}
+        }
+    }
+
+    parts.push(&text[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::window::unix_timestamp_ms;
+
+    fn window() -> MinimizedWindow {
+        serde_json::from_value(json!({
+            "address": "0x55d1c0ffee",
+            "display_title": "",
+            "class": "firefox",
+            "original_title": "Rust docs - Mozilla Firefox",
+            "preview_path": null,
+            "icon": "",
+            "workspace": 3,
+            "monitor": "DP-1",
+            "minimized_at": unix_timestamp_ms() - 125_000,
+        }))
+        .unwrap()
+    }
+
+    fn rewrite(title: &str, rewrite: &str) -> Rule {
+        Rule { title: Some(title.to_string()), rewrite: Some(rewrite.to_string()), ..Default::default() }
+    }
+
+    #[test]
+    fn formats_every_placeholder() {
+        let config = Config::default();
+        let format = |template: &str| config.format_window(template, &window());
+
+        assert_eq!(format("{icon}"), "");
+        assert_eq!(format("{class}"), "firefox");
+        assert_eq!(format("{title}"), "Rust docs - Mozilla Firefox");
+        assert_eq!(format("{workspace}"), "3");
+        assert_eq!(format("{monitor}"), "DP-1");
+        assert_eq!(format("{address}"), "0x55d1c0ffee");
+        assert_eq!(format("{short_address}"), "ffee");
+        assert_eq!(format("{age}"), "2m");
+        assert_eq!(format("{title:10}"), "Rust docs…");
+        assert_eq!(format("{class} {unknown} {title:x}"), "firefox {unknown} {title:x}");
+    }
+
+    #[test]
+    fn menu_entries_stay_on_one_line() {
+        let config = Config { title_format: String::from("{class}\n{title}"), ..Default::default() };
+        let mut window = window();
+        window.original_title = String::from("two\nlines");
+
+        assert_eq!(config.menu_entry(&window), "firefox two lines  ·  2m ago");
+    }
+
+    #[test]
+    fn rewrites_apply_in_rule_name_order() {
+        let mut config = Config::default();
+        // Each rule matches and rewrites the title as left by the ones before
+        config.rules.insert(String::from("c-brand"), rewrite(" - Mozilla Firefox$", ""));
+        config.rules.insert(String::from("b-short"), rewrite("^Rust reference", "Rust ref"));
+        config.rules.insert(String::from("a-docs"), rewrite("^(\\w+) docs", "$1 reference"));
+
+        assert_eq!(config.format_window("{title}", &window()), "Rust ref");
+        assert_eq!(config.format_window("{class}", &window()), "firefox");
+    }
+
+    #[test]
+    fn parses_the_supported_grammar() {
+        let content = r#"
+# A comment
+minimize_on_lock = true   # trailing comment
+max_minimized = 1_000
+opacity = 0.85
+title_format = "{title} # not a comment"
+literal = 'C:\path'
+escaped = "tab\there \"quoted\" \d"
+
+[preview]
+max_age_hours = 24
+
+[menu.bemenu]
+args = [
+    "-i",   # ignore case
+    '-l 10',
+]
+
+[rules."org.Kitty.desktop"]
+"key with = sign" = []
+"#;
+        let value = parse_toml(content).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "minimize_on_lock": true,
+                "max_minimized": 1000,
+                "opacity": 0.85,
+                "title_format": "{title} # not a comment",
+                "literal": "C:\\path",
+                "escaped": "tab\there \"quoted\" \\d",
+                "preview": {"max_age_hours": 24},
+                "menu": {"bemenu": {"args": ["-i", "-l 10"]}},
+                "rules": {"org.Kitty.desktop": {"key with = sign": []}},
+            })
+        );
+    }
+
+    #[test]
+    fn quoted_header_parts_keep_their_dots() {
+        let value = parse_toml("[a.'b.c'.\"d\"]\nx = 1").unwrap();
+        assert_eq!(value, json!({"a": {"b.c": {"d": {"x": 1}}}}));
+    }
+
+    #[test]
+    fn reports_errors_with_their_line() {
+        let error = |content: &str| parse_toml(content).unwrap_err();
+
+        assert_eq!(error("a = 1\n[[rules]]\nclass = \"kitty\""), "line 2: arrays of tables ([[...]]) are not supported");
+        assert_eq!(error("[preview"), "line 1: unterminated table header");
+        assert_eq!(error("[a..b]"), "line 1: empty table name in [a..b]");
+        assert_eq!(error("\nnot a pair"), "line 2: expected key = value");
+        assert_eq!(error("= 1"), "line 1: missing key");
+        assert_eq!(error("a = nope"), "line 1: invalid value: nope");
+        assert_eq!(error("a = [1,\n2,"), "line 1: unterminated array");
+        assert_eq!(error("a = 1\n[a]\nb = 2"), "line 3: a is not a table");
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    env, fs,
+    io::{BufRead, BufReader, Error, ErrorKind, Result, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::PathBuf,
+    sync::{Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+
+use serde_json::{json, Value};
+
+use crate::{compositor::Compositor, manager::Manager, rpc, state::state_root, window::most_recent_window};
+
+/// Control socket of the daemon: `$XDG_RUNTIME_DIR/minhypr.sock`.
+pub fn socket_path() -> PathBuf {
+    match env::var("XDG_RUNTIME_DIR") {
+        Ok(dir) => PathBuf::from(dir).join("minhypr.sock"),
+        Err(_) => PathBuf::from(state_root()).join("minhypr.sock"),
+    }
+}
+
+// Bumped by the `refresh` command, waking the subscribers
+static REFRESHES: (Mutex<u64>, Condvar) = (Mutex::new(0), Condvar::new());
+
+fn notify_subscribers() {
+    let (count, changed) = &REFRESHES;
+    if let Ok(mut count) = count.lock() {
+        *count += 1;
+        changed.notify_all();
+    }
+}
+
+// Wait up to `timeout` for a refresh past `seen`; returns the current count
+fn wait_for_refresh(seen: u64, timeout: Duration) -> u64 {
+    let (count, changed) = &REFRESHES;
+    let Ok(count) = count.lock() else {
+        thread::sleep(timeout);
+        return seen;
+    };
+    match changed.wait_timeout_while(count, timeout, |count| *count == seen) {
+        Ok((count, _)) => *count,
+        Err(_) => seen,
+    }
+}
+
+fn address_arg(request: &Value) -> Option<&str> {
+    request["address"].as_str().filter(|address| !address.is_empty())
+}
+
+// Run one request; the result goes in the "result" field of the response
+pub(crate) fn handle_request<C: Compositor>(manager: &Manager<C>, request: &Value) -> Result<Value> {
+    let command = request["command"].as_str().unwrap_or_default();
+
+    match command {
+        "ping" => Ok(json!("pong")),
+        "list" => Ok(serde_json::to_value(manager.windows()?)?),
+        "minimize" => match address_arg(request) {
+            Some(address) => Ok(json!(manager.minimize_address(address)?)),
+            None => manager.minimize_active().map(|_| Value::Null),
+        },
+        "minimize_all" => Ok(json!(manager.minimize_workspace(false)?)),
+        "minimize_others" => Ok(json!(manager.minimize_workspace(true)?)),
+        "show_desktop" => Ok(json!(manager.show_desktop()?)),
+        "toggle" => manager.toggle(address_arg(request)).map(|_| Value::Null),
+        "restore" => {
+            let address = address_arg(request).ok_or_else(|| Error::new(ErrorKind::InvalidInput, "restore needs an address"))?;
+            manager.restore(address).map(|_| Value::Null)
+        }
+        "restore_last" => {
+            let windows = manager.windows()?;
+            match most_recent_window(&windows) {
+                Some(window) => {
+                    manager.restore(&window.address)?;
+                    Ok(json!(window.address))
+                }
+                None => Ok(Value::Null),
+            }
+        }
+        "close" => {
+            let address = address_arg(request).ok_or_else(|| Error::new(ErrorKind::InvalidInput, "close needs an address"))?;
+            manager.close_minimized(address).map(|_| Value::Null)
+        }
+        "restore_all" => manager.restore_all().map(|_| Value::Null),
+        "cycle" => Ok(json!(manager.cycle()?)),
+        "undo" => Ok(json!(manager.undo_restore()?)),
+        "refresh" => {
+            notify_subscribers();
+            Ok(Value::Null)
+        }
+        _ => Err(Error::new(ErrorKind::InvalidInput, format!("unknown command: {}", command))),
+    }
+}
+
+// Push a line whenever the minimized set changes, until the client goes
+// away; JSON-RPC subscribers get notifications
+fn stream_events(manager: &Manager, stream: &mut UnixStream, rpc: bool) -> Result<()> {
+    let mut last_modified = None;
+    let mut refreshes = wait_for_refresh(0, Duration::ZERO);
+
+    loop {
+        // A `refresh` request pushes the event right away, the cache file is polled
+        let refreshed = wait_for_refresh(refreshes, Duration::from_millis(250));
+        let modified = fs::metadata(manager.store.cache_file()).and_then(|m| m.modified()).ok();
+        if refreshed != refreshes || last_modified.is_some_and(|last| last != modified) {
+            let windows = manager.windows()?;
+            let event = if rpc {
+                rpc::notification("windows_changed", json!({"count": windows.len(), "windows": windows}))
+            } else {
+                json!({"event": "windows_changed", "count": windows.len(), "windows": windows})
+            };
+            writeln!(stream, "{}", event)?;
+        }
+        refreshes = refreshed;
+        last_modified = Some(modified);
+    }
+}
+
+fn handle_client(stream: UnixStream) -> Result<()> {
+    let mut writer = stream.try_clone()?;
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // A fresh manager per request picks up config changes and the
+        // daemon's switches to a new Hyprland instance
+        let manager = Manager::new();
+
+        let request: Value = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            // JSON-RPC clients get a JSON-RPC error
+            Err(e) if line.contains("\"jsonrpc\"") => {
+                writeln!(writer, "{}", rpc::error_response(Value::Null, rpc::PARSE_ERROR, &e.to_string(), None))?;
+                continue;
+            }
+            Err(e) => {
+                writeln!(writer, "{}", json!({"ok": false, "error": format!("invalid request: {}", e)}))?;
+                continue;
+            }
+        };
+
+        if rpc::is_rpc(&request) {
+            match rpc::handle(&manager, &request) {
+                rpc::Reply::Subscribe(ack) => {
+                    if let Some(ack) = ack {
+                        writeln!(writer, "{}", ack)?;
+                    }
+                    return stream_events(&manager, &mut writer, true);
+                }
+                rpc::Reply::Response(Some(response)) => writeln!(writer, "{}", response)?,
+                rpc::Reply::Response(None) => {}
+            }
+            continue;
+        }
+
+        if request["command"] == "subscribe" {
+            writeln!(writer, "{}", json!({"ok": true, "result": null}))?;
+            return stream_events(&manager, &mut writer, false);
+        }
+
+        let response = match handle_request(&manager, &request) {
+            Ok(result) => json!({"ok": true, "result": result}),
+            Err(e) => json!({"ok": false, "error": e.to_string(), "code": crate::error::exit_code(&e)}),
+        };
+        writeln!(writer, "{}", response)?;
+    }
+
+    Ok(())
+}
+
+/// Serve newline-delimited JSON requests on the control socket, one thread per client.
+pub fn run_server() -> Result<()> {
+    let path = socket_path();
+
+    if path.exists() {
+        // A live socket belongs to another daemon, a dead one is left over
+        if UnixStream::connect(&path).is_ok() {
+            return Err(Error::new(ErrorKind::AddrInUse, format!("{} is in use by another daemon", path.display())));
+        }
+        fs::remove_file(&path)?;
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    info!("Listening on {}", path.display());
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        thread::spawn(move || {
+            // Clients hanging up is expected
+            handle_client(stream).ok();
+        });
+    }
+
+    Ok(())
+}
+
+/// Send one request to the daemon and return its response line.
+pub fn send_request(request: &Value) -> Result<Value> {
+    let mut stream = UnixStream::connect(socket_path()).map_err(|_| crate::Error::DaemonUnavailable(socket_path()))?;
+    writeln!(stream, "{}", request)?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    serde_json::from_str(&line).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Subscribe to the daemon's events, printing each one as a JSON line.
+pub fn print_events() -> Result<()> {
+    let mut stream = UnixStream::connect(socket_path()).map_err(|_| crate::Error::DaemonUnavailable(socket_path()))?;
+    writeln!(stream, "{}", json!({"command": "subscribe"}))?;
+
+    let reader = BufReader::new(stream);
+    // The first line acknowledges the subscription
+    for line in reader.lines().skip(1) {
+        println!("{}", line?);
+    }
+
+    Ok(())
+}
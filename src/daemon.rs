@@ -0,0 +1,236 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader, Result},
+    os::unix::net::UnixStream,
+    path::Path,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    config::{config_file, Config},
+    hyprland::{find_event_socket, instance_signature, set_instance_signature},
+    lock,
+    manager::Manager,
+    notify,
+    state::StateStore,
+    window::unix_timestamp_ms,
+};
+
+// How often the snooze watcher looks for new snoozes and config changes
+const SNOOZE_POLL: Duration = Duration::from_secs(1);
+
+fn handle_event(manager: &Manager, event: &str) -> Result<()> {
+    let (name, data) = event.split_once(">>").unwrap_or((event, ""));
+
+    match name {
+        // openwindow>>ADDRESS,WORKSPACE,CLASS,TITLE (the title may hold commas)
+        "openwindow" => {
+            let mut fields = data.splitn(4, ',');
+            let (Some(address), _, Some(class), Some(title)) = (fields.next(), fields.next(), fields.next(), fields.next()) else {
+                return Ok(());
+            };
+            if manager.config.auto_minimizes(class, title) {
+                // The window may be gone already; that mustn't stop the daemon
+                if let Err(e) = manager.minimize_address(&format!("0x{}", address)) {
+                    warn!("Could not auto-minimize {}: {}", class, e);
+                }
+            }
+        }
+        // activewindowv2>>ADDRESS
+        "activewindowv2" if !data.is_empty() => {
+            if let Err(e) = manager.activated(&format!("0x{}", data)) {
+                warn!("Could not restore activated window: {}", e);
+            }
+        }
+        // urgent>>ADDRESS
+        "urgent" => {
+            if let Err(e) = manager.mark_urgent(&format!("0x{}", data)) {
+                warn!("Could not mark window urgent: {}", e);
+            }
+        }
+        // Workspaces move along when outputs come and go
+        "monitoradded" | "monitoraddedv2" | "monitorremoved" | "monitorremovedv2" => {
+            if let Err(e) = manager.remap_monitors() {
+                warn!("Could not remap monitors: {}", e);
+            }
+        }
+        // A minimized window was closed or moved out of its minimized workspace;
+        // reading the cache validates it and refreshes waybar if needed
+        "closewindow" | "movewindow" | "movewindowv2" => {
+            if let Err(e) = manager.windows() {
+                warn!("Could not validate the minimized windows: {}", e);
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn modified(path: impl AsRef<Path>) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// Earliest snooze end in the cache, Unix time in milliseconds
+fn next_snooze_end(manager: &Manager) -> Option<u64> {
+    manager.store.load().ok()?.iter().filter_map(|w| w.restore_at).min()
+}
+
+// Restore snoozed windows once their timer runs out. Between deadlines only
+// the cache file and config are checked; Hyprland is asked when one is due.
+fn watch_snoozes() {
+    let mut signature = instance_signature().unwrap_or_default();
+    let mut manager = Manager::new();
+    let mut config_modified = modified(config_file());
+    // None until the first check, so the deadlines are read right away
+    let mut cache_modified: Option<Option<SystemTime>> = None;
+    let mut next_end = None;
+
+    loop {
+        // The daemon switched to a new Hyprland instance
+        let current = instance_signature().unwrap_or_default();
+        if current != signature {
+            signature = current;
+            manager = Manager::new();
+            cache_modified = None;
+        }
+        let config_now = modified(config_file());
+        if config_now != config_modified {
+            config_modified = config_now;
+            manager.config = Config::load();
+        }
+
+        // A snooze was added or removed
+        let cache_now = modified(manager.store.cache_file());
+        if cache_modified != Some(cache_now) {
+            cache_modified = Some(cache_now);
+            next_end = next_snooze_end(&manager);
+        }
+
+        if next_end.is_some_and(|end| end <= unix_timestamp_ms()) {
+            match manager.restore_due() {
+                Ok(restored) => {
+                    for window in restored.iter().filter(|w| manager.config.notifications_for(&w.class)) {
+                        notify::notify_snooze_over(window);
+                    }
+                }
+                Err(e) => debug!("Could not restore the snoozed windows: {}", e),
+            }
+            next_end = next_snooze_end(&manager);
+        }
+
+        // Until the next snooze ends, but new ones may come in meanwhile; a
+        // failed restore is retried after SNOOZE_POLL
+        let until_end = next_end.map(|end| Duration::from_millis(end.saturating_sub(unix_timestamp_ms())));
+        thread::sleep(until_end.filter(|d| !d.is_zero()).map_or(SNOOZE_POLL, |d| d.min(SNOOZE_POLL)));
+    }
+}
+
+/// Watch Hyprland events forever, keeping the state in sync and serving the
+/// control socket. With `dbus`, the `org.minhypr.Manager` service is also
+/// served on the session bus.
+pub fn run_daemon(dbus: bool) -> Result<()> {
+    thread::spawn(|| {
+        if let Err(e) = crate::control::run_server() {
+            error!("Control socket stopped: {}", e);
+        }
+    });
+
+    if dbus {
+        thread::spawn(|| {
+            if let Err(e) = crate::dbus::run_service() {
+                error!("D-Bus service stopped: {}", e);
+            }
+        });
+    }
+
+    // Snoozed windows come back on their own
+    thread::spawn(watch_snoozes);
+
+    if Manager::new().config.minimize_on_lock {
+        thread::spawn(lock::watch_lock);
+    }
+
+    // Previews left behind by crashes or killed preview jobs go away on
+    // startup and every hour
+    thread::spawn(|| loop {
+        let manager = Manager::new();
+        let max_age = Some(manager.config.preview.max_age_hours).filter(|hours| *hours > 0).map(|hours| Duration::from_secs(hours * 3600));
+        match manager.collect_previews(max_age) {
+            Ok(0) => {}
+            Ok(removed) => info!("Removed {} stale preview files", removed),
+            Err(e) => debug!("Could not clean up the previews: {}", e),
+        }
+
+        thread::sleep(Duration::from_secs(3600));
+    });
+
+    // Long-minimized windows get fresh thumbnails
+    let refresh_minutes = Manager::new().config.preview_refresh_minutes;
+    if refresh_minutes > 0 {
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_secs(refresh_minutes * 60));
+
+            if let Err(e) = Manager::new().refresh_previews(None) {
+                debug!("Could not refresh the previews: {}", e);
+            }
+        });
+    }
+
+    let mut current_signature = instance_signature().unwrap_or_default();
+    let mut manager = Manager::new();
+
+    loop {
+        let Some((signature, socket)) = find_event_socket() else {
+            thread::sleep(Duration::from_secs(1));
+            continue;
+        };
+
+        let stream = match UnixStream::connect(&socket) {
+            Ok(stream) => stream,
+            Err(_) => {
+                thread::sleep(Duration::from_secs(1));
+                continue;
+            }
+        };
+
+        // Hyprland restarted: point every thread at the new instance and carry
+        // the state over from the previous instance's store
+        if signature != current_signature {
+            info!("Connected to Hyprland instance: {}", signature);
+            set_instance_signature(&signature);
+
+            let store = StateStore::for_instance(&signature);
+            if let Err(e) = store.init().and_then(|_| store.migrate_from(&manager.store)) {
+                warn!("Could not carry the state over to the new instance: {}", e);
+            }
+
+            manager = Manager {
+                store,
+                compositor: manager.compositor,
+                config: manager.config,
+                effects: manager.effects,
+            };
+            current_signature = signature;
+        }
+
+        // Re-adopt windows every time we (re)connect
+        if let Err(e) = manager.resync() {
+            warn!("Could not resync the minimized windows: {}", e);
+        }
+        if let Err(e) = manager.remap_monitors() {
+            warn!("Could not remap monitors: {}", e);
+        }
+
+        for line in BufReader::new(stream).lines() {
+            match line {
+                Ok(event) => handle_event(&manager, &event)?,
+                Err(_) => break,
+            }
+        }
+
+        // Socket closed: Hyprland exited, wait for it to come back
+        thread::sleep(Duration::from_secs(1));
+    }
+}
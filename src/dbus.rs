@@ -0,0 +1,789 @@
+use std::{
+    collections::VecDeque,
+    env, fs,
+    io::{Error, ErrorKind, Read, Result, Write},
+    os::unix::{fs::MetadataExt, net::UnixStream},
+    time::{Duration, SystemTime},
+};
+
+use crate::{hyprland::instance_signature, manager::Manager, window::MinimizedWindow};
+
+/// Well-known bus name, object path and interface of the service.
+pub const BUS_NAME: &str = "org.minhypr.Manager";
+pub const OBJECT_PATH: &str = "/org/minhypr/Manager";
+pub const INTERFACE: &str = "org.minhypr.Manager";
+
+const INTROSPECTION: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node>
+  <interface name="org.minhypr.Manager">
+    <method name="Minimize">
+      <arg name="address" type="s" direction="in"/>
+    </method>
+    <method name="Restore">
+      <arg name="address" type="s" direction="in"/>
+    </method>
+    <method name="RestoreAll"/>
+    <method name="Close">
+      <arg name="address" type="s" direction="in"/>
+    </method>
+    <method name="List">
+      <arg name="windows" type="a(sssit)" direction="out"/>
+    </method>
+    <signal name="WindowsChanged">
+      <arg name="count" type="u"/>
+    </signal>
+  </interface>
+  <interface name="org.freedesktop.DBus.Introspectable">
+    <method name="Introspect">
+      <arg name="data" type="s" direction="out"/>
+    </method>
+  </interface>
+  <interface name="org.freedesktop.DBus.Peer">
+    <method name="Ping"/>
+  </interface>
+</node>
+"#;
+
+// Message types
+const METHOD_CALL: u8 = 1;
+const METHOD_RETURN: u8 = 2;
+const ERROR: u8 = 3;
+const SIGNAL: u8 = 4;
+
+const NO_REPLY_EXPECTED: u8 = 0x1;
+
+// Header field codes
+const FIELD_PATH: u8 = 1;
+const FIELD_INTERFACE: u8 = 2;
+const FIELD_MEMBER: u8 = 3;
+const FIELD_ERROR_NAME: u8 = 4;
+const FIELD_REPLY_SERIAL: u8 = 5;
+const FIELD_DESTINATION: u8 = 6;
+const FIELD_SENDER: u8 = 7;
+const FIELD_SIGNATURE: u8 = 8;
+
+enum Field<'a> {
+    Path(&'a str),
+    Str(&'a str),
+    Signature(&'a str),
+    U32(u32),
+}
+
+// Little-endian marshalling of the few types the service needs
+#[derive(Default)]
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn pad(&mut self, align: usize) {
+        while !self.buf.len().is_multiple_of(align) {
+            self.buf.push(0);
+        }
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.pad(4);
+        self.buf.extend(value.to_le_bytes());
+    }
+
+    fn i32(&mut self, value: i32) {
+        self.pad(4);
+        self.buf.extend(value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.pad(8);
+        self.buf.extend(value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.buf.extend(value.as_bytes());
+        self.buf.push(0);
+    }
+
+    fn signature(&mut self, value: &str) {
+        self.u8(value.len() as u8);
+        self.buf.extend(value.as_bytes());
+        self.buf.push(0);
+    }
+
+    // An array of structs: its length, then 8-aligned elements not counted in it
+    fn struct_array<T>(&mut self, items: &[T], mut write: impl FnMut(&mut Self, &T)) {
+        self.u32(0);
+        let length_at = self.buf.len() - 4;
+        self.pad(8);
+        let start = self.buf.len();
+        for item in items {
+            self.pad(8);
+            write(self, item);
+        }
+        let length = (self.buf.len() - start) as u32;
+        self.buf[length_at..length_at + 4].copy_from_slice(&length.to_le_bytes());
+    }
+}
+
+fn build_message(msg_type: u8, flags: u8, serial: u32, fields: &[(u8, Field)], signature: &str, body: &[u8]) -> Vec<u8> {
+    let mut w = Writer::default();
+    w.u8(b'l');
+    w.u8(msg_type);
+    w.u8(flags);
+    w.u8(1);
+    w.u32(body.len() as u32);
+    w.u32(serial);
+
+    let mut all_fields: Vec<&(u8, Field)> = fields.iter().collect();
+    let signature_field = (FIELD_SIGNATURE, Field::Signature(signature));
+    if !signature.is_empty() {
+        all_fields.push(&signature_field);
+    }
+
+    // a(yv): length, then 8-aligned structs not counted in it
+    let length_at = w.buf.len();
+    w.u32(0);
+    w.pad(8);
+    let start = w.buf.len();
+    for (code, value) in all_fields {
+        w.pad(8);
+        w.u8(*code);
+        match value {
+            Field::Path(path) => {
+                w.signature("o");
+                w.string(path);
+            }
+            Field::Str(string) => {
+                w.signature("s");
+                w.string(string);
+            }
+            Field::Signature(sig) => {
+                w.signature("g");
+                w.signature(sig);
+            }
+            Field::U32(value) => {
+                w.signature("u");
+                w.u32(*value);
+            }
+        }
+    }
+    let length = (w.buf.len() - start) as u32;
+    w.buf[length_at..length_at + 4].copy_from_slice(&length.to_le_bytes());
+
+    w.pad(8);
+    w.buf.extend(body);
+    w.buf
+}
+
+// Demarshalling, honoring the sender's byte order
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl Reader<'_> {
+    fn align(&mut self, align: usize) {
+        self.pos = self.pos.div_ceil(align) * align;
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        let value = *self.buf.get(self.pos)?;
+        self.pos += 1;
+        Some(value)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.align(4);
+        let bytes: [u8; 4] = self.buf.get(self.pos..self.pos + 4)?.try_into().ok()?;
+        self.pos += 4;
+        Some(if self.big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+    }
+
+    #[cfg(test)]
+    fn u64(&mut self) -> Option<u64> {
+        self.align(8);
+        let bytes: [u8; 8] = self.buf.get(self.pos..self.pos + 8)?.try_into().ok()?;
+        self.pos += 8;
+        Some(if self.big_endian { u64::from_be_bytes(bytes) } else { u64::from_le_bytes(bytes) })
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = self.u32()? as usize;
+        let value = String::from_utf8_lossy(self.buf.get(self.pos..self.pos + len)?).to_string();
+        self.pos += len + 1;
+        Some(value)
+    }
+
+    fn signature(&mut self) -> Option<String> {
+        let len = self.u8()? as usize;
+        let value = String::from_utf8_lossy(self.buf.get(self.pos..self.pos + len)?).to_string();
+        self.pos += len + 1;
+        Some(value)
+    }
+}
+
+#[derive(Default)]
+struct Message {
+    msg_type: u8,
+    flags: u8,
+    serial: u32,
+    path: Option<String>,
+    interface: Option<String>,
+    member: Option<String>,
+    error_name: Option<String>,
+    reply_serial: Option<u32>,
+    sender: Option<String>,
+    signature: String,
+    body: Vec<u8>,
+    big_endian: bool,
+}
+
+impl Message {
+    // Total size of the message at the start of the buffer, once its fixed header is in
+    fn total_len(buf: &[u8]) -> Option<usize> {
+        if buf.len() < 16 {
+            return None;
+        }
+        let mut reader = Reader { buf, pos: 4, big_endian: buf[0] == b'B' };
+        let body_len = reader.u32()? as usize;
+        reader.pos = 12;
+        let fields_len = reader.u32()? as usize;
+        Some((16 + fields_len).div_ceil(8) * 8 + body_len)
+    }
+
+    fn parse(buf: &[u8]) -> Option<Message> {
+        let mut reader = Reader { buf, pos: 0, big_endian: buf[0] == b'B' };
+        let mut message = Message { big_endian: reader.big_endian, ..Default::default() };
+
+        reader.pos = 1;
+        message.msg_type = reader.u8()?;
+        message.flags = reader.u8()?;
+        reader.pos = 4;
+        let body_len = reader.u32()? as usize;
+        message.serial = reader.u32()?;
+        let fields_end = 16 + reader.u32()? as usize;
+
+        while reader.pos < fields_end {
+            reader.align(8);
+            let code = reader.u8()?;
+            let signature = reader.signature()?;
+            match signature.as_str() {
+                "s" | "o" => {
+                    let value = reader.string()?;
+                    match code {
+                        FIELD_PATH => message.path = Some(value),
+                        FIELD_INTERFACE => message.interface = Some(value),
+                        FIELD_MEMBER => message.member = Some(value),
+                        FIELD_ERROR_NAME => message.error_name = Some(value),
+                        FIELD_SENDER => message.sender = Some(value),
+                        _ => {}
+                    }
+                }
+                "g" => {
+                    let value = reader.signature()?;
+                    if code == FIELD_SIGNATURE {
+                        message.signature = value;
+                    }
+                }
+                "u" => {
+                    let value = reader.u32()?;
+                    if code == FIELD_REPLY_SERIAL {
+                        message.reply_serial = Some(value);
+                    }
+                }
+                _ => return None,
+            }
+        }
+
+        reader.pos = fields_end.div_ceil(8) * 8;
+        message.body = buf.get(reader.pos..reader.pos + body_len)?.to_vec();
+        Some(message)
+    }
+
+    // Leading string arguments of the body
+    fn string_args(&self) -> Vec<String> {
+        let mut reader = Reader { buf: &self.body, pos: 0, big_endian: self.big_endian };
+        self.signature
+            .chars()
+            .take_while(|c| *c == 's')
+            .map_while(|_| reader.string())
+            .collect()
+    }
+}
+
+/// Minimal session bus connection: just enough of the D-Bus wire protocol to
+/// own a name, answer method calls and emit signals.
+pub struct Bus {
+    stream: UnixStream,
+    buffer: Vec<u8>,
+    // Messages that arrived while waiting for a reply from the bus
+    pending: VecDeque<Message>,
+    serial: u32,
+}
+
+// Connect to the first unix socket of a bus address; None if it has none
+fn connect_address(address: &str) -> Option<Result<UnixStream>> {
+    // unix:path=/run/user/1000/bus[,guid=...]; possibly several ';'-separated
+    for entry in address.split(';') {
+        let Some(params) = entry.strip_prefix("unix:") else {
+            continue;
+        };
+        for param in params.split(',') {
+            if let Some(path) = param.strip_prefix("path=") {
+                return Some(UnixStream::connect(path));
+            }
+            #[cfg(target_os = "linux")]
+            if let Some(name) = param.strip_prefix("abstract=") {
+                use std::os::{linux::net::SocketAddrExt, unix::net::SocketAddr};
+                return Some(SocketAddr::from_abstract_name(name).and_then(|addr| UnixStream::connect_addr(&addr)));
+            }
+        }
+    }
+    None
+}
+
+fn session_bus_stream() -> Result<UnixStream> {
+    if let Some(stream) = connect_address(&env::var("DBUS_SESSION_BUS_ADDRESS").unwrap_or_default()) {
+        return stream;
+    }
+
+    // Default location used by systemd and most setups
+    let runtime_dir = env::var("XDG_RUNTIME_DIR").map_err(|_| Error::new(ErrorKind::NotFound, "no session bus address"))?;
+    UnixStream::connect(format!("{}/bus", runtime_dir))
+}
+
+fn system_bus_stream() -> Result<UnixStream> {
+    let address = env::var("DBUS_SYSTEM_BUS_ADDRESS").unwrap_or_else(|_| String::from("unix:path=/run/dbus/system_bus_socket"));
+    connect_address(&address).unwrap_or_else(|| Err(Error::new(ErrorKind::NotFound, "no system bus address")))
+}
+
+impl Bus {
+    pub fn connect_session() -> Result<Self> {
+        Self::connect(session_bus_stream()?)
+    }
+
+    /// Connect to the system bus, e.g. to follow logind.
+    pub fn connect_system() -> Result<Self> {
+        Self::connect(system_bus_stream()?)
+    }
+
+    fn connect(mut stream: UnixStream) -> Result<Self> {
+
+        // SASL EXTERNAL with our uid, hex-encoded as a decimal string
+        let uid = fs::metadata("/proc/self").map(|m| m.uid()).unwrap_or(0);
+        let hex_uid: String = uid.to_string().bytes().map(|b| format!("{:02x}", b)).collect();
+        stream.write_all(format!("\0AUTH EXTERNAL {}\r\n", hex_uid).as_bytes())?;
+
+        let mut line = Vec::new();
+        let mut byte = [0u8];
+        while !line.ends_with(b"\r\n") {
+            if stream.read(&mut byte)? == 0 {
+                return Err(Error::new(ErrorKind::UnexpectedEof, "bus closed during authentication"));
+            }
+            line.push(byte[0]);
+        }
+        if !line.starts_with(b"OK") {
+            return Err(Error::new(ErrorKind::PermissionDenied, "bus authentication failed"));
+        }
+        stream.write_all(b"BEGIN\r\n")?;
+
+        let mut bus = Bus { stream, buffer: Vec::new(), pending: VecDeque::new(), serial: 0 };
+        bus.call_bus("Hello", "", &[])?;
+        Ok(bus)
+    }
+
+    fn next_serial(&mut self) -> u32 {
+        self.serial += 1;
+        self.serial
+    }
+
+    // Call a method of the bus itself and wait for the reply
+    fn call_bus(&mut self, member: &str, signature: &str, body: &[u8]) -> Result<Message> {
+        self.call("org.freedesktop.DBus", "/org/freedesktop/DBus", "org.freedesktop.DBus", member, signature, body)
+    }
+
+    // Call a method and wait for the reply
+    fn call(&mut self, destination: &str, path: &str, interface: &str, member: &str, signature: &str, body: &[u8]) -> Result<Message> {
+        let serial = self.next_serial();
+        let fields = [
+            (FIELD_PATH, Field::Path(path)),
+            (FIELD_INTERFACE, Field::Str(interface)),
+            (FIELD_MEMBER, Field::Str(member)),
+            (FIELD_DESTINATION, Field::Str(destination)),
+        ];
+        self.stream.write_all(&build_message(METHOD_CALL, 0, serial, &fields, signature, body))?;
+
+        loop {
+            let Some(message) = self.read_message()? else {
+                continue;
+            };
+            if message.reply_serial != Some(serial) {
+                // A method call or signal meanwhile, handled afterwards
+                self.pending.push_back(message);
+                continue;
+            }
+            return match message.msg_type {
+                ERROR => Err(Error::other(message.error_name.unwrap_or_default())),
+                _ => Ok(message),
+            };
+        }
+    }
+
+    /// Take ownership of a well-known name, failing if another process has it.
+    pub fn request_name(&mut self, name: &str) -> Result<()> {
+        let mut body = Writer::default();
+        body.string(name);
+        // DBUS_NAME_FLAG_DO_NOT_QUEUE
+        body.u32(4);
+
+        let reply = self.call_bus("RequestName", "su", &body.buf)?;
+        let mut reader = Reader { buf: &reply.body, pos: 0, big_endian: reply.big_endian };
+        match reader.u32() {
+            // Primary owner, or already the owner
+            Some(1) | Some(4) => Ok(()),
+            _ => Err(Error::new(ErrorKind::AddrInUse, format!("{} is already owned", name))),
+        }
+    }
+
+    /// Receive the signals matching a rule, e.g. `type='signal',path='/org/...'`.
+    pub fn add_match(&mut self, rule: &str) -> Result<()> {
+        self.call_bus("AddMatch", "s", &string_reply(rule)).map(|_| ())
+    }
+
+    /// Call a method taking strings and returning a string or object path.
+    pub fn call_string(&mut self, destination: &str, path: &str, interface: &str, member: &str, args: &[&str]) -> Result<String> {
+        let mut body = Writer::default();
+        for arg in args {
+            body.string(arg);
+        }
+        let reply = self.call(destination, path, interface, member, &"s".repeat(args.len()), &body.buf)?;
+        let mut reader = Reader { buf: &reply.body, pos: 0, big_endian: reply.big_endian };
+        match reply.signature.as_str() {
+            "s" | "o" => reader.string(),
+            _ => None,
+        }
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("unexpected reply to {}", member)))
+    }
+
+    /// Read a boolean property through `org.freedesktop.DBus.Properties`.
+    pub fn bool_property(&mut self, destination: &str, path: &str, interface: &str, name: &str) -> Result<bool> {
+        let mut body = Writer::default();
+        body.string(interface);
+        body.string(name);
+        let reply = self.call(destination, path, "org.freedesktop.DBus.Properties", "Get", "ss", &body.buf)?;
+        // A variant: its signature, then the value
+        let mut reader = Reader { buf: &reply.body, pos: 0, big_endian: reply.big_endian };
+        match reader.signature().as_deref() {
+            Some("b") => reader.u32().map(|value| value != 0),
+            _ => None,
+        }
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("{} is not a boolean", name)))
+    }
+
+    /// Member of the next signal, or None when the read timed out.
+    pub fn next_signal(&mut self) -> Result<Option<String>> {
+        loop {
+            match self.next_message()? {
+                Some(message) if message.msg_type == SIGNAL => return Ok(Some(message.member.unwrap_or_default())),
+                Some(_) => {}
+                None => return Ok(None),
+            }
+        }
+    }
+
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        self.stream.set_read_timeout(timeout)
+    }
+
+    // Next message, queued ones first, or None when the read timed out
+    fn next_message(&mut self) -> Result<Option<Message>> {
+        match self.pending.pop_front() {
+            Some(message) => Ok(Some(message)),
+            None => self.read_message(),
+        }
+    }
+
+    // Next complete message off the connection, or None when the read timed out
+    fn read_message(&mut self) -> Result<Option<Message>> {
+        loop {
+            if let Some(len) = Message::total_len(&self.buffer) {
+                if self.buffer.len() >= len {
+                    let message = Message::parse(&self.buffer[..len]);
+                    self.buffer.drain(..len);
+                    match message {
+                        Some(message) => return Ok(Some(message)),
+                        None => continue,
+                    }
+                }
+            }
+
+            let mut chunk = [0u8; 4096];
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(Error::new(ErrorKind::UnexpectedEof, "bus connection closed")),
+                Ok(n) => self.buffer.extend(&chunk[..n]),
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => return Ok(None),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn reply(&mut self, call: &Message, signature: &str, body: &[u8]) -> Result<()> {
+        if call.flags & NO_REPLY_EXPECTED != 0 {
+            return Ok(());
+        }
+
+        let serial = self.next_serial();
+        let sender = call.sender.clone().unwrap_or_default();
+        let fields = [(FIELD_REPLY_SERIAL, Field::U32(call.serial)), (FIELD_DESTINATION, Field::Str(&sender))];
+        self.stream.write_all(&build_message(METHOD_RETURN, 0, serial, &fields, signature, body))
+    }
+
+    fn reply_error(&mut self, call: &Message, name: &str, text: &str) -> Result<()> {
+        if call.flags & NO_REPLY_EXPECTED != 0 {
+            return Ok(());
+        }
+
+        let serial = self.next_serial();
+        let sender = call.sender.clone().unwrap_or_default();
+        let mut body = Writer::default();
+        body.string(text);
+        let fields = [
+            (FIELD_ERROR_NAME, Field::Str(name)),
+            (FIELD_REPLY_SERIAL, Field::U32(call.serial)),
+            (FIELD_DESTINATION, Field::Str(&sender)),
+        ];
+        self.stream.write_all(&build_message(ERROR, 0, serial, &fields, "s", &body.buf))
+    }
+
+    /// Emit a signal with a single `u` argument.
+    pub fn emit_u32(&mut self, member: &str, value: u32) -> Result<()> {
+        let serial = self.next_serial();
+        let mut body = Writer::default();
+        body.u32(value);
+        let fields = [
+            (FIELD_PATH, Field::Path(OBJECT_PATH)),
+            (FIELD_INTERFACE, Field::Str(INTERFACE)),
+            (FIELD_MEMBER, Field::Str(member)),
+        ];
+        self.stream.write_all(&build_message(SIGNAL, 0, serial, &fields, "u", &body.buf))
+    }
+}
+
+fn string_reply(value: &str) -> Vec<u8> {
+    let mut body = Writer::default();
+    body.string(value);
+    body.buf
+}
+
+// List's a(sssit): address, class, title, workspace and minimize time (Unix ms)
+fn windows_reply(windows: &[MinimizedWindow]) -> Vec<u8> {
+    let mut body = Writer::default();
+    body.struct_array(windows, |w, window| {
+        w.string(&window.address);
+        w.string(&window.class);
+        w.string(&window.display_title);
+        w.i32(window.workspace);
+        w.u64(window.minimized_at);
+    });
+    body.buf
+}
+
+// Run a method call against the manager; Ok holds the reply's signature and body
+fn call_method(manager: &Manager, call: &Message) -> Result<(&'static str, Vec<u8>)> {
+    if call.path.as_deref() != Some(OBJECT_PATH) {
+        return Err(Error::new(ErrorKind::Unsupported, format!("No object at {}", call.path.as_deref().unwrap_or_default())));
+    }
+
+    let args = call.string_args();
+    let address = args.first().map(String::as_str).unwrap_or_default();
+
+    match (call.interface.as_deref(), call.member.as_deref().unwrap_or_default()) {
+        (Some("org.freedesktop.DBus.Introspectable"), "Introspect") => Ok(("s", string_reply(INTROSPECTION))),
+        (Some("org.freedesktop.DBus.Peer"), "Ping") => Ok(("", Vec::new())),
+        // An empty address minimizes the active window
+        (Some(INTERFACE) | None, "Minimize") if address.is_empty() => manager.minimize_active().map(|_| ("", Vec::new())),
+        (Some(INTERFACE) | None, "Minimize") => manager.minimize_address(address).map(|_| ("", Vec::new())),
+        (Some(INTERFACE) | None, "Restore") => manager.restore(address).map(|_| ("", Vec::new())),
+        (Some(INTERFACE) | None, "RestoreAll") => manager.restore_all().map(|_| ("", Vec::new())),
+        (Some(INTERFACE) | None, "Close") => manager.close_minimized(address).map(|_| ("", Vec::new())),
+        (Some(INTERFACE) | None, "List") => Ok(("a(sssit)", windows_reply(&manager.windows()?))),
+        (_, member) => Err(Error::new(ErrorKind::Unsupported, format!("Unknown method {}", member))),
+    }
+}
+
+/// Serve `org.minhypr.Manager` on the session bus until the connection drops.
+/// `WindowsChanged(count)` is emitted whenever the minimized set changes.
+pub fn run_service() -> Result<()> {
+    let mut bus = Bus::connect_session()?;
+    bus.request_name(BUS_NAME)?;
+    bus.set_read_timeout(Some(Duration::from_millis(250)))?;
+    info!("D-Bus service registered as {}", BUS_NAME);
+
+    let mut signature = instance_signature().unwrap_or_default();
+    let mut manager = Manager::new();
+    // None until the first check, so the initial state isn't signalled
+    let mut last_modified: Option<Option<SystemTime>> = None;
+
+    loop {
+        // The daemon switched to a new Hyprland instance
+        let current = instance_signature().unwrap_or_default();
+        if current != signature {
+            signature = current;
+            manager = Manager::new();
+            last_modified = None;
+        }
+
+        if let Some(call) = bus.next_message()? {
+            if call.msg_type == METHOD_CALL {
+                match call_method(&manager, &call) {
+                    Ok((signature, body)) => bus.reply(&call, signature, &body)?,
+                    Err(e) if e.kind() == ErrorKind::Unsupported => {
+                        bus.reply_error(&call, "org.freedesktop.DBus.Error.UnknownMethod", &e.to_string())?
+                    }
+                    Err(e) => bus.reply_error(&call, "org.freedesktop.DBus.Error.Failed", &e.to_string())?,
+                }
+            }
+        }
+
+        let modified = fs::metadata(manager.store.cache_file()).and_then(|m| m.modified()).ok();
+        if last_modified.is_some_and(|last| last != modified) {
+            match manager.windows() {
+                Ok(windows) => bus.emit_u32("WindowsChanged", windows.len() as u32)?,
+                Err(e) => warn!("Could not read the minimized windows: {}", e),
+            }
+        }
+        last_modified = Some(modified);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn method_call(serial: u32, member: &str, address: &str) -> Vec<u8> {
+        let fields = [
+            (FIELD_PATH, Field::Path(OBJECT_PATH)),
+            (FIELD_INTERFACE, Field::Str(INTERFACE)),
+            (FIELD_MEMBER, Field::Str(member)),
+            (FIELD_SENDER, Field::Str(":1.42")),
+        ];
+        build_message(METHOD_CALL, NO_REPLY_EXPECTED, serial, &fields, "s", &string_reply(address))
+    }
+
+    #[test]
+    fn method_calls_round_trip() {
+        let bytes = method_call(7, "Restore", "0x55d3a8f0e1b0");
+        assert_eq!(Message::total_len(&bytes), Some(bytes.len()));
+
+        let message = Message::parse(&bytes).unwrap();
+        assert_eq!(message.msg_type, METHOD_CALL);
+        assert_eq!(message.flags, NO_REPLY_EXPECTED);
+        assert_eq!(message.serial, 7);
+        assert_eq!(message.path.as_deref(), Some(OBJECT_PATH));
+        assert_eq!(message.interface.as_deref(), Some(INTERFACE));
+        assert_eq!(message.member.as_deref(), Some("Restore"));
+        assert_eq!(message.sender.as_deref(), Some(":1.42"));
+        assert_eq!(message.signature, "s");
+        assert_eq!(message.string_args(), ["0x55d3a8f0e1b0"]);
+    }
+
+    #[test]
+    fn errors_and_replies_round_trip() {
+        let fields = [(FIELD_ERROR_NAME, Field::Str("org.freedesktop.DBus.Error.Failed")), (FIELD_REPLY_SERIAL, Field::U32(3))];
+        let message = Message::parse(&build_message(ERROR, 0, 9, &fields, "s", &string_reply("Window not found"))).unwrap();
+        assert_eq!(message.msg_type, ERROR);
+        assert_eq!(message.error_name.as_deref(), Some("org.freedesktop.DBus.Error.Failed"));
+        assert_eq!(message.reply_serial, Some(3));
+        assert_eq!(message.string_args(), ["Window not found"]);
+
+        // No signature field without a body
+        let message = Message::parse(&build_message(METHOD_RETURN, 0, 10, &[(FIELD_REPLY_SERIAL, Field::U32(4))], "", &[])).unwrap();
+        assert_eq!(message.signature, "");
+        assert!(message.body.is_empty());
+    }
+
+    #[test]
+    fn truncated_messages_wait_for_the_rest() {
+        let bytes = method_call(1, "Minimize", "");
+        assert_eq!(Message::total_len(&bytes[..8]), None);
+        assert_eq!(Message::total_len(&bytes[..20]), Some(bytes.len()));
+        assert!(Message::parse(&bytes[..bytes.len() - 1]).is_none());
+    }
+
+    #[test]
+    fn list_is_an_array_of_structs() {
+        let window = |address: &str, workspace: i32| -> MinimizedWindow {
+            serde_json::from_value(serde_json::json!({
+                "address": address,
+                "display_title": "Terminal",
+                "class": "kitty",
+                "original_title": "Terminal",
+                "preview_path": null,
+                "icon": "",
+                "workspace": workspace,
+                "minimized_at": 1_700_000_000_000u64,
+            }))
+            .unwrap()
+        };
+        let body = windows_reply(&[window("0x1", 2), window("0x2", -3)]);
+
+        let mut reader = Reader { buf: &body, pos: 0, big_endian: false };
+        let length = reader.u32().unwrap() as usize;
+        reader.align(8);
+        assert_eq!(reader.pos + length, body.len());
+
+        for (address, workspace) in [("0x1", 2), ("0x2", -3)] {
+            reader.align(8);
+            assert_eq!(reader.string().as_deref(), Some(address));
+            assert_eq!(reader.string().as_deref(), Some("kitty"));
+            assert_eq!(reader.string().as_deref(), Some("Terminal"));
+            assert_eq!(reader.u32().map(|w| w as i32), Some(workspace));
+            assert_eq!(reader.u64(), Some(1_700_000_000_000));
+        }
+        assert_eq!(reader.pos, body.len());
+        assert_eq!(windows_reply(&[]), [0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn calls_arriving_before_a_bus_reply_are_queued() {
+        let (stream, mut peer) = UnixStream::pair().unwrap();
+        let mut bus = Bus { stream, buffer: Vec::new(), pending: VecDeque::new(), serial: 0 };
+
+        // A client call slips in before the bus answers our serial 1
+        peer.write_all(&method_call(5, "Restore", "0x1")).unwrap();
+        peer.write_all(&build_message(METHOD_RETURN, 0, 1, &[(FIELD_REPLY_SERIAL, Field::U32(1))], "", &[])).unwrap();
+
+        let reply = bus.call_bus("Hello", "", &[]).unwrap();
+        assert_eq!(reply.reply_serial, Some(1));
+
+        let call = bus.next_message().unwrap().unwrap();
+        assert_eq!(call.member.as_deref(), Some("Restore"));
+        assert_eq!(call.string_args(), ["0x1"]);
+    }
+
+    #[test]
+    fn logind_replies_are_read() {
+        let (stream, mut peer) = UnixStream::pair().unwrap();
+        let mut bus = Bus { stream, buffer: Vec::new(), pending: VecDeque::new(), serial: 0 };
+
+        let mut path = Writer::default();
+        path.string("/org/freedesktop/login1/session/_32");
+        peer.write_all(&build_message(METHOD_RETURN, 0, 1, &[(FIELD_REPLY_SERIAL, Field::U32(1))], "o", &path.buf)).unwrap();
+        let mut hint = Writer::default();
+        hint.signature("b");
+        hint.u32(1);
+        peer.write_all(&build_message(METHOD_RETURN, 0, 2, &[(FIELD_REPLY_SERIAL, Field::U32(2))], "v", &hint.buf)).unwrap();
+        let unlock = [(FIELD_PATH, Field::Path("/org/freedesktop/login1/session/_32")), (FIELD_MEMBER, Field::Str("Unlock"))];
+        peer.write_all(&build_message(SIGNAL, 0, 3, &unlock, "", &[])).unwrap();
+
+        let path = bus.call_string("org.freedesktop.login1", "/org/freedesktop/login1", "org.freedesktop.login1.Manager", "GetSession", &["2"]);
+        assert_eq!(path.unwrap(), "/org/freedesktop/login1/session/_32");
+        assert!(bus.bool_property("org.freedesktop.login1", "/", "org.freedesktop.login1.Session", "LockedHint").unwrap());
+        assert_eq!(bus.next_signal().unwrap().as_deref(), Some("Unlock"));
+    }
+}
@@ -0,0 +1,213 @@
+use std::{env, fs, os::unix::net::UnixStream, path::Path, process::Command};
+
+use serde_json::Value;
+
+use crate::{
+    config::{config_file, parse_toml, CaptureMethod, Config, Launcher},
+    control,
+    hyprland::{find_event_socket, runtime_dir, socket_request},
+    manager::Manager,
+};
+
+// Printed report: one line per check, with a fix under each failure
+struct Report {
+    failures: usize,
+}
+
+impl Report {
+    fn ok(&self, message: &str) {
+        println!("✓ {}", message);
+    }
+
+    fn warn(&self, message: &str, fix: &str) {
+        println!("! {}\n    {}", message, fix);
+    }
+
+    fn fail(&mut self, message: &str, fix: &str) {
+        println!("✗ {}\n    {}", message, fix);
+        self.failures += 1;
+    }
+}
+
+fn in_path(program: &str) -> bool {
+    env::var_os("PATH").is_some_and(|path| env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+fn check_programs(report: &mut Report, config: &Config) {
+    report_program(
+        report,
+        "hyprctl",
+        true,
+        "Used when the Hyprland socket can't be reached; it ships with Hyprland.",
+    );
+    report_program(report, "grim", false, "Install grim for window previews.");
+    if config.capture != CaptureMethod::Geometry && in_path("grim") {
+        check_toplevel_capture(report, config);
+    }
+
+    report_program(report, "slurp", false, "Install slurp to pick the window to minimize with `minimize --pick`.");
+
+    match config.launcher {
+        Launcher::Rofi => report_program(
+            report,
+            "rofi",
+            false,
+            "Install rofi (rofi-wayland) for the restore menu, or set launcher = \"bemenu\".",
+        ),
+        Launcher::Bemenu => report_program(report, "bemenu", true, "Install bemenu, or set launcher = \"rofi\"."),
+    }
+
+    let notifications = config.notifications || config.notify.values().any(|on| *on);
+    if notifications {
+        report_program(
+            report,
+            "notify-send",
+            true,
+            "Install libnotify (0.7.9 or newer) for notifications.",
+        );
+    }
+
+    let mute = config.mute_audio || config.mute_audio_classes.values().any(|on| *on);
+    if mute {
+        report_program(report, "pw-dump", true, "Install PipeWire's tools for mute_audio.");
+        report_program(report, "wpctl", true, "Install WirePlumber for mute_audio.");
+    }
+}
+
+// grim-hyprland's `-w` captures a window by itself; plain grim only grabs
+// the screen area under it
+fn check_toplevel_capture(report: &mut Report, config: &Config) {
+    let help = Command::new("grim").arg("-h").output();
+    let supported = help.is_ok_and(|output| {
+        String::from_utf8_lossy(&output.stdout).contains("-w ") || String::from_utf8_lossy(&output.stderr).contains("-w ")
+    });
+
+    let fix = "Install grim-hyprland for previews of the window alone, or set capture = \"geometry\".";
+    if supported {
+        report.ok("grim can capture single windows");
+    } else if config.capture == CaptureMethod::Toplevel {
+        report.fail("grim can't capture single windows (capture = \"toplevel\")", fix);
+    } else {
+        report.warn("grim can't capture single windows; previews include overlapping windows", fix);
+    }
+}
+
+fn report_program(report: &mut Report, program: &str, required: bool, fix: &str) {
+    if in_path(program) {
+        report.ok(&format!("{} found", program));
+    } else if required {
+        report.fail(&format!("{} not found in PATH", program), fix);
+    } else {
+        report.warn(&format!("{} not found in PATH", program), fix);
+    }
+}
+
+fn check_hyprland(report: &mut Report) {
+    let Ok(signature) = env::var("HYPRLAND_INSTANCE_SIGNATURE") else {
+        report.fail(
+            "HYPRLAND_INSTANCE_SIGNATURE is not set",
+            "Run minhypr from inside a Hyprland session (exec-once or a terminal in Hyprland).",
+        );
+        return;
+    };
+    report.ok(&format!("Hyprland instance {}", signature));
+
+    let instance_dir = runtime_dir().join(&signature);
+    if !instance_dir.is_dir() {
+        report.fail(
+            &format!("{} does not exist", instance_dir.display()),
+            "The signature is stale (Hyprland restarted?); start a new shell or run `minhypr daemon`, which follows restarts.",
+        );
+        return;
+    }
+
+    match socket_request("j/version").and_then(|response| serde_json::from_str::<Value>(&response).ok()) {
+        Some(version) => report.ok(&format!(
+            "Hyprland socket answers (version {})",
+            version["tag"]
+                .as_str()
+                .or(version["version"].as_str())
+                .unwrap_or("unknown")
+        )),
+        None => report.fail(
+            &format!("No answer on {}", instance_dir.join(".socket.sock").display()),
+            "Check that Hyprland is running and that XDG_RUNTIME_DIR matches its session.",
+        ),
+    }
+
+    match find_event_socket() {
+        Some((found, socket)) if found == signature => report.ok(&format!("Event socket {}", socket.display())),
+        Some((found, _)) => report.warn(
+            &format!("Event socket belongs to another instance ({})", found),
+            "The daemon will follow it; restart your shell to refresh HYPRLAND_INSTANCE_SIGNATURE.",
+        ),
+        None => report.fail(
+            "No Hyprland event socket found",
+            "The daemon, watch and listen need Hyprland's .socket2.sock.",
+        ),
+    }
+}
+
+fn check_writable(report: &mut Report, name: &str, dir: &str) {
+    let probe = Path::new(dir).join(".doctor");
+    match fs::write(&probe, b"ok") {
+        Ok(()) => {
+            fs::remove_file(&probe).ok();
+            report.ok(&format!("{} directory {} is writable", name, dir));
+        }
+        Err(e) => report.fail(
+            &format!("{} directory {} is not writable: {}", name, dir, e),
+            "Check the permissions of $XDG_RUNTIME_DIR/minhypr, or run `minhypr purge`.",
+        ),
+    }
+}
+
+fn check_config(report: &mut Report) {
+    let path = config_file();
+    let Ok(content) = fs::read_to_string(&path) else {
+        report.ok(&format!("No config file at {} (defaults)", path.display()));
+        return;
+    };
+
+    match parse_toml(&content).and_then(|value| serde_json::from_value::<Config>(value).map_err(|e| e.to_string())) {
+        Ok(_) => report.ok(&format!("Config {} is valid", path.display())),
+        Err(e) => report.fail(
+            &format!("Config {} is invalid: {}", path.display(), e),
+            "Fix the file; until then the defaults are used.",
+        ),
+    }
+}
+
+fn check_daemon(report: &Report) {
+    if UnixStream::connect(control::socket_path()).is_ok() {
+        report.ok("Daemon is running");
+    } else {
+        report.warn(
+            "Daemon is not running",
+            "Add `exec-once = minhypr daemon` to hyprland.conf to keep the state in sync (needed for snooze and auto-minimize rules).",
+        );
+    }
+}
+
+/// Check dependencies, the Hyprland connection, state directories and the
+/// config, printing a fix for each problem. Returns whether all checks passed.
+pub fn run_doctor(manager: &Manager) -> bool {
+    let mut report = Report { failures: 0 };
+
+    check_programs(&mut report, &manager.config);
+    check_hyprland(&mut report);
+
+    if let Err(e) = manager.store.init() {
+        report.fail(
+            &format!("Could not create the state directories: {}", e),
+            "Check that $XDG_RUNTIME_DIR exists and is yours.",
+        );
+    }
+    check_writable(&mut report, "Cache", manager.store.cache_dir());
+    check_writable(&mut report, "Preview", manager.store.preview_dir());
+
+    check_config(&mut report);
+    check_daemon(&report);
+
+    report.failures == 0
+}
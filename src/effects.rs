@@ -0,0 +1,100 @@
+use std::io::Result;
+
+use crate::{
+    audio,
+    config::Config,
+    history::{self, EventKind},
+    hooks, notify, preview, refresh, suspend,
+    window::MinimizedWindow,
+};
+
+/// What minhypr does outside the compositor and its own state directories:
+/// refreshing bars, hooks, history, notifications, stopping and muting
+/// processes, and screenshots. Implemented by [`SystemEffects`] and, for
+/// tests, by the recording [`MockEffects`].
+///
+/// [`MockEffects`]: crate::mock::MockEffects
+pub trait Effects: Send + Sync {
+    /// Tell bars that the minimized set changed.
+    fn refresh_bars(&self, config: &Config);
+
+    /// Run the `on_minimize` or `on_restore` command for a window.
+    fn run_hook(&self, command: Option<&str>, event: &str, window: &MinimizedWindow);
+
+    /// Append minimizes or restores to the history file.
+    fn record_history(&self, event: EventKind, windows: &[&MinimizedWindow]);
+
+    /// Show the "minimized" (or, with `urgent`, "wants attention") notification.
+    fn notify(&self, address: &str, urgent: bool) -> Result<()>;
+
+    /// Tell the user that `refused` windows stayed open because of `limit`.
+    fn notify_limit_reached(&self, refused: usize, limit: usize);
+
+    /// Mute or unmute a process's audio streams; whether any changed.
+    fn set_muted(&self, pid: i32, muted: bool) -> bool;
+
+    /// Stop a process (SIGSTOP); whether it worked.
+    fn stop(&self, pid: i32) -> bool;
+
+    /// Let a stopped process run again (SIGCONT); whether it worked.
+    fn resume(&self, pid: i32) -> bool;
+
+    /// Screenshot a window by itself; returns the raw file.
+    fn capture_toplevel(&self, preview_dir: &str, address: &str) -> Result<String>;
+
+    /// Screenshot the screen area `geometry` (`x,y wxh`); returns the raw file.
+    fn capture_area(&self, preview_dir: &str, address: &str, geometry: &str) -> Result<String>;
+
+    /// Build a window's thumbnails from a screenshot in a detached child.
+    fn spawn_preview_job(&self, address: &str, screenshot: &str) -> Result<()>;
+}
+
+/// The real thing: pkill, hooks through `sh`, notify-send, wpctl, kill and grim.
+#[derive(Clone, Copy, Default)]
+pub struct SystemEffects;
+
+impl Effects for SystemEffects {
+    fn refresh_bars(&self, config: &Config) {
+        refresh::refresh_bars(config);
+    }
+
+    fn run_hook(&self, command: Option<&str>, event: &str, window: &MinimizedWindow) {
+        hooks::run_hook(command, event, window);
+    }
+
+    fn record_history(&self, event: EventKind, windows: &[&MinimizedWindow]) {
+        history::record(event, windows.iter().copied());
+    }
+
+    fn notify(&self, address: &str, urgent: bool) -> Result<()> {
+        notify::spawn_notification(address, urgent)
+    }
+
+    fn notify_limit_reached(&self, refused: usize, limit: usize) {
+        notify::notify_limit_reached(refused, limit);
+    }
+
+    fn set_muted(&self, pid: i32, muted: bool) -> bool {
+        audio::set_muted(pid, muted)
+    }
+
+    fn stop(&self, pid: i32) -> bool {
+        suspend::stop(pid)
+    }
+
+    fn resume(&self, pid: i32) -> bool {
+        suspend::resume(pid)
+    }
+
+    fn capture_toplevel(&self, preview_dir: &str, address: &str) -> Result<String> {
+        preview::capture_window_toplevel(preview_dir, address)
+    }
+
+    fn capture_area(&self, preview_dir: &str, address: &str, geometry: &str) -> Result<String> {
+        preview::capture_window_screenshot(preview_dir, address, geometry)
+    }
+
+    fn spawn_preview_job(&self, address: &str, screenshot: &str) -> Result<()> {
+        preview::spawn_preview_job(address, screenshot)
+    }
+}
@@ -0,0 +1,61 @@
+use std::{fmt, io, path::PathBuf};
+
+/// Failures callers may want to tell apart. They travel inside `io::Error`
+/// like every other error here; `Error::of` gets them back out.
+#[derive(Debug)]
+pub enum Error {
+    /// No window (minimized, or open for minimize) matches the address or index.
+    WindowNotFound(String),
+    /// Neither the Hyprland socket nor `hyprctl` is available.
+    HyprlandUnavailable,
+    /// The daemon's control socket doesn't answer.
+    DaemonUnavailable(PathBuf),
+}
+
+impl Error {
+    /// The minhypr error carried by an `io::Error`, if any.
+    pub fn of(error: &io::Error) -> Option<&Error> {
+        error.get_ref().and_then(|inner| inner.downcast_ref::<Error>())
+    }
+
+    /// Process exit status for the error: 2 window not found, 3 Hyprland
+    /// unavailable, 4 daemon not running.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::WindowNotFound(_) => 2,
+            Error::HyprlandUnavailable => 3,
+            Error::DaemonUnavailable(_) => 4,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::WindowNotFound(window) => write!(f, "Window not found: {}", window),
+            Error::HyprlandUnavailable => {
+                write!(f, "cannot reach Hyprland: its socket is unavailable and hyprctl is not installed")
+            }
+            Error::DaemonUnavailable(socket) => {
+                write!(f, "cannot reach the daemon at {}, is `minhypr daemon` running?", socket.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        let kind = match error {
+            Error::WindowNotFound(_) => io::ErrorKind::NotFound,
+            Error::HyprlandUnavailable | Error::DaemonUnavailable(_) => io::ErrorKind::NotConnected,
+        };
+        io::Error::new(kind, error)
+    }
+}
+
+/// Exit status for an error: the `Error` code, or 1 for anything else.
+pub fn exit_code(error: &io::Error) -> i32 {
+    Error::of(error).map_or(1, Error::exit_code)
+}
@@ -0,0 +1,127 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader, ErrorKind, Result},
+    os::unix::net::UnixStream,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+
+use crate::{compositor::Compositor, hyprland::find_event_socket, manager::Manager, window::MinimizedWindow};
+
+// Outputs show how long ago windows were minimized, so they're refreshed
+// this often even without changes
+const AGE_REFRESH: Duration = Duration::from_secs(60);
+
+// Events that can change the set of minimized windows
+fn is_window_event(event: &str) -> bool {
+    let name = event.split_once(">>").map_or(event, |(name, _)| name);
+    matches!(name, "openwindow" | "closewindow" | "movewindow" | "movewindowv2")
+}
+
+/// Call `on_change` now and whenever the minimized set may have changed.
+/// Driven by the Hyprland event socket, plus changes to the cache file made
+/// by other minhypr invocations. Runs until `on_change` fails.
+pub fn watch_changes(manager: &Manager, mut on_change: impl FnMut() -> Result<()>) -> Result<()> {
+    let mut last_modified = None;
+    let mut last_refresh = Instant::now();
+
+    on_change()?;
+
+    loop {
+        let stream = find_event_socket().and_then(|(_, socket)| UnixStream::connect(socket).ok());
+        let Some(stream) = stream else {
+            thread::sleep(Duration::from_secs(1));
+            continue;
+        };
+
+        stream.set_read_timeout(Some(Duration::from_millis(250)))?;
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+
+        loop {
+            let refresh = match reader.read_line(&mut line) {
+                // Socket closed: Hyprland exited
+                Ok(0) => break,
+                Ok(_) => {
+                    let refresh = is_window_event(line.trim_end());
+                    line.clear();
+                    refresh
+                }
+                // Timed out, keep any partial line for the next read
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => false,
+                Err(_) => break,
+            };
+
+            let modified = fs::metadata(manager.store.cache_file())
+                .and_then(|m| m.modified())
+                .ok();
+
+            if refresh || modified != last_modified || last_refresh.elapsed() >= AGE_REFRESH {
+                last_modified = modified;
+                last_refresh = Instant::now();
+                on_change()?;
+            }
+        }
+
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+fn window_event(event: &str, window: &MinimizedWindow) -> Value {
+    json!({
+        "event": event,
+        "address": window.address,
+        "class": window.class,
+        "title": window.original_title,
+        "workspace": window.workspace,
+    })
+}
+
+// Events turning the `previous` minimized set into `current`. Windows that
+// left the set are "restored" if they still exist and "closed" otherwise.
+fn diff_events(manager: &Manager, previous: &[MinimizedWindow], current: &[MinimizedWindow]) -> Result<Vec<Value>> {
+    let mut events = Vec::new();
+
+    for window in current {
+        if !previous.iter().any(|w| w.address == window.address) {
+            events.push(window_event("minimized", window));
+        }
+    }
+
+    for window in previous {
+        if !current.iter().any(|w| w.address == window.address) {
+            let event = if manager.compositor.client(&window.address)?.is_some() { "restored" } else { "closed" };
+            events.push(window_event(event, window));
+        }
+    }
+
+    if previous.len() != current.len() {
+        events.push(json!({"event": "count_changed", "count": current.len()}));
+    }
+
+    Ok(events)
+}
+
+/// Print one JSON event per line as windows are minimized, restored or
+/// closed, for eww `deflisten`, ironbar scripts and similar. The first line
+/// is a `count_changed` with the current count.
+pub fn listen(manager: &Manager) -> Result<()> {
+    let mut previous: Option<Vec<MinimizedWindow>> = None;
+
+    watch_changes(manager, || {
+        let current = manager.windows()?;
+
+        let events = match &previous {
+            Some(previous) => diff_events(manager, previous, &current)?,
+            None => vec![json!({"event": "count_changed", "count": current.len()})],
+        };
+        for event in events {
+            println!("{}", event);
+        }
+
+        previous = Some(current);
+        Ok(())
+    })
+}
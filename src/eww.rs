@@ -0,0 +1,63 @@
+use std::io::Result;
+
+use serde_json::{json, Value};
+
+use crate::{
+    events::watch_changes,
+    manager::Manager,
+    window::{windows_by_recency, MinimizedWindow},
+};
+
+/// Sample widget reading `minhypr eww --watch`; restores on click.
+pub const SAMPLE_YUCK: &str = r#"(deflisten minimized :initial "[]" "minhypr eww --watch")
+
+(defwidget minimized-windows []
+  (box :class "minimized" :space-evenly false :spacing 4
+    (for window in minimized
+      (button :class "minimized-window"
+              :tooltip "${window.class} - ${window.title}"
+              :onclick "minhypr restore ${window.address}"
+              :onmiddleclick "hyprctl dispatch closewindow address:${window.address}"
+        (box :space-evenly false :spacing 4
+          (image :path {window.icon_path} :image-width 16 :image-height 16)
+          (label :limit-width 24 :text {window.title}))))))
+"#;
+
+/// The minimized windows as a JSON array for eww, most recent first. Paths
+/// are empty strings when unknown, which is easier to test for in yuck.
+pub fn eww_json(windows: &[MinimizedWindow]) -> Value {
+    let windows: Vec<Value> = windows_by_recency(windows)
+        .into_iter()
+        .map(|w| {
+            json!({
+                "address": w.address,
+                "class": w.class,
+                "title": w.original_title,
+                "workspace": w.workspace,
+                "icon": w.icon,
+                "icon_path": w.icon_path.clone().unwrap_or_default(),
+                "preview_path": w.preview_path.clone().unwrap_or_default(),
+            })
+        })
+        .collect();
+
+    Value::Array(windows)
+}
+
+/// Print the eww JSON once, or a new line whenever it changes with `watch`.
+pub fn print_eww(manager: &Manager, watch: bool) -> Result<()> {
+    if !watch {
+        println!("{}", eww_json(&manager.windows()?));
+        return Ok(());
+    }
+
+    let mut last = String::new();
+    watch_changes(manager, || {
+        let output = eww_json(&manager.windows()?).to_string();
+        if output != last {
+            println!("{}", output);
+            last = output;
+        }
+        Ok(())
+    })
+}
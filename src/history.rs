@@ -0,0 +1,132 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Result, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    log::state_home,
+    window::{unix_timestamp_ms, MinimizedWindow},
+};
+
+// Past this, the oldest half of the history is dropped
+const MAX_HISTORY_SIZE: u64 = 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Minimize,
+    Restore,
+}
+
+/// A minimize or restore, one JSON object per line in the history file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Event {
+    /// Unix time in milliseconds.
+    pub time: u64,
+    pub event: EventKind,
+    pub class: String,
+    pub address: String,
+    /// When the window was minimized, for restores.
+    pub minimized_at: u64,
+}
+
+/// Counts and time spent minimized for one class.
+#[derive(Debug, Default, Serialize)]
+pub struct ClassStats {
+    pub minimized: usize,
+    pub restored: usize,
+    /// Average time between minimize and restore, in seconds.
+    pub average_minimized_secs: u64,
+}
+
+pub fn history_file_path() -> PathBuf {
+    state_home().join("history.jsonl")
+}
+
+/// Append events to the history. Only the class and address are kept, never
+/// titles, so private windows stay private here too.
+pub fn record<'a>(event: EventKind, windows: impl IntoIterator<Item = &'a MinimizedWindow>) {
+    let windows: Vec<&MinimizedWindow> = windows.into_iter().collect();
+    if windows.is_empty() {
+        return;
+    }
+    if let Err(e) = append(event, &windows) {
+        debug!("Could not write the history: {}", e);
+    }
+}
+
+fn append(event: EventKind, windows: &[&MinimizedWindow]) -> Result<()> {
+    let path = history_file_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    if fs::metadata(&path).is_ok_and(|m| m.len() > MAX_HISTORY_SIZE) {
+        trim(&path)?;
+    }
+
+    let time = unix_timestamp_ms();
+    let mut lines = String::new();
+    for window in windows {
+        let entry = Event {
+            time,
+            event,
+            class: window.class.clone(),
+            address: window.address.clone(),
+            minimized_at: window.minimized_at,
+        };
+        lines.push_str(&serde_json::to_string(&entry)?);
+        lines.push('\n');
+    }
+
+    // One write in append mode, so concurrent minhypr processes don't interleave
+    OpenOptions::new().create(true).append(true).open(&path)?.write_all(lines.as_bytes())
+}
+
+// Keep the newest half of the events
+fn trim(path: &Path) -> Result<()> {
+    let content = fs::read_to_string(path)?;
+    let lines: Vec<&str> = content.lines().collect();
+    let kept = lines[lines.len() / 2..].join("\n") + "\n";
+
+    let tmp_path = path.with_extension("jsonl.tmp");
+    fs::write(&tmp_path, kept)?;
+    fs::rename(&tmp_path, path)
+}
+
+/// Every recorded event, oldest first. Unreadable lines are skipped.
+pub fn load() -> Result<Vec<Event>> {
+    match fs::read_to_string(history_file_path()) {
+        Ok(content) => Ok(content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Per-class statistics over the events.
+pub fn class_stats(events: &[Event]) -> BTreeMap<String, ClassStats> {
+    let mut stats: BTreeMap<String, ClassStats> = BTreeMap::new();
+    let mut minimized_ms: BTreeMap<&str, u64> = BTreeMap::new();
+
+    for event in events {
+        let entry = stats.entry(event.class.clone()).or_default();
+        match event.event {
+            EventKind::Minimize => entry.minimized += 1,
+            EventKind::Restore => {
+                entry.restored += 1;
+                *minimized_ms.entry(&event.class).or_default() += event.time.saturating_sub(event.minimized_at);
+            }
+        }
+    }
+
+    for (class, entry) in stats.iter_mut() {
+        if entry.restored > 0 {
+            entry.average_minimized_secs = minimized_ms.get(class.as_str()).copied().unwrap_or(0) / entry.restored as u64 / 1000;
+        }
+    }
+
+    stats
+}
@@ -0,0 +1,42 @@
+use std::{process::Command, thread};
+
+use crate::{hyprland::follow_instance, window::MinimizedWindow};
+
+// Single-quote a value for sh, so titles can't break out of the command
+pub(crate) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Run a user hook for `event` ("minimize", "restore") in the background.
+/// `{address}`, `{class}`, `{title}` and `{workspace}` in the command are
+/// replaced by shell-quoted values, which are also exported as
+/// `MINHYPR_ADDRESS`, `MINHYPR_CLASS`, `MINHYPR_TITLE` and `MINHYPR_WORKSPACE`.
+pub fn run_hook(command: Option<&str>, event: &str, window: &MinimizedWindow) {
+    let Some(command) = command.filter(|command| !command.trim().is_empty()) else {
+        return;
+    };
+
+    let workspace = window.workspace.to_string();
+    let command = command
+        .replace("{address}", &shell_quote(&window.address))
+        .replace("{class}", &shell_quote(&window.class))
+        .replace("{title}", &shell_quote(&window.original_title))
+        .replace("{workspace}", &shell_quote(&workspace));
+
+    let child = follow_instance(&mut Command::new("sh"))
+        .args(["-c", &command])
+        .env("MINHYPR_EVENT", event)
+        .env("MINHYPR_ADDRESS", &window.address)
+        .env("MINHYPR_CLASS", &window.class)
+        .env("MINHYPR_TITLE", &window.original_title)
+        .env("MINHYPR_WORKSPACE", &workspace)
+        .spawn();
+
+    match child {
+        // Reap it when it exits, the daemon lives long enough to collect zombies
+        Ok(mut child) => {
+            thread::spawn(move || child.wait());
+        }
+        Err(e) => warn!("Failed to run {} hook: {}", event, e),
+    }
+}
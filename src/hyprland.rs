@@ -0,0 +1,300 @@
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{
+    env, fs,
+    io::{self, ErrorKind, Read, Result, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    sync::RwLock,
+};
+
+use crate::{
+    compositor::{Action, Compositor},
+    error::Error,
+};
+
+/// The Hyprland [`Compositor`], over its IPC socket or `hyprctl`.
+#[derive(Clone, Copy, Default)]
+pub struct Hyprland;
+
+/// A window as reported by `hyprctl clients -j` / `activewindow -j`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Client {
+    pub address: String,
+    pub at: (i32, i32),
+    pub size: (i32, i32),
+    pub workspace: WorkspaceRef,
+    pub floating: bool,
+    pub monitor: i64,
+    pub class: String,
+    pub title: String,
+    pub pid: i32,
+    // 0/1/2 on newer Hyprland, a bool on older versions
+    pub fullscreen: serde_json::Value,
+    #[serde(rename = "fullscreenMode")]
+    pub fullscreen_mode: i64,
+    // Window tags; dynamic ones (set with tagwindow) end with '*'
+    pub tags: Vec<String>,
+    // Members of the window's group (tabs), itself included; empty if not grouped
+    pub grouped: Vec<String>,
+}
+
+impl Client {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.trim_end_matches('*') == tag)
+    }
+
+    /// The other windows of its group.
+    pub fn group_members(&self) -> Vec<String> {
+        self.grouped.iter().filter(|a| **a != self.address).cloned().collect()
+    }
+
+    /// Fullscreen state: 0 none, 1 maximized, 2 fullscreen.
+    pub fn fullscreen_state(&self) -> u8 {
+        match &self.fullscreen {
+            // Older versions: fullscreenMode 0 is fullscreen, 1 maximize
+            serde_json::Value::Bool(true) if self.fullscreen_mode == 1 => 1,
+            serde_json::Value::Bool(true) => 2,
+            serde_json::Value::Number(state) => state.as_u64().unwrap_or(0).min(2) as u8,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceRef {
+    pub id: i64,
+    pub name: String,
+}
+
+/// A workspace as reported by `hyprctl workspaces -j` / `activeworkspace -j`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Workspace {
+    pub id: i64,
+    pub name: String,
+    pub monitor: String,
+    pub windows: u32,
+}
+
+/// A monitor as reported by `hyprctl monitors -j`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Monitor {
+    pub id: i64,
+    pub name: String,
+    pub focused: bool,
+    #[serde(rename = "activeWorkspace")]
+    pub active_workspace: WorkspaceRef,
+    /// Position in the layout and size in physical pixels.
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub scale: f64,
+    pub transform: i64,
+}
+
+impl Monitor {
+    /// Size in layout (logical) pixels, as window geometry is given.
+    pub fn logical_size(&self) -> (i32, i32) {
+        let scale = if self.scale > 0.0 { self.scale } else { 1.0 };
+        let (width, height) = ((self.width as f64 / scale).round() as i32, (self.height as f64 / scale).round() as i32);
+        // Odd transforms rotate by 90 or 270 degrees
+        if self.transform % 2 == 1 {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+}
+
+// The instance the daemon followed after a compositor restart; None until then
+static INSTANCE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Signature of the Hyprland instance to talk to: `$HYPRLAND_INSTANCE_SIGNATURE`,
+/// unless [`set_instance_signature`] switched to another one.
+pub fn instance_signature() -> Option<String> {
+    let instance = INSTANCE.read().unwrap_or_else(|e| e.into_inner());
+    instance.clone().or_else(|| env::var("HYPRLAND_INSTANCE_SIGNATURE").ok())
+}
+
+/// Talk to another Hyprland instance from now on, in every thread.
+pub fn set_instance_signature(signature: &str) {
+    *INSTANCE.write().unwrap_or_else(|e| e.into_inner()) = Some(signature.to_string());
+}
+
+/// Point a child process (hyprctl, hooks, minhypr workers) at the same instance.
+pub(crate) fn follow_instance(command: &mut Command) -> &mut Command {
+    if let Some(signature) = instance_signature() {
+        command.env("HYPRLAND_INSTANCE_SIGNATURE", signature);
+    }
+    command
+}
+
+// Send a request over Hyprland's command socket (what hyprctl does), avoiding
+// a process spawn. None if the socket isn't reachable.
+pub(crate) fn socket_request(request: &str) -> Option<String> {
+    let signature = instance_signature()?;
+    let mut stream = UnixStream::connect(runtime_dir().join(signature).join(".socket.sock")).ok()?;
+    stream.write_all(request.as_bytes()).ok()?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).ok()?;
+    trace!("{} -> {}", request, response.trim());
+    Some(response)
+}
+
+// Run hyprctl, which may not be installed when the socket isn't reachable either
+fn run_hyprctl(command: &mut Command) -> Result<Output> {
+    follow_instance(command).output().map_err(|e| match e.kind() {
+        ErrorKind::NotFound => Error::HyprlandUnavailable.into(),
+        _ => e,
+    })
+}
+
+// Deserialize a query's JSON; anything else (an error message from an older
+// or newer Hyprland) is an error rather than an empty answer
+fn parse_reply<T: DeserializeOwned>(args: &[&str], reply: &[u8]) -> Result<T> {
+    serde_json::from_slice(reply).map_err(|e| {
+        let reply = String::from_utf8_lossy(reply);
+        io::Error::new(ErrorKind::InvalidData, format!("unexpected reply to {}: {} ({})", args.join(" "), reply.trim(), e))
+    })
+}
+
+// Run a hyprctl query and deserialize its JSON output
+fn query<T: DeserializeOwned>(args: &[&str]) -> Result<T> {
+    if let Some(response) = socket_request(&format!("j/{}", args.join(" "))) {
+        return parse_reply(args, response.as_bytes());
+    }
+
+    debug!("Hyprland socket unreachable, running hyprctl {} -j", args.join(" "));
+    let output = run_hyprctl(Command::new("hyprctl").args(args).arg("-j"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(format!("hyprctl {} failed: {}", args.join(" "), stderr.trim())));
+    }
+
+    parse_reply(args, &output.stdout)
+}
+
+impl Hyprland {
+    pub fn new() -> Self {
+        Hyprland
+    }
+
+    /// Run `hyprctl dispatch <args>`, returning whether it succeeded.
+    pub fn dispatch(&self, args: &[&str]) -> Result<bool> {
+        if let Some(response) = socket_request(&format!("/dispatch {}", args.join(" "))) {
+            if response.trim() != "ok" {
+                debug!("dispatch {} failed: {}", args.join(" "), response.trim());
+            }
+            return Ok(response.trim() == "ok");
+        }
+
+        debug!("Hyprland socket unreachable, running hyprctl dispatch {}", args.join(" "));
+        let output = run_hyprctl(Command::new("hyprctl").arg("dispatch").args(args))?;
+
+        Ok(output.status.success())
+    }
+
+    /// Run several dispatches in one `hyprctl --batch` call.
+    pub fn dispatch_batch(&self, commands: &[String]) -> Result<bool> {
+        let batch = commands
+            .iter()
+            .map(|command| format!("dispatch {}", command))
+            .collect::<Vec<_>>()
+            .join(" ; ");
+
+        // One "ok" per command, separated by blank lines
+        if let Some(response) = socket_request(&format!("[[BATCH]]{}", batch)) {
+            let ok = response.split("\n\n").map(str::trim).all(|r| r.is_empty() || r == "ok");
+            if !ok {
+                debug!("batch {} failed: {}", batch, response.trim());
+            }
+            return Ok(ok);
+        }
+
+        debug!("Hyprland socket unreachable, running hyprctl --batch");
+
+        let output = run_hyprctl(Command::new("hyprctl").args(["--batch", &batch]))?;
+
+        Ok(output.status.success())
+    }
+}
+
+impl Compositor for Hyprland {
+    fn clients(&self) -> Result<Vec<Client>> {
+        query(&["clients"])
+    }
+
+    fn active_window(&self) -> Result<Option<Client>> {
+        let client: Client = query(&["activewindow"])?;
+
+        // Nothing is focused (e.g. empty workspace)
+        if client.address.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(client))
+    }
+
+    fn active_workspace(&self) -> Result<Workspace> {
+        query(&["activeworkspace"])
+    }
+
+    fn workspaces(&self) -> Result<Vec<Workspace>> {
+        query(&["workspaces"])
+    }
+
+    fn monitors(&self) -> Result<Vec<Monitor>> {
+        query(&["monitors"])
+    }
+
+    fn run(&self, actions: &[Action]) -> Result<bool> {
+        match actions {
+            [] => Ok(true),
+            [action] => {
+                let [dispatcher, arg] = action.dispatch_args();
+                self.dispatch(&[&dispatcher, &arg])
+            }
+            _ => {
+                let commands: Vec<String> = actions.iter().map(|action| action.dispatch_args().join(" ")).collect();
+                self.dispatch_batch(&commands)
+            }
+        }
+    }
+}
+
+pub fn runtime_dir() -> PathBuf {
+    match env::var("XDG_RUNTIME_DIR") {
+        Ok(dir) => Path::new(&dir).join("hypr"),
+        Err(_) => PathBuf::from("/tmp/hypr"),
+    }
+}
+
+// Find the event socket of the running Hyprland instance. If the instance we
+// were started under is gone (compositor restarted), fall back to the newest one.
+pub fn find_event_socket() -> Option<(String, PathBuf)> {
+    let runtime_dir = runtime_dir();
+
+    if let Some(signature) = instance_signature() {
+        let socket = runtime_dir.join(&signature).join(".socket2.sock");
+        if socket.exists() {
+            return Some((signature, socket));
+        }
+    }
+
+    fs::read_dir(&runtime_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join(".socket2.sock").exists())
+        .max_by_key(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+        .map(|entry| {
+            let signature = entry.file_name().to_string_lossy().to_string();
+            (signature, entry.path().join(".socket2.sock"))
+        })
+}
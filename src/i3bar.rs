@@ -0,0 +1,86 @@
+use std::{
+    env,
+    io::{self, BufRead, Result},
+    process::{Command, Stdio},
+    thread,
+};
+
+use serde_json::{json, Value};
+
+use crate::{events::watch_changes, manager::Manager, window::MinimizedWindow};
+
+/// Block name used in the status line and matched in click events.
+pub const BLOCK_NAME: &str = "minhypr";
+
+/// The i3bar status line (an array with our single block) for the given windows.
+pub fn status_line(windows: &[MinimizedWindow]) -> Value {
+    let count = windows.len();
+    let text = if count > 0 { format!("󰘸 {}", count) } else { "󰘸".to_string() };
+
+    json!([{
+        "name": BLOCK_NAME,
+        "full_text": text,
+        "short_text": count.to_string(),
+        "urgent": windows.iter().any(|w| w.urgent),
+    }])
+}
+
+// Click events arrive as an infinite JSON array, one element per line:
+// "[", "{...}", ",{...}"
+fn parse_click(line: &str) -> Option<Value> {
+    let line = line.trim().trim_start_matches(['[', ',']).trim();
+    serde_json::from_str(line).ok()
+}
+
+// Left click opens the restore menu, middle click restores everything and
+// right click restores the most recent window. Run as separate processes so
+// a menu never blocks the status updates.
+fn handle_click(click: &Value) {
+    if click["name"] != BLOCK_NAME {
+        return;
+    }
+
+    let args: &[&str] = match click["button"].as_u64() {
+        Some(1) => &["restore"],
+        Some(2) => &["restore-all"],
+        Some(3) => &["restore-last"],
+        _ => return,
+    };
+
+    let exe = env::current_exe().unwrap_or_else(|_| "minhypr".into());
+    Command::new(exe)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .spawn()
+        .ok();
+}
+
+/// Speak the i3bar protocol (swaybar and compatible bars) on stdout: a
+/// header, then a new status line whenever the minimized set changes.
+/// Click events are read from stdin.
+pub fn run(manager: &Manager) -> Result<()> {
+    println!("{}", json!({"version": 1, "click_events": true}));
+    println!("[");
+
+    thread::spawn(|| {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if let Some(click) = parse_click(&line) {
+                handle_click(&click);
+            }
+        }
+    });
+
+    let mut last = String::new();
+    watch_changes(manager, || {
+        let line = status_line(&manager.windows()?).to_string();
+        if line != last {
+            println!("{},", line);
+            last = line;
+        }
+        Ok(())
+    })
+}
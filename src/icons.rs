@@ -0,0 +1,179 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{config::Config, window::get_app_icon};
+
+// Largest first, the menus scale icons down
+const ICON_SIZES: &[&str] = &["scalable", "256x256", "128x128", "96x96", "64x64", "48x48", "32x32"];
+const ICON_EXTENSIONS: &[&str] = &["svg", "png", "xpm"];
+
+// $XDG_DATA_HOME followed by $XDG_DATA_DIRS, plus the Flatpak export dirs
+fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let home = env::var("HOME").unwrap_or_default();
+
+    match env::var("XDG_DATA_HOME") {
+        Ok(dir) if !dir.is_empty() => dirs.push(PathBuf::from(dir)),
+        _ => dirs.push(Path::new(&home).join(".local/share")),
+    }
+
+    let system_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| String::from("/usr/local/share:/usr/share"));
+    dirs.extend(system_dirs.split(':').map(PathBuf::from));
+
+    dirs.push(Path::new(&home).join(".local/share/flatpak/exports/share"));
+    dirs.push(PathBuf::from("/var/lib/flatpak/exports/share"));
+
+    dirs
+}
+
+// Desktop entries, including the ones in vendor subdirectories
+fn desktop_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if path.is_dir() {
+            desktop_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "desktop") {
+            files.push(path);
+        }
+    }
+}
+
+// Value of a key in the [Desktop Entry] group
+fn desktop_entry_value(content: &str, key: &str) -> Option<String> {
+    let mut in_entry = false;
+
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+        } else if in_entry {
+            if let Some((name, value)) = line.split_once('=') {
+                if name.trim() == key {
+                    return Some(value.trim().to_string());
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// Icon name from the desktop entry of the window class: StartupWMClass first,
+// then the file name (org.gnome.Nautilus.desktop also matches "nautilus")
+fn desktop_icon_name(class_name: &str) -> Option<String> {
+    let mut files = Vec::new();
+    for dir in data_dirs() {
+        desktop_files(&dir.join("applications"), &mut files);
+    }
+
+    let entries: Vec<(PathBuf, String)> = files
+        .into_iter()
+        .filter_map(|path| fs::read_to_string(&path).ok().map(|content| (path, content)))
+        .collect();
+
+    let by_wm_class = entries.iter().find(|(_, content)| {
+        desktop_entry_value(content, "StartupWMClass").is_some_and(|wm_class| wm_class.eq_ignore_ascii_case(class_name))
+    });
+
+    let by_file_name = || {
+        entries.iter().find(|(path, _)| {
+            let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            let last = stem.rsplit('.').next().unwrap_or_default();
+            stem.eq_ignore_ascii_case(class_name) || last.eq_ignore_ascii_case(class_name)
+        })
+    };
+
+    by_wm_class
+        .or_else(by_file_name)
+        .and_then(|(_, content)| desktop_entry_value(content, "Icon"))
+}
+
+// Icon theme configured for GTK, searched before hicolor
+fn gtk_icon_theme() -> Option<String> {
+    let home = env::var("HOME").ok()?;
+    let content = fs::read_to_string(Path::new(&home).join(".config/gtk-3.0/settings.ini")).ok()?;
+
+    content
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == "gtk-icon-theme-name")
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+}
+
+// Resolve an icon name to a file in the icon themes or pixmaps
+fn find_icon_file(icon_name: &str) -> Option<PathBuf> {
+    if Path::new(icon_name).is_absolute() {
+        return Path::new(icon_name).exists().then(|| PathBuf::from(icon_name));
+    }
+
+    let mut themes: Vec<String> = gtk_icon_theme().into_iter().collect();
+    themes.push(String::from("hicolor"));
+
+    let dirs = data_dirs();
+
+    for theme in &themes {
+        for dir in &dirs {
+            for size in ICON_SIZES {
+                for extension in ICON_EXTENSIONS {
+                    let path = dir
+                        .join("icons")
+                        .join(theme)
+                        .join(size)
+                        .join("apps")
+                        .join(format!("{}.{}", icon_name, extension));
+                    if path.exists() {
+                        return Some(path);
+                    }
+                }
+            }
+        }
+    }
+
+    dirs.iter()
+        .flat_map(|dir| ICON_EXTENSIONS.iter().map(move |ext| dir.join("pixmaps").join(format!("{}.{}", icon_name, ext))))
+        .find(|path| path.exists())
+}
+
+/// Path of the icon file for a window class, looked up through the freedesktop
+/// desktop entries and icon themes.
+pub fn find_app_icon(class_name: &str) -> Option<String> {
+    if class_name.is_empty() {
+        return None;
+    }
+
+    // Many apps have no StartupWMClass but ship an icon named after the class
+    desktop_icon_name(class_name)
+        .and_then(|name| find_icon_file(&name))
+        .or_else(|| find_icon_file(&class_name.to_lowercase()))
+        .map(|path| path.to_string_lossy().to_string())
+}
+
+// Expand a leading ~ in configured icon paths
+pub(crate) fn expand_home(path: &str) -> String {
+    match path.strip_prefix("~/") {
+        Some(rest) => format!("{}/{}", env::var("HOME").unwrap_or_default(), rest),
+        None => path.to_string(),
+    }
+}
+
+/// Glyph and icon file for a window class. Icons configured in `[icons]`
+/// (a glyph, or a path to an image) take precedence over the desktop entry
+/// lookup and the built-in glyph table.
+pub fn app_icon(config: &Config, class_name: &str) -> (String, Option<String>) {
+    let class_name = config.resolve_class(class_name);
+
+    match config.icon_for(class_name) {
+        Some(icon) if icon.starts_with('/') || icon.starts_with("~/") => {
+            (get_app_icon(class_name), Some(expand_home(icon)))
+        }
+        Some(glyph) => (glyph.to_string(), find_app_icon(class_name)),
+        None => (get_app_icon(class_name), find_app_icon(class_name)),
+    }
+}
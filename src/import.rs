@@ -0,0 +1,107 @@
+use std::{
+    fs,
+    io::{self, Result},
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use crate::{config, hooks::shell_quote, setup::hypr_config_dir};
+
+/// pyprland's config: `pyprland.toml`, or `pyprland.json` of older versions.
+pub fn pyprland_config_path() -> PathBuf {
+    let toml = hypr_config_dir().join("pyprland.toml");
+    let json = hypr_config_dir().join("pyprland.json");
+    if !toml.exists() && json.exists() {
+        json
+    } else {
+        toml
+    }
+}
+
+fn read_pyprland(path: &Path) -> Result<Value> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not read {}: {}", path.display(), e)))?;
+    if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&content).map_err(io::Error::other)
+    } else {
+        config::parse_toml(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+    }
+}
+
+// The binds running `pypr toggle <name>`, with that part replaced by `command`
+fn translate_binds(hyprland_conf: &str, name: &str, command: &str) -> Vec<String> {
+    let pattern = format!("pypr toggle {}", name);
+    hyprland_conf
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("bind"))
+        .filter_map(|line| {
+            let start = line.find(&pattern)?;
+            let rest = &line[start + pattern.len()..];
+            // Not a scratchpad whose name only starts with this one
+            if rest.chars().next().is_some_and(|c| !c.is_whitespace() && c != '&' && c != ';') {
+                return None;
+            }
+            Some(format!("{}{}{}", &line[..start], command, rest))
+        })
+        .collect()
+}
+
+// TOML key, quoted unless bare
+fn toml_key(name: &str) -> String {
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        name.to_string()
+    } else {
+        serde_json::to_string(name).unwrap_or_default()
+    }
+}
+
+/// minhypr's version of pyprland's scratchpads: `[scratchpads.*]` tables for
+/// config.toml, and binds running `minhypr scratch` in place of the `pypr
+/// toggle` ones in hyprland.conf, on the same keys.
+pub fn pyprland(path: &Path) -> Result<String> {
+    let pyprland = read_pyprland(path)?;
+    let scratchpads = pyprland["scratchpads"].as_object().filter(|pads| !pads.is_empty()).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("{} defines no scratchpads", path.display()))
+    })?;
+    let hyprland_conf = fs::read_to_string(hypr_config_dir().join("hyprland.conf")).unwrap_or_default();
+
+    let mut tables = String::new();
+    let mut binds = String::new();
+    for (name, pad) in scratchpads {
+        // pyprland can also follow the launched process; minhypr needs the class
+        if pad["class"].as_str().is_none_or(str::is_empty) {
+            warn!("Scratchpad {} has no class, skipping it", name);
+            tables.push_str(&format!("\n# {}: skipped, set `class` to its window class (see `hyprctl clients`)\n", name));
+            continue;
+        }
+
+        tables.push_str(&format!("\n[scratchpads.{}]\n", toml_key(name)));
+        for key in ["command", "class", "size", "position"] {
+            if let Some(value) = pad[key].as_str() {
+                tables.push_str(&format!("{} = {}\n", key, serde_json::to_string(value).unwrap_or_default()));
+            }
+        }
+        if pad["lazy"].as_bool() == Some(false) {
+            tables.push_str("# pyprland started it hidden at login; minhypr launches it on the first toggle\n");
+        }
+
+        let command = format!("minhypr scratch {}", if toml_key(name) == *name { name.clone() } else { shell_quote(name) });
+        let translated = translate_binds(&hyprland_conf, name, &command);
+        if translated.is_empty() {
+            binds.push_str(&format!("# bind = $mainMod, KEY, exec, {}\n", command));
+        }
+        for bind in translated {
+            binds.push_str(&bind);
+            binds.push('\n');
+        }
+    }
+
+    Ok(format!(
+        "# Imported from {} by `minhypr import pyprland`.\n\n# Add to ~/.config/minhypr/config.toml:\n{}\n# And to hyprland.conf, in place of the `pypr toggle` binds:\n\n{}",
+        path.display(),
+        tables,
+        binds
+    ))
+}
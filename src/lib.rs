@@ -0,0 +1,61 @@
+/*
+ * Minhypr - A window minimization manager for Hyprland
+ *
+ * Library crate: window model, state storage, Hyprland IPC and preview
+ * capture, so other tools can embed minhypr instead of shelling out to it.
+ */
+
+// Declared first so its macros are visible in every other module
+#[macro_use]
+pub mod log;
+
+/// Silence informational messages printed by the library (warnings and
+/// errors still show).
+pub fn set_quiet(quiet: bool) {
+    log::set_level(if quiet { log::Level::Warn } else { log::Level::Info });
+}
+
+pub mod audio;
+pub mod completions;
+pub mod compositor;
+pub mod config;
+pub mod control;
+pub mod daemon;
+pub mod dbus;
+pub mod doctor;
+pub mod effects;
+pub mod error;
+pub mod events;
+pub mod eww;
+pub mod history;
+pub mod hooks;
+pub mod hyprland;
+pub mod i3bar;
+pub mod icons;
+pub mod import;
+pub mod lock;
+pub mod manager;
+pub mod menu;
+pub mod mock;
+pub mod notify;
+pub mod placeholder;
+pub mod preview;
+pub mod refresh;
+pub mod rofi;
+pub mod rpc;
+pub mod setup;
+pub mod state;
+pub mod status;
+pub mod suspend;
+pub mod theme;
+pub mod tui;
+pub mod waybar;
+pub mod window;
+
+pub use compositor::Compositor;
+pub use config::Config;
+pub use error::Error;
+pub use hyprland::{Client, Hyprland};
+pub use manager::Manager;
+pub use state::StateStore;
+pub use window::MinimizedWindow;
@@ -0,0 +1,104 @@
+use std::{env, fs, io::Result, os::unix::fs::MetadataExt, process::Command, thread, time::Duration};
+
+use crate::{dbus::Bus, manager::Manager};
+
+const LOGIND: &str = "org.freedesktop.login1";
+const SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+
+// How often the lock state is checked between logind signals (hyprlock
+// started without `loginctl lock-session`), or at all without logind
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+// hyprlock holds the session lock while it runs
+fn hyprlock_running() -> bool {
+    let uid = fs::metadata("/proc/self").map(|m| m.uid()).unwrap_or_default();
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        entry.metadata().is_ok_and(|m| m.uid() == uid)
+            && fs::read_to_string(entry.path().join("comm")).is_ok_and(|comm| comm.trim() == "hyprlock")
+    })
+}
+
+fn session_id() -> String {
+    env::var("XDG_SESSION_ID").unwrap_or_else(|_| String::from("self"))
+}
+
+// logind's LockedHint, set by lockers that report to it (and by
+// `loginctl lock-session` aware ones)
+fn locked_hint() -> bool {
+    Command::new("loginctl")
+        .args(["show-session", &session_id(), "--property=LockedHint", "--value"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "yes")
+}
+
+/// Whether the session is locked: hyprlock is running or logind says so.
+pub fn is_session_locked() -> bool {
+    hyprlock_running() || locked_hint()
+}
+
+// Our logind session on the system bus, subscribed to its Lock and Unlock
+// signals and property changes
+struct Session {
+    bus: Bus,
+    path: String,
+}
+
+impl Session {
+    fn connect() -> Result<Self> {
+        let mut bus = Bus::connect_system()?;
+        // "auto" is the caller's session, or the user's graphical one
+        let id = env::var("XDG_SESSION_ID").unwrap_or_else(|_| String::from("auto"));
+        let path = bus.call_string(LOGIND, "/org/freedesktop/login1", "org.freedesktop.login1.Manager", "GetSession", &[&id])?;
+        bus.add_match(&format!("type='signal',sender='{}',path='{}'", LOGIND, path))?;
+        bus.set_read_timeout(Some(POLL_INTERVAL))?;
+        Ok(Session { bus, path })
+    }
+
+    // Wait for a signal or the poll interval, then read the lock state
+    fn wait(&mut self) -> Result<bool> {
+        if let Some(signal) = self.bus.next_signal()? {
+            debug!("logind: {}", signal);
+            // The locker starts (or exits) right after Lock (or Unlock)
+            thread::sleep(Duration::from_millis(500));
+        }
+        Ok(hyprlock_running() || self.bus.bool_property(LOGIND, &self.path, SESSION_INTERFACE, "LockedHint")?)
+    }
+}
+
+/// Follow the lock state, minimizing windows when the session locks and
+/// restoring them when it unlocks. Runs forever, in the daemon.
+pub fn watch_lock() {
+    let mut locked = Manager::new().lock_active().unwrap_or(false);
+    let mut session = Session::connect()
+        .inspect_err(|e| warn!("Could not follow logind, polling the lock state instead: {}", e))
+        .ok();
+
+    loop {
+        let now_locked = match session.as_mut().map(Session::wait) {
+            Some(Ok(now_locked)) => now_locked,
+            Some(Err(e)) => {
+                warn!("Lost logind, polling the lock state instead: {}", e);
+                session = None;
+                continue;
+            }
+            None => {
+                thread::sleep(POLL_INTERVAL);
+                is_session_locked()
+            }
+        };
+        if now_locked == locked {
+            continue;
+        }
+        locked = now_locked;
+
+        let manager = Manager::new();
+        let result = if locked { manager.lock_session() } else { manager.unlock_session() };
+        match result {
+            Ok(windows) => debug!("Session {}: {} windows", if locked { "locked" } else { "unlocked" }, windows.len()),
+            Err(e) => warn!("Could not {} windows: {}", if locked { "hide" } else { "restore" }, e),
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::{
+    env, fmt,
+    fs::{self, File, OpenOptions},
+    io::{Result, Write},
+    path::PathBuf,
+    process,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Deserialize;
+
+// The log file is moved to minhypr.log.1 once it grows past this
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+/// Severity of a message, most severe first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Level::Error,
+            1 => Level::Warn,
+            2 => Level::Info,
+            3 => Level::Debug,
+            _ => Level::Trace,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+// Set by --quiet / --verbose
+static CONSOLE_LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+
+struct LogFile {
+    path: PathBuf,
+    level: Level,
+    file: File,
+}
+
+static LOG_FILE: Mutex<Option<LogFile>> = Mutex::new(None);
+
+/// Most verbose level printed to the terminal.
+pub fn set_level(level: Level) {
+    CONSOLE_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn level() -> Level {
+    Level::from_u8(CONSOLE_LEVEL.load(Ordering::Relaxed))
+}
+
+/// `$XDG_STATE_HOME/minhypr/minhypr.log`, falling back to `~/.local/state`.
+pub fn log_file_path() -> PathBuf {
+    state_home().join("minhypr.log")
+}
+
+/// `$XDG_STATE_HOME/minhypr`, for what outlives a session (log, history).
+pub fn state_home() -> PathBuf {
+    let state_home = env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env::var("HOME").unwrap_or_default()).join(".local/state"));
+    state_home.join("minhypr")
+}
+
+/// Also append messages up to `level` to the log file, which keeps one
+/// rotated copy. Keybind-invoked runs have no terminal, so this is where
+/// their errors end up.
+pub fn open_log_file(level: Level) -> Result<PathBuf> {
+    let path = log_file_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    // Append mode keeps each line whole when several minhypr processes log at once
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    *LOG_FILE.lock().unwrap_or_else(|e| e.into_inner()) = Some(LogFile { path: path.clone(), level, file });
+
+    Ok(path)
+}
+
+// Move a full log file aside and start a new one
+fn rotate(log: &mut LogFile) -> Result<()> {
+    if log.file.metadata()?.len() < MAX_LOG_SIZE {
+        return Ok(());
+    }
+
+    let rotated = log.path.with_extension("log.1");
+    fs::rename(&log.path, rotated)?;
+    log.file = OpenOptions::new().create(true).append(true).open(&log.path)?;
+    Ok(())
+}
+
+// UTC "YYYY-MM-DDTHH:MM:SS.mmmZ" from the system clock
+fn timestamp() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = now.as_secs();
+    let (days, day_secs) = (secs / 86400, secs % 86400);
+
+    // Days since the epoch to a civil date (Howard Hinnant's algorithm)
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        day_secs / 3600,
+        day_secs % 3600 / 60,
+        day_secs % 60,
+        now.subsec_millis()
+    )
+}
+
+#[doc(hidden)]
+pub fn write(level: Level, target: &str, args: fmt::Arguments) {
+    let console = level <= self::level();
+    let mut guard = LOG_FILE.lock().unwrap_or_else(|e| e.into_inner());
+    let file = guard.as_mut().filter(|log| level <= log.level);
+    if !console && file.is_none() {
+        return;
+    }
+
+    let message = args.to_string();
+
+    // The terminal gets the bare message; info is regular output on stdout
+    if console {
+        match level {
+            Level::Info => println!("{}", message),
+            Level::Error => eprintln!("Error: {}", message),
+            Level::Warn => eprintln!("{}", message),
+            Level::Debug | Level::Trace => eprintln!("{}: {}", level.label().to_lowercase(), message),
+        }
+    }
+
+    if let Some(log) = file {
+        rotate(log).ok();
+        let line = format!("{} {:5} [{}] {}: {}\n", timestamp(), level.label(), process::id(), target, message);
+        log.file.write_all(line.as_bytes()).ok();
+    }
+}
+
+/// Log an error: printed to stderr with an "Error:" prefix.
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Error, module_path!(), format_args!($($arg)*))
+    };
+}
+
+/// Log a warning to stderr.
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Warn, module_path!(), format_args!($($arg)*))
+    };
+}
+
+/// Informational output on stdout, silenced by --quiet.
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Info, module_path!(), format_args!($($arg)*))
+    };
+}
+
+/// Diagnostics shown with --verbose.
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Debug, module_path!(), format_args!($($arg)*))
+    };
+}
+
+/// Very chatty diagnostics (every Hyprland request), shown with -vv.
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Trace, module_path!(), format_args!($($arg)*))
+    };
+}
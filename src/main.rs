@@ -2,916 +2,1130 @@
  * Minhypr - A window minimization manager for Hyprland
  */
 
- use std::{
-    collections::HashMap,
+use std::{
     env,
-    fs::{self},
-    io::{self, Result, Write},
-    path::Path,
+    io::{self, Result},
+    os::unix::process::CommandExt,
+    path::PathBuf,
     process::Command,
+    time::Duration,
 };
-use serde::{Deserialize, Serialize};
-use lazy_static::lazy_static;
-
-// Get HOME and define constants based on it
-fn get_base_dirs() -> (String, String, String) {
-    let cache_dir = String::from("/tmp/minhypr-state");
-    let cache_file = format!("{}/windows.json", cache_dir);
-    let preview_dir = String::from("/tmp/minhypr-previews");
-    
-    (cache_dir, cache_file, preview_dir)
-}
 
-// Global constants
-lazy_static! {
-    static ref DIRS: (String, String, String) = get_base_dirs();
-}
+use clap::{ArgAction, CommandFactory, Parser, Subcommand, ValueEnum};
+use regex::Regex;
+use minhypr::{
+    completions::{self, Shell},
+    config::{self, Launcher, RestoreTarget, RofiLayout, RofiTheme},
+    control, daemon, debug, doctor, error, events, eww, history, i3bar, import, info,
+    log::{self, Level},
+    menu, notify, rofi, rpc, setup, status, tui, warn, waybar,
+    window::{
+        format_age, format_window, most_recent_window, oldest_window, search_windows, window_age, windows_by_recency,
+        MinimizedWindow,
+    },
+    Compositor, Error, Manager,
+};
 
-// Access constants
-fn cache_dir() -> &'static str {
-    &DIRS.0
-}
+#[derive(Parser)]
+#[command(name = "minhypr", version, about = "A window minimization manager for Hyprland")]
+struct Cli {
+    /// Print machine-readable JSON output where supported
+    #[arg(long, global = true)]
+    json: bool,
 
-fn cache_file() -> &'static str {
-    &DIRS.1
-}
+    /// Suppress informational messages
+    #[arg(short, long, global = true)]
+    quiet: bool,
 
-fn preview_dir() -> &'static str {
-    &DIRS.2
-}
+    /// Print diagnostics to stderr (-vv for every Hyprland request)
+    #[arg(short, long, global = true, action = ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
 
-const ICONS: &[(&str, &str)] = &[
-    ("firefox", ""),
-    ("Alacritty", ""),
-    ("kitty", ""),
-    ("discord", "󰙯"),
-    ("Steam", ""),
-    ("chromium", ""),
-    ("chrome", ""),
-    ("code", "󰨞"),
-    ("spotify", ""),
-    ("default", "󰖲"),
-];
-
-#[derive(Clone, Serialize, Deserialize)]
-struct MinimizedWindow {
-    address: String,
-    display_title: String,
-    class: String,
-    original_title: String,
-    preview_path: Option<String>,
-    icon: String,
-    workspace: i32,
-}
+    /// Also log to $XDG_STATE_HOME/minhypr/minhypr.log (or set log_file in the config)
+    #[arg(long, global = true)]
+    log_file: bool,
 
-fn get_app_icon(class_name: &str) -> String {
-    ICONS
-        .iter()
-        .find(|(name, _)| class_name.to_lowercase().contains(&name.to_lowercase()))
-        .map(|(_, icon)| *icon)
-        .unwrap_or(ICONS.last().unwrap().1)
-        .to_string()
+    #[command(subcommand)]
+    command: Commands,
 }
 
-fn capture_window_preview(window_id: &str, geometry: &str) -> Result<String> {
-    let preview_path = format!("{}/{}.png", preview_dir(), window_id);
-    let thumb_path = format!("{}/{}.thumb.png", preview_dir(), window_id);
-    let icon_path = format!("{}/{}.icon.png", preview_dir(), window_id);
-
-    // Capture screenshot with grim
-    Command::new("grim")
-        .args(["-g", geometry, &preview_path])
-        .output()?;
-
-    // Create a thumbnail for the menu
-    Command::new("convert")
-        .args([
-            &preview_path,
-            "-resize",
-            "200x150^",
-            "-gravity",
-            "center",
-            "-extent",
-            "200x150",
-            "-quality", "90",
-            &thumb_path,
-        ])
-        .output()?;
-        
-    // Create a smaller icon for Rofi
-    Command::new("convert")
-        .args([
-            &preview_path,
-            "-resize",
-            "64x64^",
-            "-gravity",
-            "center",
-            "-extent",
-            "64x64",
-            "-quality", "90",
-            &icon_path,
-        ])
-        .output()?;
-
-    // Save storage space by removing the original
-    fs::remove_file(&preview_path)?;
-
-    // Return path to thumbnail
-    Ok(thumb_path)
+#[derive(Subcommand)]
+enum Commands {
+    /// Minimize the active window, or the windows selected by the options
+    Minimize {
+        /// Minimize the window with this address
+        #[arg(long, conflicts_with_all = ["class", "title"])]
+        address: Option<String>,
+        /// Click the window to minimize (needs slurp)
+        #[arg(long, conflicts_with_all = ["address", "class", "title"])]
+        pick: bool,
+        /// Minimize all windows whose class matches this regex
+        #[arg(long)]
+        class: Option<String>,
+        /// Minimize all windows whose title matches this regex
+        #[arg(long)]
+        title: Option<String>,
+        /// Restore automatically after this long (e.g. 25m, 1h30m, 90s; needs the daemon)
+        #[arg(long = "for", value_name = "DURATION", value_parser = parse_duration)]
+        snooze: Option<Duration>,
+    },
+    /// Pick windows to minimize from a menu of the open ones, with live thumbnails in Rofi
+    MinimizeMenu {
+        /// Menu to use instead of the configured `launcher`
+        #[arg(long, value_enum)]
+        launcher: Option<Launcher>,
+    },
+    /// Minimize every window on the current workspace
+    MinimizeAll,
+    /// Minimize every window on the current workspace except the active one
+    MinimizeOthers,
+    /// Minimize every window on the focused monitor, or restore them if run again
+    ShowDesktop,
+    /// Restore a window, or show the restore menu when no address or pattern is given
+    Restore {
+        /// Window address, or a class for its most recently minimized window
+        #[arg(conflicts_with_all = ["class", "title", "index"])]
+        address: Option<String>,
+        /// Restore the Nth minimized window, most recently minimized first (see `list --numbered`)
+        #[arg(long, conflicts_with_all = ["class", "title"], value_parser = clap::value_parser!(u32).range(1..))]
+        index: Option<u32>,
+        /// Restore all minimized windows whose class matches this regex
+        #[arg(long)]
+        class: Option<String>,
+        /// Restore all minimized windows whose title matches this regex
+        #[arg(long)]
+        title: Option<String>,
+        /// Only restore the most recently minimized matching window
+        #[arg(long)]
+        first: bool,
+        /// Menu shown when no window is given (defaults to the config, then rofi)
+        #[arg(long, value_enum)]
+        launcher: Option<Launcher>,
+        /// Bring the window to the current workspace instead of its original one
+        #[arg(long)]
+        here: bool,
+        /// Restore the window to this workspace (id or name) instead of its original one
+        #[arg(long, value_name = "WORKSPACE", requires = "address", conflicts_with = "here")]
+        to_workspace: Option<String>,
+        /// Restore in the background without switching focus
+        #[arg(long)]
+        no_focus: bool,
+    },
+    /// Minimize the active (or given) window, or restore it if already minimized
+    Toggle {
+        address: Option<String>,
+        /// Toggle a class like a scratchpad: restore its minimized window, or
+        /// minimize its open one
+        #[arg(long, conflicts_with = "address")]
+        class: Option<String>,
+        /// With --class, launch this when no window of the class is open
+        #[arg(long, value_name = "CMD", requires = "class")]
+        exec: Option<String>,
+    },
+    /// Show or hide a dropdown app from the config's [scratchpads] table
+    Scratch { name: String },
+    /// Restore the most recently minimized window and minimize the active one
+    Cycle,
+    /// Minimize the last restored window again
+    Undo,
+    /// Briefly show a minimized window without restoring it
+    Peek {
+        address: String,
+        /// Restore the window for this many seconds, then minimize it again
+        /// (by default the special workspace is shown as an overlay)
+        #[arg(long)]
+        seconds: Option<u64>,
+    },
+    /// Restore all minimized windows
+    RestoreAll,
+    /// Restore the most recently minimized window
+    RestoreLast {
+        /// Restore the oldest minimized window instead
+        #[arg(long)]
+        oldest: bool,
+        /// Bring the window to the current workspace instead of its original one
+        #[arg(long)]
+        here: bool,
+        /// Restore in the background without switching focus
+        #[arg(long)]
+        no_focus: bool,
+    },
+    /// List minimized windows
+    List {
+        /// Output format (--json is a shorthand for --format json)
+        #[arg(long, value_enum, default_value_t = ListFormat::Plain)]
+        format: ListFormat,
+        /// Most recently minimized first, numbered for `restore --index`
+        #[arg(long)]
+        numbered: bool,
+    },
+    /// Fuzzy-search the minimized windows by class and title, best matches first
+    Find {
+        #[arg(required = true)]
+        query: Vec<String>,
+        /// Restore the best match instead of printing the matches
+        #[arg(long)]
+        restore: bool,
+        /// With --restore, restore every match
+        #[arg(long, requires = "restore")]
+        all: bool,
+        /// Bring the window to the current workspace instead of its original one
+        #[arg(long, requires = "restore")]
+        here: bool,
+        /// Restore in the background without switching focus
+        #[arg(long, requires = "restore")]
+        no_focus: bool,
+    },
+    /// Show status for waybar
+    #[command(visible_alias = "show")]
+    Status {
+        /// Format of each tooltip line ({icon}, {class}, {title}, {workspace}, {monitor}, {address}, {age});
+        /// defaults to the config's title_format and age_format
+        #[arg(long)]
+        tooltip_format: Option<String>,
+        /// Bar protocol or preset; i3bar keeps running and handles click events on stdin
+        #[arg(long, value_enum, default_value_t = StatusProtocol::Waybar)]
+        protocol: StatusProtocol,
+        /// Plain status line template ({count}, {classes}, {icons}) instead of the preset
+        #[arg(long)]
+        format: Option<String>,
+        /// One Waybar entry per minimized window, most recent first, as a JSON array
+        #[arg(long, conflicts_with_all = ["format", "protocol"])]
+        per_window: bool,
+        /// Only the entry of the Nth most recent window (from 1), for a custom module per window
+        #[arg(long, requires = "per_window", value_parser = clap::value_parser!(u32).range(1..))]
+        slot: Option<u32>,
+        /// Keep running and print the status again whenever it changes
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Keep running and print a waybar status line whenever the minimized set changes
+    Watch {
+        /// Format of each tooltip line ({icon}, {class}, {title}, {workspace}, {monitor}, {address}, {age});
+        /// defaults to the config's title_format and age_format
+        #[arg(long)]
+        tooltip_format: Option<String>,
+    },
+    /// Print one JSON event per line (minimized, restored, closed, count_changed)
+    Listen,
+    /// Print the minimized windows as JSON for eww widgets
+    Eww {
+        /// Keep running and print a new line whenever the list changes (for deflisten)
+        #[arg(long, conflicts_with = "yuck")]
+        watch: bool,
+        /// Print a sample yuck widget instead
+        #[arg(long)]
+        yuck: bool,
+    },
+    /// Rebuild state from the windows in the minimized special workspaces
+    Resync,
+    /// Minimize a group of windows under a name (lists the stashes without a name)
+    Stash {
+        name: Option<String>,
+        /// Only windows whose class matches this regex (default: the active workspace)
+        #[arg(long)]
+        class: Option<String>,
+        /// Only windows whose title matches this regex
+        #[arg(long)]
+        title: Option<String>,
+    },
+    /// Restore the windows of a stash
+    Unstash { name: String },
+    /// Protect a window (address) or class from bulk minimizes: minimize-all,
+    /// show-desktop, focus mode, stashes, idle and lock
+    Pin {
+        /// Window address (0x...) or class; default: the active window
+        target: Option<String>,
+        /// List the pinned windows and classes
+        #[arg(long, conflicts_with = "target")]
+        list: bool,
+    },
+    /// Stop protecting a window or class
+    Unpin {
+        /// Window address (0x...) or class; default: the active window
+        target: Option<String>,
+    },
+    /// Park a whole workspace: minimize all its windows and later bring them back
+    Workspace {
+        #[command(subcommand)]
+        action: WorkspaceAction,
+    },
+    /// Focus mode: minimize the configured distraction classes, or bring them back
+    Focus {
+        #[arg(value_enum)]
+        state: FocusState,
+    },
+    /// Hide the `idle_classes` windows while idle (hypridle on-timeout / on-resume)
+    Idle {
+        #[arg(value_enum)]
+        event: IdleEvent,
+    },
+    /// Minimize the `lock_classes` windows (all when unset) for a locked session
+    Lock,
+    /// Restore the windows minimized by `lock`
+    Unlock,
+    /// Check dependencies, the Hyprland connection and the state directories
+    Doctor,
+    /// Print the shell completion script (e.g. `minhypr completions fish > ~/.config/fish/completions/minhypr.fish`)
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print the minimized windows and classes for shell completion
+    #[command(hide = true)]
+    CompleteWindows,
+    /// Minimize counts and times per class, and how long windows have been minimized
+    Stats,
+    /// Clear the minimized list, groups and preview files
+    #[command(visible_alias = "reset")]
+    Purge {
+        /// Only delete the preview files (screenshots and thumbnails)
+        #[arg(long, conflicts_with = "restore")]
+        previews: bool,
+        /// First bring back every window still in the minimized workspaces
+        #[arg(long)]
+        restore: bool,
+    },
+    /// Delete preview files no minimized window uses anymore
+    Gc {
+        /// Also delete previews older than this (e.g. "12h" or "2d")
+        #[arg(long, value_parser = parse_duration)]
+        max_age: Option<Duration>,
+    },
+    /// Capture the thumbnails of minimized windows again (needs grim-hyprland)
+    RefreshPreviews {
+        /// Only refresh this window
+        address: Option<String>,
+    },
+    /// Track windows placed in the minimized workspace by something else
+    Adopt {
+        /// Only adopt this window
+        address: Option<String>,
+    },
+    /// Stop tracking a minimized window, leaving it where it is
+    Disown { address: String },
+    /// Close a minimized window (or one picked from a menu) and delete its previews
+    Close {
+        /// Window address, or a class for its most recently minimized window
+        address: Option<String>,
+        /// Menu to pick the window with, instead of the configured `launcher`
+        #[arg(long, value_enum, conflicts_with = "address")]
+        launcher: Option<Launcher>,
+    },
+    /// Watch Hyprland events and keep state in sync
+    Daemon {
+        /// Also serve org.minhypr.Manager on the session bus
+        #[arg(long)]
+        dbus: bool,
+    },
+    /// Send a command to the running daemon over its control socket
+    Ctl {
+        /// ping, list, minimize, minimize_all, minimize_others, show_desktop, toggle,
+        /// restore, restore_last, restore_all, close, cycle, undo, refresh or subscribe
+        command: String,
+        /// Window address for minimize, toggle, restore and close
+        address: Option<String>,
+    },
+    /// Print the control socket's JSON-RPC protocol as an OpenRPC document
+    Schema,
+    /// Full-screen grid of minimized window thumbnails; the selected one is restored
+    Expose,
+    /// Always-visible dock with the minimized windows (runs minhypr-dock)
+    Dock {
+        /// Screen edge the dock is anchored to
+        #[arg(long, default_value = "bottom", value_parser = ["top", "bottom", "left", "right"])]
+        edge: String,
+    },
+    /// System tray icon listing the minimized windows (runs minhypr-tray)
+    Tray,
+    /// Interactive terminal picker to restore or close minimized windows
+    Tui,
+    /// Pick a window to restore with any dmenu-compatible command
+    Pick {
+        /// Menu command reading lines on stdin and printing the selection (e.g. "fzf", "tofi")
+        #[arg(long, value_name = "CMD")]
+        stdin_menu: String,
+        /// Format of each line ({icon}, {class}, {title}, {workspace}, {monitor}, {address}, {age})
+        #[arg(long, default_value = menu::DEFAULT_MENU_FORMAT)]
+        format: String,
+    },
+    /// Configure integration with Rofi
+    SetupRofi {
+        /// Colors of the generated theme (default: `rofi_theme` from the config)
+        #[arg(long, value_enum)]
+        theme: Option<RofiTheme>,
+        /// Arrangement of the menu (default: `rofi_layout` from the config)
+        #[arg(long, value_enum)]
+        layout: Option<RofiLayout>,
+    },
+    /// Convert the setup of another tool to minhypr
+    Import {
+        #[command(subcommand)]
+        source: ImportSource,
+    },
+    /// Generate ready-made configuration for other programs
+    Setup {
+        #[command(subcommand)]
+        target: SetupTarget,
+    },
+    /// Internal command used by the Rofi scripts
+    #[command(hide = true)]
+    ShowRofi {
+        /// Speak Rofi's script-mode protocol (the restore menu)
+        #[arg(long)]
+        script: bool,
+        selection: Vec<String>,
+    },
+    /// Internal command spawned by minimize to show the notification
+    #[command(hide = true)]
+    Notify {
+        address: String,
+        #[arg(long)]
+        urgent: bool,
+    },
+    /// Internal command spawned by minimize to process the screenshot
+    #[command(hide = true)]
+    GeneratePreview { address: String, screenshot: String },
 }
 
-fn read_windows_from_cache() -> Result<Vec<MinimizedWindow>> {
-    if !Path::new(cache_file()).exists() {
-        return Ok(Vec::new());
-    }
-    
-    let content = fs::read_to_string(cache_file())?;
-    match serde_json::from_str::<Vec<MinimizedWindow>>(&content) {
-        Ok(windows) => {
-            // Additional validation to ensure that windows still exist
-            validate_cached_windows(windows)
-        },
-        Err(_) => Ok(Vec::new()),
-    }
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum FocusState {
+    On,
+    Off,
+    Toggle,
+    Status,
 }
 
-fn validate_cached_windows(windows: Vec<MinimizedWindow>) -> Result<Vec<MinimizedWindow>> {
-    if windows.is_empty() {
-        return Ok(Vec::new());
-    }
-
-    // Get all Hyprland windows
-    let window_check = Command::new("hyprctl")
-        .args(["clients", "-j"])
-        .output()?;
-    
-    let windows_json = String::from_utf8(window_check.stdout).unwrap_or_default();
-    
-    // Check each window in the special workspace: special:minimized
-    let special_check = Command::new("hyprctl")
-        .args(["workspaces", "-j"])
-        .output()?;
-    
-    let workspaces_json = String::from_utf8(special_check.stdout).unwrap_or_default();
-    
-    // Filter only valid windows
-    let mut valid_windows = Vec::new();
-    let mut need_update = false;
-    
-    for window in windows {
-        // Double check: the window must exist in the system AND be in the special:minimized workspace
-        if (windows_json.contains(&window.address.clone()) &&
-           (workspaces_json.contains("special:minimized") &&
-            workspaces_json.contains(&window.address))) {
-            valid_windows.push(window);
-        } else {
-            need_update = true;
-        }
-    }
-    
-    // If we found invalid windows, update the cache
-    if need_update {
-        save_windows_to_cache(&valid_windows)?;
-        signal_waybar();
-    }
-    
-    Ok(valid_windows)
+#[derive(Subcommand)]
+enum WorkspaceAction {
+    /// Minimize every window on a workspace (id or name; default: the active one)
+    Stash { workspace: Option<String> },
+    /// Restore the windows of a stashed workspace to it, in their layout order
+    Unstash { workspace: String },
+    /// List the stashed workspaces
+    List,
 }
 
-fn save_windows_to_cache(windows: &[MinimizedWindow]) -> Result<()> {
-    let json = serde_json::to_string(windows)?;
-    fs::write(cache_file(), json)
+#[derive(Subcommand)]
+enum ImportSource {
+    /// Print the [scratchpads] config and binds replacing pyprland's scratchpads
+    Pyprland {
+        /// pyprland config (default: ~/.config/hypr/pyprland.toml)
+        path: Option<PathBuf>,
+    },
 }
 
-fn parse_window_info(info: &str) -> Result<HashMap<String, String>> {
-    match serde_json::from_str::<HashMap<String, String>>(info) {
-        Ok(map) => Ok(map),
-        Err(_) => {
-            // Fallback parsing for simpler formats
-            let mut result = HashMap::new();
-            let content = info.trim_matches(|c| c == '{' || c == '}');
-
-            for pair in content.split(',') {
-                if let Some((key, value)) = pair.split_once(':') {
-                    let clean_key = key.trim().trim_matches('"');
-                    let clean_value = value.trim().trim_matches('"');
-                    result.insert(clean_key.to_string(), clean_value.to_string());
-                }
-            }
-
-            Ok(result)
-        }
-    }
+#[derive(Subcommand)]
+enum SetupTarget {
+    /// Print Hyprland binds (minimize, menu, restore-all, cycle, toggle) and a Waybar module
+    Hyprland {
+        /// Modifier of the binds, e.g. SUPER or "SUPER CTRL"
+        #[arg(long = "mod", value_name = "MODS", default_value = "ALT")]
+        modifier: String,
+        /// Added to the modifier for the menu, restore-all and toggle binds
+        #[arg(long, value_name = "MODS", default_value = "SHIFT")]
+        extra_mod: String,
+        /// Write them to ~/.config/hypr/conf.d/minhypr.conf instead of printing them
+        #[arg(long)]
+        write: bool,
+    },
+    /// Print a Hyprland submap ("minimize mode") with single-key binds to restore, cycle and minimize
+    Submap {
+        /// Modifier of the bind entering the mode, e.g. SUPER or "SUPER CTRL"
+        #[arg(long = "mod", value_name = "MODS", default_value = "ALT")]
+        modifier: String,
+        /// Key entering the mode, with the modifier
+        #[arg(long, default_value = "Z")]
+        key: String,
+        /// Write it to ~/.config/hypr/conf.d/minhypr-submap.conf instead of printing it
+        #[arg(long)]
+        write: bool,
+    },
+    /// Same as setup-rofi
+    Rofi {
+        #[arg(long, value_enum)]
+        theme: Option<RofiTheme>,
+        #[arg(long, value_enum)]
+        layout: Option<RofiLayout>,
+    },
 }
 
-fn restore_specific_window(window_id: &str) -> Result<()> {
-    println!("Restoring window: {}", window_id);
-    
-    // Get the specific window from cache
-    let windows = read_windows_from_cache()?;
-    
-    // Find the window we want to restore
-    let mut found = false;
-    let mut updated_windows = Vec::new();
-    
-    // Move the window back to its original workspace
-    for window in &windows {
-        if window.address == window_id {
-            Command::new("hyprctl")
-                .args([
-                    "dispatch",
-                    "movetoworkspace",
-                    &format!("{},address:{}", window.workspace, window_id),
-                ])
-                .output()?;
-            
-            // Focus on the window
-            Command::new("hyprctl")
-                .args(["dispatch", "focuswindow", &format!("address:{}", window_id)])
-                .output()?;
-            
-            // Remove only this window from the minimized list
-            found = true;
-        } else {
-            updated_windows.push(window.clone());
-        }
-    }
-    
-    if !found {
-        println!("Window not found in cache: {}", window_id);
-        return Ok(());
-    }
-    
-    // Update cache with remaining windows
-    save_windows_to_cache(&updated_windows)?;
-    
-    Ok(())
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum IdleEvent {
+    /// Minimize the idle_classes windows
+    Timeout,
+    /// Restore the windows minimized on timeout
+    Resume,
+    /// Print whether windows are hidden for idleness
+    Status,
 }
 
-fn restore_all_windows() -> Result<()> {
-    let windows = read_windows_from_cache()?;
-
-    for window in windows {
-        restore_specific_window(&window.address)?;
-    }
-
-    Ok(())
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum StatusProtocol {
+    Waybar,
+    I3bar,
+    Ironbar,
+    Yambar,
 }
 
-fn show_restore_menu() -> Result<()> {
-    println!("Starting restoration menu with Rofi...");
-    
-    let windows = read_windows_from_cache()?;
-    
-    if windows.is_empty() {
-        println!("No minimized windows");
-        return Ok(());
-    }
-
-    // Create temporary directory for Rofi script
-    let rofi_script_dir = format!("{}/rofi", cache_dir());
-    fs::create_dir_all(&rofi_script_dir)?;
-    let rofi_script = format!("{}/minhypr-menu.sh", rofi_script_dir);
-    
-    // Create temporary Rofi configuration file
-    let rofi_config = format!("{}/minhypr.rasi", rofi_script_dir);
-    let config_content = r#"
-configuration {
-    modi: "window";
-    display-window: "Minimized Windows";
-    window-format: "{icon} {t}";
-    window-thumbnail: true;
-    show-icons: true;
-    drun-display-format: "{name}";
-    fullscreen: false;
-    sidebar-mode: false;
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum ListFormat {
+    Plain,
+    Tsv,
+    Json,
 }
 
-* {
-    background-color: #2E3440;
-    text-color: #ECEFF4;
-    border-color: #4C566A;
-    selected-background: #3B4252;
-    selected-text: #88C0D0;
+// Keep TSV rows on one line with a fixed number of columns
+fn tsv_field(value: &str) -> String {
+    value.replace(['\t', '\n'], " ")
 }
 
-window {
-    width: 800px;
-    border: 2px;
-    border-radius: 6px;
-    padding: 12px;
-}
-
-element {
-    padding: 8px 12px;
-    border-radius: 4px;
-    spacing: 8px;
-}
-
-element selected {
-    background-color: @selected-background;
-    text-color: @selected-text;
-}
-
-element-icon {
-    size: 32px;
-}
-
-element-text {
-    vertical-align: 0.5;
-}
-"#;
-    fs::write(&rofi_config, config_content)?;
+fn list_windows(manager: &Manager, format: ListFormat, numbered: bool) -> Result<()> {
+    let cached = manager.windows()?;
+    let windows: Vec<&MinimizedWindow> = if numbered {
+        windows_by_recency(&cached)
+    } else {
+        cached.iter().collect()
+    };
 
-    // Generate script for Rofi with images and descriptions
-    let mut script_content = String::from("#!/bin/bash\n\n");
-    script_content.push_str("function gen_entries() {\n");
-    
-    for window in &windows {
-        let display = window.display_title.replace("\"", "\\\"");
-        let address = window.address.replace("\"", "\\\"");
-        
-        // Add preview if available
-        if let Some(preview) = &window.preview_path {
-            script_content.push_str(&format!(
-                "    echo -en \"{display}\\0icon\\x1f{preview}\\x1finfo\\x1f{address}\\n\"\n",
-                display = display,
-                preview = preview,
-                address = address
-            ));
-        } else {
-            // No preview, use only the application icon
-            script_content.push_str(&format!(
-                "    echo -en \"{display}\\0icon\\x1f{icon}\\x1finfo\\x1f{address}\\n\"\n",
-                display = display,
-                icon = window.class.to_lowercase(),
-                address = address
-            ));
-        }
-    }
-    
-    script_content.push_str("}\n\n");
-    
-    // Add logic for selection
-    script_content.push_str("if [ -z \"$@\" ]; then\n");
-    script_content.push_str("    gen_entries\n");
-    script_content.push_str("else\n");
-    script_content.push_str("    # Restore selected window\n");
-    script_content.push_str("    WINDOW_ID=\"$(echo \"$@\" | sed 's/.*info\\x1f\\(.*\\)/\\1/')\" \n");
-    script_content.push_str("    minhypr restore \"$WINDOW_ID\"\n");
-    script_content.push_str("fi\n");
-    
-    // Make the script executable
-    fs::write(&rofi_script, script_content)?;
-    Command::new("chmod").args(["+x", &rofi_script]).output()?;
-    
-    // Execute Rofi with our script
-    let output = Command::new("rofi")
-        .args([
-            "-show", "window",
-            "-theme", &rofi_config,
-            "-modi", &format!("window:{}", rofi_script),
-            "-no-fixed-num-lines",
-            "-no-click-to-exit",
-            "-no-custom",
-            "-window-thumbnail", // Show thumbnails if available
-            "-theme-str", "window {width: 600px;}"
-        ])
-        .output()?;
-    
-    if !output.status.success() {
-        // Fallback to simple Rofi if advanced configuration fails
-        let mut items = String::new();
-        for window in &windows {
-            items.push_str(&format!("{}\n", window.display_title));
+    match format {
+        ListFormat::Json => {
+            let mut entries = Vec::new();
+            for (i, window) in windows.iter().enumerate() {
+                let mut entry = serde_json::to_value(window)?;
+                if numbered {
+                    entry["index"] = serde_json::json!(i + 1);
+                }
+                entries.push(entry);
+            }
+            println!("{}", serde_json::to_string(&entries)?);
         }
-
-        let mut selection = Command::new("rofi")
-            .args([
-                "-dmenu",
-                "-p", "Restore window:",
-                "-i", // case insensitive matching
-                "-no-custom"
-            ])
-            .stdin(std::process::Stdio::piped())
-            .stdout(std::process::Stdio::piped())
-            .spawn()?;
-
-        if let Some(ref mut stdin) = selection.stdin {
-            stdin.write_all(items.as_bytes())?;
+        ListFormat::Tsv => {
+            let header = "address\tclass\ttitle\tworkspace\tmonitor\tpreview\tminimized_at";
+            if numbered {
+                println!("index\t{}", header);
+            } else {
+                println!("{}", header);
+            }
+            for (i, window) in windows.iter().enumerate() {
+                let index = if numbered { format!("{}\t", i + 1) } else { String::new() };
+                println!(
+                    "{}{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    index,
+                    window.address,
+                    tsv_field(&window.class),
+                    tsv_field(&window.original_title),
+                    window.workspace,
+                    window.monitor.as_deref().unwrap_or_default(),
+                    window.preview_path.as_deref().unwrap_or_default(),
+                    window.minimized_at
+                );
+            }
         }
-
-        let output = selection.wait_with_output()?;
-        let selection = String::from_utf8_lossy(&output.stdout);
-        let selection = selection.trim();
-
-        if !selection.is_empty() {
-            if let Some(window) = windows.iter().find(|w| w.display_title == selection) {
-                restore_specific_window(&window.address)?;
+        ListFormat::Plain => {
+            for (i, window) in windows.iter().enumerate() {
+                let index = if numbered { format!("{}: ", i + 1) } else { String::new() };
+                let age = format_window(&manager.config.age_format, window);
+                println!(
+                    "{}{} {} [workspace {} on {}{}]",
+                    index,
+                    window.address,
+                    manager.config.display_title(window),
+                    window.workspace,
+                    window.monitor.as_deref().unwrap_or("unknown monitor"),
+                    if age.is_empty() { age } else { format!(", minimized {}", age) }
+                );
             }
         }
     }
-    
-    Ok(()) // Added Ok() return to correct the error
-}
-
-fn restore_window(window_id: Option<&str>) -> Result<()> {
-    match window_id {
-        Some(id) => restore_specific_window(id),
-        None => show_restore_menu(),
-    }
-}
-
-fn minimize_window() -> Result<()> {
-    // Get active window information
-    let output = Command::new("hyprctl")
-        .args(["activewindow", "-j"])
-        .output()?;
-
-    if !output.status.success() {
-        return Ok(());
-    }
-
-    let window_info = String::from_utf8(output.stdout).unwrap_or_default();
-    let window_data = parse_window_info(&window_info)?;
-
-    // Do not minimize wofi (menu) windows
-    if window_data.get("class").map_or(false, |c| c == "wofi") {
-        return Ok(());
-    }
-
-    // Get the current workspace
-    let workspace_output = Command::new("hyprctl")
-        .args(["activeworkspace", "-j"])
-        .output()?;
-
-    let current_workspace = if workspace_output.status.success() {
-        let workspace_info = String::from_utf8(workspace_output.stdout).unwrap_or_default();
-        let workspace_data = parse_window_info(&workspace_info)?;
-        workspace_data
-            .get("id")
-            .and_then(|id| id.parse::<i32>().ok())
-            .unwrap_or(1)
-    } else {
-        1 // Default workspace if unable to get current one
-    };
-
-    // Extract window information
-    let window_addr = match window_data.get("address") {
-        Some(addr) => addr,
-        None => return Ok(()),
-    };
-    
-    let short_addr: String = window_addr.chars().rev().take(4).collect();
-    
-    let class_name = match window_data.get("class") {
-        Some(class) => class,
-        None => return Ok(()),
-    };
-    
-    let title = match window_data.get("title") {
-        Some(title) => title,
-        None => return Ok(()),
-    };
-    
-    let icon = get_app_icon(class_name);
-
-    // Capture window preview if possible
-    let preview_path = if let (Some(at), Some(size)) = (window_data.get("at"), window_data.get("size")) {
-        let geometry = format!("{},{}", at.trim(), size.trim());
-        capture_window_preview(window_addr, &geometry).ok()
-    } else {
-        None
-    };
-
-    // Create minimized window object
-    let window = MinimizedWindow {
-        address: window_addr.to_string(),
-        display_title: format!("{} {} - {} [{}]", icon, class_name, title, short_addr),
-        class: class_name.to_string(),
-        original_title: title.to_string(),
-        preview_path,
-        icon,
-        workspace: current_workspace,
-    };
-
-    // Move to special workspace (minimize)
-    let output = Command::new("hyprctl")
-        .args([
-            "dispatch",
-            "movetoworkspacesilent",
-            &format!("special:minimized,address:{}", window_addr),
-        ])
-        .output()?;
-
-    if output.status.success() {
-        // Update list of minimized windows
-        let mut windows = read_windows_from_cache()?;
-        windows.push(window);
-        save_windows_to_cache(&windows)?;
-        signal_waybar();
-    }
 
     Ok(())
 }
 
-fn show_status() -> Result<()> {
-    let windows = read_windows_from_cache()?;
-    let count = windows.len();
-
-    if count > 0 {
-        println!(
-            "{{\"text\":\"󰘸 {}\",\"class\":\"has-windows\",\"tooltip\":\"{} minimized windows\"}}",
-            count, count
-        );
-    } else {
-        println!("{{\"text\":\"󰘸\",\"class\":\"empty\",\"tooltip\":\"No minimized windows\"}}");
+// Companion programs built from their own crates (minhypr-dock, minhypr-tray):
+// next to the current executable, or from PATH
+fn exec_companion(name: &str, args: &[&str]) -> Result<()> {
+    let program = env::current_exe()
+        .ok()
+        .map(|exe| exe.with_file_name(name))
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| PathBuf::from(name));
+
+    let error = Command::new(program).args(args).exec();
+    if error.kind() == io::ErrorKind::NotFound {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{0} is not installed, build it with: cargo build --release --manifest-path {0}/Cargo.toml", name),
+        ));
     }
-
-    Ok(())
-}
-
-fn generate_rofi_config() -> Result<()> {
-    let home = env::var("HOME").unwrap_or_else(|_| String::from("/tmp"));
-    let config_dir = format!("{}/.config/minhypr", home);
-    fs::create_dir_all(&config_dir)?;
-    
-    // Generate Rofi theme file
-    let rofi_theme = format!("{}/minhypr.rasi", config_dir);
-    let theme_content = r#"/**
- * MinHypr Rofi Theme
- */
-
-configuration {
-    modi: "window";
-    display-window: "Minimized Windows";
-    window-format: "{icon} {t}";
-    window-thumbnail: true;
-    show-icons: true;
-    drun-display-format: "{name}";
-    fullscreen: false;
-    sidebar-mode: false;
-}
-
-* {
-    background:     #2E3440;
-    background-alt: #3B4252;
-    foreground:     #ECEFF4;
-    selected:       #88C0D0;
-    active:         #A3BE8C;
-    urgent:         #BF616A;
-    border:         #4C566A;
-}
-
-window {
-    width: 650px;
-    border: 2px;
-    border-color: @border;
-    border-radius: 6px;
-    padding: 12px;
-    background-color: @background;
-}
-
-mainbox {
-    border: 0;
-    padding: 0;
-}
-
-message {
-    border: 2px 0px 0px;
-    border-color: @border;
-    padding: 10px;
+    Err(error)
 }
 
-textbox {
-    text-color: @foreground;
-}
-
-inputbar {
-    children: [ prompt, textbox-prompt-colon, entry, case-indicator ];
-    padding: 12px;
-}
-
-prompt {
-    text-color: @selected;
-}
-
-textbox-prompt-colon {
-    expand: false;
-    str: ":";
-    margin: 0px 4px 0px 0px;
-    text-color: @foreground;
-}
-
-entry {
-    text-color: @foreground;
-}
-
-case-indicator {
-    text-color: @foreground;
+// The given window or class, or the active window
+fn pin_target(manager: &Manager, target: Option<String>) -> Result<String> {
+    match target {
+        Some(target) => Ok(target),
+        None => match manager.compositor.active_window()? {
+            Some(active) => Ok(active.address),
+            None => Err(Error::WindowNotFound(String::from("active window")).into()),
+        },
+    }
 }
 
-listview {
-    fixed-height: 0;
-    border: 2px 0px 0px;
-    border-color: @border;
-    spacing: 4px;
-    scrollbar: true;
-    padding: 10px 5px 0px;
+// `restore` takes a class too, meaning its most recently minimized window
+fn restore_target(manager: &Manager, target: String) -> Result<String> {
+    if target.starts_with("0x") {
+        return Ok(target);
+    }
+    let windows: Vec<MinimizedWindow> =
+        manager.windows()?.into_iter().filter(|w| w.class.eq_ignore_ascii_case(&target)).collect();
+    Ok(most_recent_window(&windows).map(|w| w.address.clone()).unwrap_or(target))
 }
 
-element {
-    border: 0;
-    border-radius: 4px;
-    padding: 8px 12px;
+// Command line flags override the config defaults
+fn apply_restore_flags(manager: &mut Manager, here: bool, no_focus: bool) {
+    if here {
+        manager.config.restore_target = RestoreTarget::Current;
+    }
+    if no_focus {
+        manager.config.restore_focus = false;
+    }
 }
 
-element normal.normal {
-    background-color: inherit;
-    text-color: @foreground;
-}
+// Durations like "25m", "1h30m" or "90s"; a bare number is seconds
+fn parse_duration(value: &str) -> std::result::Result<Duration, String> {
+    let mut seconds = 0;
+    let mut number = String::new();
 
-element selected.normal {
-    background-color: @background-alt;
-    text-color: @selected;
-}
+    for c in value.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
 
-element-icon {
-    size: 42px;
-    margin: 0 8px 0 0;
-}
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            _ => return Err(format!("unknown unit '{}' (use s, m, h or d)", c)),
+        };
+        let amount: u64 = number.parse().map_err(|_| format!("missing number before '{}'", c))?;
+        seconds += amount * unit;
+        number.clear();
+    }
 
-element-text {
-    background-color: inherit;
-    text-color: inherit;
-    vertical-align: 0.5;
-}
+    if !number.is_empty() {
+        seconds += number.parse::<u64>().map_err(|e| e.to_string())?;
+    }
+    if seconds == 0 {
+        return Err(String::from("duration must be positive"));
+    }
 
-scrollbar {
-    width: 4px;
-    border: 0;
-    handle-width: 8px;
-    padding: 0;
-    handle-color: @border;
+    Ok(Duration::from_secs(seconds))
 }
 
-button {
-    text-color: @foreground;
-    border: 2px 0px 0px;
-    border-color: @border;
-    border-radius: 4px;
+fn parse_regex(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
 }
 
-button selected {
-    background-color: @background-alt;
-    text-color: @selected;
-}
-"#;
-    fs::write(&rofi_theme, theme_content)?;
-    
-    // Generate portable launch script
-    let rofi_script = format!("{}/launch-menu.sh", config_dir);
-    let script_content = r#"#!/bin/bash
-
-# Script to launch the Rofi menu for minimized windows
-# Generated by MinHypr - Portable Version
-
-# Find minhypr executable
-if [ -x "$HOME/.local/bin/minhypr" ]; then
-    MINHYPR="$HOME/.local/bin/minhypr"
-elif [ -x "/usr/local/bin/minhypr" ]; then
-    MINHYPR="/usr/local/bin/minhypr"
-elif [ -x "/usr/bin/minhypr" ]; then
-    MINHYPR="/usr/bin/minhypr"
-elif command -v minhypr &> /dev/null; then
-    MINHYPR="minhypr"
-else
-    notify-send "Error" "Unable to find minhypr executable"
-    exit 1
-fi
-
-# Configure theme
-THEME="$HOME/.config/minhypr/minhypr.rasi"
-
-# Execute Rofi with configurations
-rofi \
-  -show window \
-  -theme "$THEME" \
-  -modi "window:$MINHYPR show-rofi" \
-  -no-fixed-num-lines \
-  -window-thumbnail \
-  -theme-str "window {width: 650px;}"
-"#;
-
-    fs::write(&rofi_script, script_content)?;
-    Command::new("chmod").args(["+x", &rofi_script]).output()?;
-    
-    // Generate simple backup script (in case Rofi fails)
-    let simple_script = format!("{}/simple-menu.sh", config_dir);
-    let simple_content = r#"#!/bin/bash
-
-# Simple script to show and restore minimized windows
-# Works as a backup in case Rofi has problems
-
-# Find minhypr executable
-if [ -x "$HOME/.local/bin/minhypr" ]; then
-    MINHYPR="$HOME/.local/bin/minhypr"
-elif [ -x "/usr/local/bin/minhypr" ]; then
-    MINHYPR="/usr/local/bin/minhypr"
-elif [ -x "/usr/bin/minhypr" ]; then
-    MINHYPR="/usr/bin/minhypr"
-elif command -v minhypr &> /dev/null; then
-    MINHYPR="minhypr"
-else
-    notify-send "Error" "Unable to find minhypr executable"
-    exit 1
-fi
-
-# Check if there are minimized windows
-WINDOWS=$($MINHYPR show)
-if [[ $WINDOWS == *"empty"* ]]; then
-    notify-send "MinHypr" "No minimized windows"
-    exit 0
-fi
-
-# Use simple Rofi to show the list of windows
-$MINHYPR restore
-"#;
-    
-    fs::write(&simple_script, simple_content)?;
-    Command::new("chmod").args(["+x", &simple_script]).output()?;
-    
-    // Generate script to restore all windows
-    let restore_script = format!("{}/restore-all.sh", config_dir);
-    let restore_content = r#"#!/bin/bash
-
-# Script to restore all minimized windows
-
-# Find minhypr executable
-if [ -x "$HOME/.local/bin/minhypr" ]; then
-    MINHYPR="$HOME/.local/bin/minhypr"
-elif [ -x "/usr/local/bin/minhypr" ]; then
-    MINHYPR="/usr/local/bin/minhypr"
-elif [ -x "/usr/bin/minhypr" ]; then
-    MINHYPR="/usr/bin/minhypr"
-elif command -v minhypr &> /dev/null; then
-    MINHYPR="minhypr"
-else
-    notify-send "Error" "Unable to find minhypr executable"
-    exit 1
-fi
-
-$MINHYPR restore-all
-"#;
-    
-    fs::write(&restore_script, restore_content)?;
-    Command::new("chmod").args(["+x", &restore_script]).output()?;
-    
-    println!("Rofi configuration generated in: {}", config_dir);
-    println!("Available scripts:");
-    println!("  {}/launch-menu.sh - Full Rofi menu", config_dir);
-    println!("  {}/simple-menu.sh - Simple menu (in case Rofi fails)", config_dir);
-    println!("  {}/restore-all.sh - Restores all windows", config_dir);
-    
-    println!("\nYou can add these shortcuts to your Hyprland:");
-    println!("  bind = ALT SHIFT, M, exec, $HOME/.config/minhypr/launch-menu.sh");
-    println!("  bind = ALT CTRL, M, exec, $HOME/.config/minhypr/simple-menu.sh");
-    println!("  bind = ALT SHIFT, R, exec, $HOME/.config/minhypr/restore-all.sh");
-    
-    Ok(())
-}
+fn main() {
+    let cli = Cli::parse();
 
-fn show_rofi_menu() -> Result<()> {
-    let windows = read_windows_from_cache()?;
-    
-    if windows.is_empty() {
-        println!("INFO: No minimized windows");
-        return Ok(());
+    if let Err(e) = run(cli) {
+        error!("{}", e);
+        std::process::exit(error::exit_code(&e));
     }
+}
 
-    // Get the current workspace
-    let workspace_output = Command::new("hyprctl")
-        .args(["activeworkspace", "-j"])
-        .output()?;
-
-    let current_workspace = if workspace_output.status.success() {
-        let workspace_info = String::from_utf8(workspace_output.stdout).unwrap_or_default();
-        let workspace_data = parse_window_info(&workspace_info)?;
-        workspace_data
-            .get("id")
-            .and_then(|id| id.parse::<i32>().ok())
-            .unwrap_or(1)
-    } else {
-        1 // Default workspace if unable to get current one
-    };
-
-    // Verify closed/restored windows
-    let mut updated_windows = Vec::new();
-    let mut at_least_one_changed = false;
-    
-    // Verify which windows actually exist
-    let window_check = Command::new("hyprctl")
-        .args(["clients", "-j"])
-        .output()?;
-    
-    let windows_json = String::from_utf8(window_check.stdout).unwrap_or_default();
-    
-    // Show only existing windows - simpler format for parsing
-    for window in &windows {
-        if windows_json.contains(&window.address) {
-            // This window still exists
-            updated_windows.push(window.clone());
-            
-            // Use simpler and more reliable format
-            // Short title followed by address with "info" prefix
-            let short_title = format!("{} - {}", window.class, window.original_title);
-            let short_addr = window.address.chars().rev().take(8).collect::<String>();
-            
-            // Include workspace information in display
-            println!("[WS:{}] {} [{}] info{}", 
-                window.workspace, 
-                short_title, 
-                short_addr, 
-                window.address);
-        } else {
-            // This window no longer exists, we don't include
-            at_least_one_changed = true;
+fn run(cli: Cli) -> Result<()> {
+    log::set_level(match (cli.quiet, cli.verbose) {
+        (true, _) => Level::Warn,
+        (_, 0) => Level::Info,
+        (_, 1) => Level::Debug,
+        _ => Level::Trace,
+    });
+
+    let mut manager = Manager::new();
+
+    if cli.log_file || manager.config.log_file {
+        // The file gets at least what the terminal shows
+        let level = manager.config.log_level.max(log::level());
+        if let Err(e) = log::open_log_file(level) {
+            warn!("Could not open {}: {}", log::log_file_path().display(), e);
         }
     }
-    
-    // Update list of windows if changes
-    if at_least_one_changed {
-        save_windows_to_cache(&updated_windows)?;
-        signal_waybar();
-    }
-    
-    Ok(())
-}
-
-fn signal_waybar() {
-    Command::new("pkill")
-        .args(["-RTMIN+8", "waybar"])
-        .output()
-        .ok();
-}
+    debug!("Running {}", env::args().collect::<Vec<_>>().join(" "));
 
-fn main() -> Result<()> {
     // Create necessary directories
-    fs::create_dir_all(cache_dir())?;
-    fs::create_dir_all(preview_dir())?;
+    manager.store.init()?;
+
+    match cli.command {
+        Commands::Minimize { address, pick, class, title, snooze } => {
+            let minimized = if pick {
+                match menu::pick_window(&manager)? {
+                    Some(client) if manager.minimize(&client)? => vec![client.address],
+                    _ => Vec::new(),
+                }
+            } else if let Some(address) = address {
+                if manager.minimize_address(&address)? { vec![address] } else { Vec::new() }
+            } else if class.is_some() || title.is_some() {
+                let class = class.as_deref().map(parse_regex).transpose()?;
+                let title = title.as_deref().map(parse_regex).transpose()?;
+                let minimized = manager.minimize_matching(class.as_ref(), title.as_ref())?;
+                info!("Minimized {} windows", minimized.len());
+                minimized
+            } else {
+                match manager.compositor.active_window()? {
+                    Some(client) if manager.minimize(&client)? => vec![client.address],
+                    _ => Vec::new(),
+                }
+            };
 
-    if !Path::new(cache_file()).exists() {
-        save_windows_to_cache(&Vec::new())?;
-    }
+            if let Some(duration) = snooze {
+                manager.snooze(&minimized, duration)?;
+            }
+        }
+        Commands::MinimizeMenu { launcher } => {
+            let minimized = match launcher.unwrap_or(manager.config.launcher) {
+                Launcher::Rofi => rofi::show_minimize_menu(&manager)?,
+                Launcher::Bemenu => menu::show_minimize_bemenu(&manager)?.into_iter().collect(),
+            };
+            if !minimized.is_empty() {
+                info!("Minimized {} windows", minimized.len());
+            }
+        }
+        Commands::MinimizeAll => {
+            let minimized = manager.minimize_workspace(false)?;
+            info!("Minimized {} windows", minimized.len());
+        }
+        Commands::MinimizeOthers => {
+            let minimized = manager.minimize_workspace(true)?;
+            info!("Minimized {} windows", minimized.len());
+        }
+        Commands::ShowDesktop => {
+            manager.show_desktop()?;
+        }
+        Commands::Restore { address, index, class, title, first, launcher, here, to_workspace, no_focus } => {
+            apply_restore_flags(&mut manager, here, no_focus);
+            let address = address.map(|address| restore_target(&manager, address)).transpose()?;
+            if let (Some(id), Some(workspace)) = (&address, &to_workspace) {
+                manager.restore_to_workspace(id, workspace)?;
+            } else if let Some(id) = address {
+                manager.restore(&id)?;
+            } else if let Some(index) = index {
+                if manager.restore_index(index as usize)?.is_none() {
+                    return Err(Error::WindowNotFound(format!("index {}", index)).into());
+                }
+            } else if class.is_some() || title.is_some() {
+                let class = class.as_deref().map(parse_regex).transpose()?;
+                let title = title.as_deref().map(parse_regex).transpose()?;
+                let limit = if first { Some(1) } else { None };
+                let restored = manager.restore_matching(class.as_ref(), title.as_ref(), limit)?;
+                info!("Restored {} windows", restored.len());
+            } else {
+                match launcher.unwrap_or(manager.config.launcher) {
+                    Launcher::Rofi => rofi::show_restore_menu(&manager)?,
+                    Launcher::Bemenu => {
+                        menu::show_bemenu(&manager)?;
+                    }
+                }
+            }
+        }
+        Commands::Toggle { class: Some(class), exec, .. } => {
+            manager.toggle_class(&class, exec.as_deref())?;
+        }
+        Commands::Toggle { address, .. } => {
+            manager.toggle(address.as_deref())?;
+        }
+        Commands::Scratch { name } => {
+            manager.scratch(&name)?;
+        }
+        Commands::Cycle => {
+            if manager.cycle()?.is_none() {
+                info!("No minimized windows to restore");
+            }
+        }
+        Commands::Undo => {
+            if manager.undo_restore()?.is_none() {
+                info!("Nothing to undo");
+            }
+        }
+        Commands::Peek { address, seconds } => {
+            manager.peek(&address, seconds.map(Duration::from_secs))?;
+        }
+        Commands::RestoreAll => {
+            manager.restore_all()?;
+        }
+        Commands::RestoreLast { oldest, here, no_focus } => {
+            apply_restore_flags(&mut manager, here, no_focus);
+            let windows = manager.windows()?;
+            let window = if oldest {
+                oldest_window(&windows)
+            } else {
+                most_recent_window(&windows)
+            };
+            if let Some(window) = window {
+                manager.restore(&window.address)?;
+            } else {
+                info!("No minimized windows to restore");
+            }
+        }
+        Commands::List { format, numbered } => {
+            let format = if cli.json { ListFormat::Json } else { format };
+            list_windows(&manager, format, numbered)?;
+        }
+        Commands::Find { query, restore, all, here, no_focus } => {
+            let query = query.join(" ");
+            let windows = manager.windows()?;
+            let matches = search_windows(&windows, &query);
+            if matches.is_empty() {
+                return Err(Error::WindowNotFound(query).into());
+            }
 
-    // Process arguments
-    let args: Vec<String> = env::args().collect();
-    let command = args.get(1).map(|s| s.as_str()).unwrap_or("");
+            if restore {
+                apply_restore_flags(&mut manager, here, no_focus);
+                let count = if all { matches.len() } else { 1 };
+                let addresses: Vec<String> = matches.iter().take(count).map(|w| w.address.clone()).collect();
+                let restored = manager.restore_many(&addresses)?;
+                if cli.json {
+                    println!("{}", serde_json::to_string(&restored)?);
+                }
+            } else if cli.json {
+                println!("{}", serde_json::to_string(&matches)?);
+            } else {
+                for window in matches {
+                    println!("{} {}", window.address, manager.config.display_title(window));
+                }
+            }
+        }
+        Commands::Status { tooltip_format, protocol, format, per_window, slot, watch } => {
+            let tooltip_format = tooltip_format.unwrap_or_else(|| manager.config.entry_format());
+            if per_window {
+                status::print_status(&manager, watch, |windows| match slot {
+                    Some(slot) => waybar::slot_json(&manager.config, windows, &tooltip_format, slot as usize),
+                    None => waybar::per_window_json(&manager.config, windows, &tooltip_format),
+                })?;
+            } else if protocol == StatusProtocol::I3bar {
+                i3bar::run(&manager)?;
+            } else {
+                status::print_status(&manager, watch, |windows| match (&format, protocol) {
+                    (Some(format), _) => status::format_status(format, windows),
+                    (None, StatusProtocol::Ironbar) => status::format_status(status::IRONBAR_FORMAT, windows),
+                    (None, StatusProtocol::Yambar) => status::yambar_tags(windows),
+                    (None, _) => waybar::status_json(&manager.config, windows, &tooltip_format),
+                })?;
+            }
+        }
+        Commands::Watch { tooltip_format } => {
+            let tooltip_format = tooltip_format.unwrap_or_else(|| manager.config.entry_format());
+            waybar::watch(&manager, &tooltip_format)?;
+        }
+        Commands::Listen => {
+            events::listen(&manager)?;
+        }
+        Commands::Eww { watch, yuck } => {
+            if yuck {
+                print!("{}", eww::SAMPLE_YUCK);
+            } else {
+                eww::print_eww(&manager, watch)?;
+            }
+        }
+        Commands::Notify { address, urgent } => {
+            notify::notify_minimized(&manager, &address, urgent)?;
+        }
+        Commands::GeneratePreview { address, screenshot } => {
+            manager.generate_preview(&address, &screenshot)?;
+        }
+        Commands::Resync => {
+            let windows = manager.resync()?;
+            if cli.json {
+                println!("{}", serde_json::to_string(&windows)?);
+            } else {
+                info!("{} minimized windows tracked", windows.len());
+            }
+        }
+        Commands::Stash { name: None, .. } => {
+            let stashes = manager.stashes()?;
+            if cli.json {
+                let stashes: Vec<_> = stashes.iter().map(|(name, count)| serde_json::json!({"name": name, "count": count})).collect();
+                println!("{}", serde_json::to_string(&stashes)?);
+            } else {
+                for (name, count) in stashes {
+                    println!("{}\t{}", name, count);
+                }
+            }
+        }
+        Commands::Stash { name: Some(name), class, title } => {
+            let class = class.as_deref().map(parse_regex).transpose()?;
+            let title = title.as_deref().map(parse_regex).transpose()?;
+            let minimized = manager.stash(&name, class.as_ref(), title.as_ref())?;
+            info!("{} windows stashed in {}", minimized.len(), name);
+        }
+        Commands::Unstash { name } => {
+            let restored = manager.unstash(&name)?;
+            if restored.is_empty() {
+                info!("Nothing to restore in {}", name);
+            }
+        }
+        Commands::Pin { list: true, .. } => {
+            let pins = manager.pins()?;
+            if cli.json {
+                println!("{}", serde_json::to_string(&pins)?);
+            } else {
+                for pin in pins {
+                    println!("{}", pin);
+                }
+            }
+        }
+        Commands::Pin { target, .. } => {
+            let target = pin_target(&manager, target)?;
+            if manager.pin(&target)? {
+                info!("Pinned {}", target);
+            } else {
+                info!("{} is already pinned", target);
+            }
+        }
+        Commands::Unpin { target } => {
+            let target = pin_target(&manager, target)?;
+            if manager.unpin(&target)? {
+                info!("Unpinned {}", target);
+            } else {
+                info!("{} was not pinned", target);
+            }
+        }
+        Commands::Workspace { action: WorkspaceAction::Stash { workspace } } => {
+            let minimized = manager.stash_workspace(workspace.as_deref())?;
+            info!("{} windows stashed", minimized.len());
+        }
+        Commands::Workspace { action: WorkspaceAction::Unstash { workspace } } => {
+            let restored = manager.unstash_workspace(&workspace)?;
+            if restored.is_empty() {
+                info!("Nothing to restore to workspace {}", workspace);
+            }
+        }
+        Commands::Workspace { action: WorkspaceAction::List } => {
+            let stashes = manager.workspace_stashes()?;
+            if cli.json {
+                let stashes: Vec<_> = stashes.iter().map(|(name, count)| serde_json::json!({"workspace": name, "count": count})).collect();
+                println!("{}", serde_json::to_string(&stashes)?);
+            } else {
+                for (name, count) in stashes {
+                    println!("{}\t{}", name, count);
+                }
+            }
+        }
+        Commands::Focus { state: FocusState::Status } => {
+            println!("{}", if manager.focus_active()? { "on" } else { "off" });
+        }
+        Commands::Focus { state } => {
+            let turn_on = match state {
+                FocusState::On => true,
+                FocusState::Off => false,
+                _ => !manager.focus_active()?,
+            };
+
+            if turn_on {
+                if manager.config.distractions.is_empty() {
+                    info!("No distractions configured, add `distractions = [...]` to {}", config::config_file().display());
+                }
+                let minimized = manager.focus_on()?;
+                info!("Focus mode on, {} windows minimized", minimized.len());
+            } else {
+                let restored = manager.focus_off()?;
+                info!("Focus mode off, {} windows restored", restored.len());
+            }
+        }
+        Commands::Idle { event: IdleEvent::Timeout } => {
+            let minimized = manager.idle_timeout()?;
+            info!("Idle, {} windows minimized", minimized.len());
+        }
+        Commands::Idle { event: IdleEvent::Resume } => {
+            let restored = manager.idle_resume()?;
+            info!("Resumed, {} windows restored", restored.len());
+        }
+        Commands::Idle { event: IdleEvent::Status } => {
+            println!("{}", if manager.idle_active()? { "idle" } else { "active" });
+        }
+        Commands::Lock => {
+            let minimized = manager.lock_session()?;
+            info!("Locked, {} windows minimized", minimized.len());
+        }
+        Commands::Unlock => {
+            let restored = manager.unlock_session()?;
+            info!("Unlocked, {} windows restored", restored.len());
+        }
+        Commands::Completions { shell } => {
+            print!("{}", completions::generate(shell, &Cli::command()));
+        }
+        Commands::CompleteWindows => {
+            for (value, description) in completions::window_candidates(&manager.windows()?) {
+                println!("{}\t{}", value, description);
+            }
+        }
+        Commands::Doctor => {
+            if !doctor::run_doctor(&manager) {
+                std::process::exit(1);
+            }
+        }
+        Commands::Stats => {
+            let stats = history::class_stats(&history::load()?);
+            let mut windows = manager.windows()?;
+            windows.sort_by_key(|w| w.minimized_at);
+
+            if cli.json {
+                let minimized: Vec<_> = windows
+                    .iter()
+                    .map(|w| serde_json::json!({"address": w.address, "class": w.class, "title": w.original_title, "age_secs": window_age(w)}))
+                    .collect();
+                println!("{}", serde_json::to_string(&serde_json::json!({"classes": stats, "minimized": minimized}))?);
+            } else {
+                if !manager.config.history {
+                    warn!("The history is off (history = false in the config), counts may be outdated");
+                }
+                println!("{:<30} {:>9} {:>9} {:>12}", "CLASS", "MINIMIZED", "RESTORED", "AVG. HIDDEN");
+                for (class, entry) in &stats {
+                    let average = if entry.restored > 0 { format_age(entry.average_minimized_secs) } else { String::from("-") };
+                    println!("{:<30} {:>9} {:>9} {:>12}", class, entry.minimized, entry.restored, average);
+                }
 
-    match command {
-        "minimize" => {
-            minimize_window()?;
+                if !windows.is_empty() {
+                    println!("\nMinimized now:");
+                    for window in &windows {
+                        println!("  {:>8}  {}  {}", format_window("{age}", window), window.class, window.original_title);
+                    }
+                }
+            }
+        }
+        Commands::Purge { previews: true, .. } => {
+            let removed = manager.purge_previews()?;
+            info!("Removed {} preview files", removed);
+        }
+        Commands::Purge { restore, .. } => {
+            let restored = manager.reset(restore)?;
+            if restore {
+                info!("Restored {} windows", restored);
+            }
+            info!("State cleared");
         }
-        "restore" => {
-            let window_id = args.get(2).map(|s| s.as_str());
-            restore_window(window_id)?;
+        Commands::Gc { max_age } => {
+            let removed = manager.collect_previews(max_age)?;
+            info!("Removed {} preview files", removed);
         }
-        "restore-all" => {
-            restore_all_windows()?;
+        Commands::RefreshPreviews { address } => {
+            let refreshed = manager.refresh_previews(address.as_deref())?;
+            if cli.json {
+                println!("{}", serde_json::to_string(&refreshed)?);
+            } else {
+                info!("{} previews refreshed", refreshed.len());
+            }
         }
-        "restore-last" => {
-            let windows = read_windows_from_cache()?;
-            if let Some(window) = windows.first() {
-                restore_specific_window(&window.address)?;
+        Commands::Adopt { address } => {
+            let adopted = manager.adopt(address.as_deref())?;
+            if cli.json {
+                println!("{}", serde_json::to_string(&adopted)?);
             } else {
-                println!("No minimized windows to restore");
+                info!("{} windows adopted", adopted.len());
             }
         }
-        "show" => {
-            show_status()?;
+        Commands::Disown { address } => {
+            manager.disown(&address)?;
+        }
+        Commands::Close { address: Some(address), .. } => {
+            manager.close_minimized(&restore_target(&manager, address)?)?;
+        }
+        Commands::Close { address: None, launcher } => {
+            menu::close_menu(&manager, launcher.unwrap_or(manager.config.launcher))?;
         }
-        "show-rofi" => {
+        Commands::Daemon { dbus } => {
+            daemon::run_daemon(dbus)?;
+        }
+        Commands::Schema => {
+            println!("{}", serde_json::to_string_pretty(&rpc::schema())?);
+        }
+        Commands::Ctl { command, address } => {
+            if command == "subscribe" {
+                control::print_events()?;
+            } else {
+                let response = control::send_request(&serde_json::json!({"command": command, "address": address}))?;
+                if response["ok"] != true {
+                    // Same exit status as the command run directly
+                    error!("{}", response["error"].as_str().unwrap_or("request failed"));
+                    std::process::exit(response["code"].as_i64().unwrap_or(1) as i32);
+                }
+                println!("{}", response["result"]);
+            }
+        }
+        Commands::ShowRofi { script: true, selection } => {
+            rofi::run_script_mode(&manager, &selection)?;
+        }
+        Commands::ShowRofi { selection, .. } => {
             // Special command for integration with Rofi
-            show_rofi_menu()?;
+            rofi::show_rofi_menu(&manager, &selection)?;
+        }
+        Commands::Expose => {
+            rofi::show_expose(&manager)?;
+        }
+        Commands::Dock { edge } => {
+            exec_companion("minhypr-dock", &["--edge", &edge])?;
         }
-        "setup-rofi" => {
+        Commands::Tray => {
+            exec_companion("minhypr-tray", &[])?;
+        }
+        Commands::Tui => {
+            tui::run_tui(&manager)?;
+        }
+        Commands::Pick { stdin_menu, format } => {
+            menu::pick(&manager, &stdin_menu, &format)?;
+        }
+        Commands::SetupRofi { theme, layout } | Commands::Setup { target: SetupTarget::Rofi { theme, layout } } => {
             // Generate Rofi configuration files
-            generate_rofi_config()?;
-        }
-        _ => {
-            println!("Unknown command: {}", command);
-            println!("Usage: minhypr <command> [window_id]");
-            println!("Available commands:");
-            println!("  minimize       - Minimize active window");
-            println!("  restore        - Show menu to restore windows");
-            println!("  restore <id>   - Restore specific window");
-            println!("  restore-all    - Restore all windows");
-            println!("  restore-last   - Restore last minimized window");
-            println!("  show           - Show status for waybar");
-            println!("  setup-rofi     - Configure integration with Rofi");
-            println!("  show-rofi      - Internal script used by Rofi");
+            rofi::generate_rofi_config(
+                theme.unwrap_or(manager.config.rofi_theme),
+                layout.unwrap_or(manager.config.rofi_layout),
+            )?;
+        }
+        Commands::Import { source: ImportSource::Pyprland { path } } => {
+            print!("{}", import::pyprland(&path.unwrap_or_else(import::pyprland_config_path))?);
+        }
+        Commands::Setup { target: SetupTarget::Hyprland { modifier, extra_mod, write } } => {
+            let content = setup::hyprland_config(&modifier, &extra_mod)?;
+            if write {
+                setup::write_hyprland_config(&setup::hyprland_config_path(), &content)?;
+            } else {
+                print!("{}", content);
+            }
+        }
+        Commands::Setup { target: SetupTarget::Submap { modifier, key, write } } => {
+            let content = setup::submap_config(&modifier, &key)?;
+            if write {
+                setup::write_hyprland_config(&setup::submap_config_path(), &content)?;
+                info!("Press {} + {} to enter minimize mode, then 1-9 to restore a window or Escape to leave", modifier, key);
+            } else {
+                print!("{}", content);
+            }
         }
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_combine_units() {
+        assert_eq!(parse_duration("25m"), Ok(Duration::from_secs(25 * 60)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(90 * 60)));
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("45"), Ok(Duration::from_secs(45)));
+    }
+
+    #[test]
+    fn invalid_durations_are_rejected() {
+        for value in ["", "0", "0m", "10x", "m", "1h m", "soon"] {
+            assert!(parse_duration(value).is_err(), "{:?} was accepted", value);
+        }
+    }
+}
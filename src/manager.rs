@@ -0,0 +1,2085 @@
+use std::{
+    fs,
+    io::{self, Result},
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+use regex::Regex;
+
+use crate::{
+    config::{CaptureMethod, Config, LimitAction, MenuGroup, MenuSort, RestoreTarget, UrgentAction},
+    effects::{Effects, SystemEffects},
+    error::Error,
+    compositor::{Action, Compositor},
+    hyprland::{Client, Hyprland},
+    history::EventKind,
+    icons,
+    preview,
+    state::StateStore,
+    window::{most_recent_window, unix_timestamp_ms, windows_by_recency, MinimizedWindow},
+};
+
+// Group holding the windows minimized by show-desktop
+const SHOW_DESKTOP_GROUP: &str = "show-desktop";
+
+// Group holding the windows minimized by focus mode
+const FOCUS_GROUP: &str = "focus";
+
+// Group holding the windows minimized while the session is idle
+const IDLE_GROUP: &str = "idle";
+
+// Group holding the windows minimized while the session is locked
+const LOCK_GROUP: &str = "lock";
+
+// Group holding the window shown by `peek`, whose focus mustn't restore it
+const PEEK_GROUP: &str = "peek";
+
+// Pinned window addresses and classes, left alone by bulk minimizes
+const PIN_GROUP: &str = "pinned";
+
+// Named stashes live next to the other groups, under this prefix
+const STASH_PREFIX: &str = "stash:";
+
+// Stashed workspaces, by workspace name
+const WORKSPACE_STASH_PREFIX: &str = "workspace:";
+
+/// Hyprland window tag marking the windows minhypr minimized.
+pub const MINHYPR_TAG: &str = "minhypr";
+
+// Pins are window addresses or class names
+fn is_address(pin: &str) -> bool {
+    pin.starts_with("0x")
+}
+
+fn is_pinned(pins: &[String], client: &Client) -> bool {
+    pins.iter().any(|pin| if is_address(pin) { *pin == client.address } else { pin.eq_ignore_ascii_case(&client.class) })
+}
+
+// Hyprland takes a bare word as a workspace id, so plain names get `name:`
+fn workspace_selector(workspace: &str) -> String {
+    if workspace.parse::<i64>().is_ok() || workspace.contains(':') {
+        workspace.to_string()
+    } else {
+        format!("name:{}", workspace)
+    }
+}
+
+/// Minimize/restore operations on top of a [`StateStore`] and a
+/// [`Compositor`], [`Hyprland`] unless given another (e.g. a mock in tests).
+/// Everything else they touch goes through [`Effects`].
+pub struct Manager<C: Compositor = Hyprland> {
+    pub store: StateStore,
+    pub compositor: C,
+    pub config: Config,
+    pub effects: Box<dyn Effects>,
+}
+
+impl Default for Manager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Manager {
+    pub fn new() -> Self {
+        Manager {
+            store: StateStore::new(),
+            compositor: Hyprland::new(),
+            config: Config::load(),
+            effects: Box::new(SystemEffects),
+        }
+    }
+}
+
+impl<C: Compositor> Manager<C> {
+    /// Manager for another compositor, with an explicit store and config.
+    pub fn with_compositor(compositor: C, store: StateStore, config: Config) -> Self {
+        Manager { store, compositor, config, effects: Box::new(SystemEffects) }
+    }
+
+    /// Replace the side effects, e.g. with [`MockEffects`] in tests.
+    ///
+    /// [`MockEffects`]: crate::mock::MockEffects
+    pub fn with_effects(mut self, effects: impl Effects + 'static) -> Self {
+        self.effects = Box::new(effects);
+        self
+    }
+
+    /// Whether a window sits in one of the workspaces minimized windows go to.
+    pub fn is_minimized(&self, client: &Client) -> bool {
+        self.config.is_minimized_workspace(&client.workspace.name)
+    }
+
+    /// Clients currently sitting in the minimized workspaces.
+    pub fn minimized_clients(&self) -> Result<Vec<Client>> {
+        Ok(self.compositor.clients()?.into_iter().filter(|c| self.is_minimized(c)).collect())
+    }
+
+    /// Minimized windows, checked against Hyprland.
+    pub fn windows(&self) -> Result<Vec<MinimizedWindow>> {
+        let _lock = self.store.lock()?;
+
+        let windows = self.store.load()?;
+        // Additional validation to ensure that windows still exist
+        self.validate_cached_windows(windows)
+    }
+
+    /// Windows in the order every menu lists them: by `[menu] sort`, then
+    /// gathered by `group_by`, with the `menu_top` windows first.
+    pub fn menu_order<'a>(&self, windows: &'a [MinimizedWindow]) -> Vec<&'a MinimizedWindow> {
+        let mut sorted: Vec<&MinimizedWindow> = match self.config.menu.sort {
+            MenuSort::Recency => windows_by_recency(windows),
+            _ => windows.iter().collect(),
+        };
+        // Stable sorts, so each keeps the previous order among equals
+        match self.config.menu.sort {
+            MenuSort::Workspace => sorted.sort_by_key(|w| w.workspace),
+            MenuSort::Class => sorted.sort_by_key(|w| w.class.to_lowercase()),
+            MenuSort::Minimized | MenuSort::Recency => {}
+        }
+        match self.config.menu.group_by {
+            MenuGroup::Workspace => sorted.sort_by_key(|w| w.workspace),
+            MenuGroup::Class => sorted.sort_by_key(|w| w.class.to_lowercase()),
+            MenuGroup::None => {}
+        }
+        sorted.sort_by_key(|w| !self.config.menu_top(&w.class, &w.original_title));
+        sorted
+    }
+
+    /// Header of the group a window is listed under, unless `group_by` is off.
+    pub fn menu_group(&self, window: &MinimizedWindow) -> Option<String> {
+        match self.config.menu.group_by {
+            MenuGroup::None => None,
+            _ if self.config.menu_top(&window.class, &window.original_title) => Some(String::from("Pinned")),
+            MenuGroup::Workspace => Some(format!("Workspace {}", window.workspace)),
+            MenuGroup::Class => Some(format!("{} {}", window.icon, window.class)),
+        }
+    }
+
+    fn validate_cached_windows(&self, windows: Vec<MinimizedWindow>) -> Result<Vec<MinimizedWindow>> {
+        if windows.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Windows currently in the minimized special workspaces
+        let clients = self.minimized_clients()?;
+
+        // Filter only valid windows
+        let mut valid_windows = Vec::new();
+        let mut need_update = false;
+
+        for window in windows {
+            // The window must exist in the system AND still be minimized
+            if clients.iter().any(|c| c.address == window.address) {
+                valid_windows.push(window);
+            } else {
+                need_update = true;
+            }
+        }
+
+        // If we found invalid windows, try to re-adopt them before updating the cache
+        if need_update {
+            let resynced = self.resync_windows(self.store.load()?)?;
+            self.store.save(&resynced)?;
+            self.effects.refresh_bars(&self.config);
+            return Ok(resynced);
+        }
+
+        Ok(valid_windows)
+    }
+
+    /// Rebuild the cache from the windows in the minimized workspaces and save it.
+    pub fn resync(&self) -> Result<Vec<MinimizedWindow>> {
+        let _lock = self.store.lock()?;
+
+        let windows = self.resync_windows(self.store.load()?)?;
+        self.store.save(&windows)?;
+        self.effects.refresh_bars(&self.config);
+        Ok(windows)
+    }
+
+    // Rebuild the cache from the windows actually sitting in the minimized workspaces.
+    // Entries whose address is gone (e.g. after a Hyprland restart) are matched
+    // to surviving windows by class and title, then by class alone. Windows
+    // carrying the minhypr tag but missing from the cache (e.g. it was deleted)
+    // get a fresh entry.
+    fn resync_windows(&self, windows: Vec<MinimizedWindow>) -> Result<Vec<MinimizedWindow>> {
+        let clients = self.minimized_clients()?;
+
+        let mut resynced = Vec::new();
+        let mut orphans = Vec::new();
+
+        for window in windows {
+            if clients.iter().any(|c| c.address == window.address) {
+                resynced.push(window);
+            } else {
+                orphans.push(window);
+            }
+        }
+
+        for client in &clients {
+            if resynced.iter().any(|w| w.address == client.address) {
+                continue;
+            }
+
+            let matched = orphans
+                .iter()
+                .position(|w| w.class == client.class && w.original_title == self.config.stored_title(&client.class, &client.title))
+                .or_else(|| orphans.iter().position(|w| w.class == client.class));
+
+            if let Some(index) = matched {
+                let mut window = orphans.remove(index);
+                info!("Re-adopted window: {} ({} -> {})", window.class, window.address, client.address);
+                window.original_title = self.config.stored_title(&client.class, &client.title);
+                window.address = client.address.clone();
+                window.display_title = self.config.display_title(&window);
+                window.pid = client.pid;
+                resynced.push(window);
+            } else if client.has_tag(MINHYPR_TAG) {
+                info!("Recovered tagged window: {} ({})", client.class, client.address);
+                resynced.push(self.window_entry(client)?);
+            }
+        }
+
+        for window in &orphans {
+            info!("Dropped window that no longer exists: {} ({})", window.class, window.address);
+            self.remove_previews(&window.address, window.preview_path.as_deref(), &resynced)?;
+        }
+
+        Ok(resynced)
+    }
+
+    /// Take over windows sitting in the minimized workspaces that minhypr
+    /// doesn't track (e.g. moved there by another script), or only the one
+    /// with `address`. They get fresh metadata and restore to the active
+    /// workspace. Returns the adopted addresses.
+    pub fn adopt(&self, address: Option<&str>) -> Result<Vec<String>> {
+        let _lock = self.store.lock()?;
+
+        let mut windows = self.windows()?;
+        let mut adopted = Vec::new();
+
+        for client in self.minimized_clients()? {
+            if address.is_some_and(|address| address != client.address) || windows.iter().any(|w| w.address == client.address) {
+                continue;
+            }
+
+            info!("Adopted window: {} ({})", client.class, client.address);
+            self.compositor.tag_window(&client.address, MINHYPR_TAG, true)?;
+            adopted.push(client.address.clone());
+            windows.push(self.window_entry(&client)?);
+        }
+
+        if !adopted.is_empty() {
+            self.store.save(&windows)?;
+            self.effects.refresh_bars(&self.config);
+        }
+
+        Ok(adopted)
+    }
+
+    /// Stop managing a minimized window without moving it: its cache entry and
+    /// tag are dropped, and a stopped or muted process is let go.
+    pub fn disown(&self, window_id: &str) -> Result<()> {
+        let _lock = self.store.lock()?;
+
+        let mut windows = self.windows()?;
+        let Some(index) = windows.iter().position(|w| w.address == window_id) else {
+            return Err(Error::WindowNotFound(window_id.to_string()).into());
+        };
+        let window = windows.remove(index);
+
+        if window.suspended {
+            self.effects.resume(window.pid);
+        }
+        if window.muted {
+            self.effects.set_muted(window.pid, false);
+        }
+        for other in windows.iter_mut().filter(|w| w.pid == window.pid) {
+            other.suspended &= !window.suspended;
+            other.muted &= !window.muted;
+        }
+
+        self.compositor.tag_window(window_id, MINHYPR_TAG, false)?;
+        self.store.save(&windows)?;
+        self.effects.refresh_bars(&self.config);
+
+        info!("Disowned window: {} ({})", window.class, window.address);
+        Ok(())
+    }
+
+    pub fn restore(&self, window_id: &str) -> Result<()> {
+        info!("Restoring window: {}", window_id);
+
+        if self.restore_many(&[window_id.to_string()])?.is_empty() {
+            return Err(Error::WindowNotFound(window_id.to_string()).into());
+        }
+
+        Ok(())
+    }
+
+    /// Restore a window to the given workspace (an id, a name, or a Hyprland
+    /// selector such as `name:mail` or `special:scratch`) instead of its own.
+    pub fn restore_to_workspace(&self, window_id: &str, workspace: &str) -> Result<()> {
+        info!("Restoring window {} to workspace {}", window_id, workspace);
+
+        if self.restore_windows(&[window_id.to_string()], Some(&workspace_selector(workspace)))?.is_empty() {
+            return Err(Error::WindowNotFound(window_id.to_string()).into());
+        }
+
+        Ok(())
+    }
+
+    /// Restore several minimized windows with a single batched dispatch and
+    /// cache update, in the given order (the last one ends up focused).
+    /// Addresses that aren't minimized are skipped; returns the restored ones.
+    pub fn restore_many(&self, addresses: &[String]) -> Result<Vec<String>> {
+        self.restore_windows(addresses, None)
+    }
+
+    // Restore to the configured target, or to `to_workspace` when given
+    fn restore_windows(&self, addresses: &[String], to_workspace: Option<&str>) -> Result<Vec<String>> {
+        let _lock = self.store.lock()?;
+
+        let windows = self.windows()?;
+        let targets: Vec<&MinimizedWindow> = addresses
+            .iter()
+            .filter_map(|address| windows.iter().find(|w| w.address == *address))
+            .collect();
+        if targets.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // A stopped process can't map its window again; the whole process
+        // resumes (and is heard again), so its other minimized windows aren't
+        // suspended or muted anymore
+        let mut resumed_pids = Vec::new();
+        let mut unmuted_pids = Vec::new();
+        for window in &targets {
+            if window.suspended && !resumed_pids.contains(&window.pid) && self.effects.resume(window.pid) {
+                resumed_pids.push(window.pid);
+            }
+            if window.muted && !unmuted_pids.contains(&window.pid) && self.effects.set_muted(window.pid, false) {
+                unmuted_pids.push(window.pid);
+            }
+        }
+
+        // A window whose monitor was unplugged would land on a workspace
+        // nobody sees, so it may go to the active one instead
+        let monitor_names = self.compositor.monitor_names()?;
+        let monitor_gone = |window: &MinimizedWindow| match &window.monitor {
+            Some(monitor) => !monitor_names.contains(monitor),
+            // Remapped, but its workspace didn't survive the unplug
+            None => window.home_monitor.is_some(),
+        };
+        let target_for = |window: &MinimizedWindow| match self.config.restore_target {
+            RestoreTarget::Origin if monitor_gone(window) => self.config.missing_monitor_target,
+            target => target,
+        };
+
+        let active = if to_workspace.is_none() && targets.iter().any(|w| target_for(w) == RestoreTarget::Current) {
+            Some(self.compositor.active_workspace()?).filter(|ws| ws.id != 0)
+        } else {
+            None
+        };
+
+        // The same dispatches as restoring the windows one by one, in one request
+        let mut actions = Vec::new();
+        for window in &targets {
+            let (workspace, monitor) = match (to_workspace, &active, target_for(window)) {
+                // Wherever that is, Hyprland shows it on its own monitor
+                (Some(workspace), _, _) => (workspace.to_string(), None),
+                (None, Some(active), RestoreTarget::Current) => (active.id.to_string(), Some(active.monitor.clone())),
+                _ => (window.workspace.to_string(), window.monitor.clone()),
+            };
+            if to_workspace.is_none() && monitor_gone(window) {
+                debug!("Monitor of {} ({}) is gone, restoring to workspace {}", window.class, window.address, workspace);
+            }
+
+            if self.config.restore_focus {
+                // Go to the target screen first, so the workspace is shown (or created) there
+                if let Some(monitor) = monitor.as_ref().filter(|m| monitor_names.contains(m)) {
+                    actions.push(Action::FocusMonitor(monitor.clone()));
+                }
+                actions.push(Action::MoveToWorkspace { workspace: workspace.clone(), address: window.address.clone(), silent: false });
+                actions.push(Action::FocusWindow(window.address.clone()));
+            } else {
+                actions.push(Action::MoveToWorkspace { workspace, address: window.address.clone(), silent: true });
+            }
+
+            // The saved position is only meaningful on the original monitor
+            actions.extend(self.geometry_actions(window, monitor == window.monitor));
+            actions.push(Action::Tag { address: window.address.clone(), tag: MINHYPR_TAG.to_string(), add: false });
+        }
+
+        if !self.compositor.run(&actions)? {
+            warn!("Hyprland refused some of the dispatches restoring {} windows", targets.len());
+        }
+        self.rejoin_groups(&targets)?;
+
+        // Only the last restored window keeps its previews, for undo
+        let restored: Vec<String> = targets.iter().map(|w| w.address.clone()).collect();
+        let last = targets[targets.len() - 1];
+        let mut stale_previews: Vec<MinimizedWindow> = targets.iter().filter(|w| w.address != last.address).map(|w| (*w).clone()).collect();
+        if let Some(previous) = self.store.load_last_restore()? {
+            if previous.address != last.address && !windows.iter().any(|w| w.address == previous.address) {
+                stale_previews.push(previous);
+            }
+        }
+        self.store.save_last_restore(Some(last))?;
+        let kept: Vec<MinimizedWindow> = windows.iter().filter(|w| !stale_previews.iter().any(|s| s.address == w.address)).cloned().collect();
+        for window in &stale_previews {
+            self.remove_previews(&window.address, window.preview_path.as_deref(), &kept)?;
+        }
+
+        // Update cache with remaining windows
+        let remaining: Vec<MinimizedWindow> = windows
+            .iter()
+            .filter(|w| !restored.contains(&w.address))
+            .cloned()
+            .map(|mut window| {
+                window.suspended &= !resumed_pids.contains(&window.pid);
+                window.muted &= !unmuted_pids.contains(&window.pid);
+                window
+            })
+            .collect();
+        self.store.save(&remaining)?;
+
+        if self.config.history {
+            self.effects.record_history(EventKind::Restore, &targets);
+        }
+        for window in &targets {
+            self.effects.run_hook(self.config.on_restore.as_deref(), "restore", window);
+        }
+
+        Ok(restored)
+    }
+
+    // Put windows taken out of their group back into it: from the window,
+    // `moveintogroup` toward a member still in the group on its workspace
+    fn rejoin_groups(&self, windows: &[&MinimizedWindow]) -> Result<()> {
+        if !self.config.rejoin_groups || windows.iter().all(|w| w.group.is_empty()) {
+            return Ok(());
+        }
+
+        let clients = self.compositor.clients()?;
+        let mut actions = Vec::new();
+        for window in windows.iter().filter(|w| !w.group.is_empty()) {
+            // Came back with its group, or can't be grouped by direction
+            let Some(client) = clients.iter().find(|c| c.address == window.address && c.grouped.is_empty() && !c.floating) else {
+                continue;
+            };
+            let Some(member) = clients
+                .iter()
+                .find(|c| window.group.contains(&c.address) && c.workspace.id == client.workspace.id && !c.grouped.is_empty())
+            else {
+                continue;
+            };
+
+            let center = |c: &Client| (c.at.0 + c.size.0 / 2, c.at.1 + c.size.1 / 2);
+            let ((x, y), (member_x, member_y)) = (center(client), center(member));
+            let (dx, dy) = (member_x - x, member_y - y);
+            let direction = match (dx.abs() >= dy.abs(), dx > 0, dy > 0) {
+                (true, true, _) => 'r',
+                (true, false, _) => 'l',
+                (false, _, true) => 'd',
+                (false, _, false) => 'u',
+            };
+            debug!("Moving {} ({}) back into its group ({})", window.class, window.address, direction);
+            actions.push(Action::FocusWindow(window.address.clone()));
+            actions.push(Action::MoveIntoGroup(direction));
+        }
+        if actions.is_empty() {
+            return Ok(());
+        }
+
+        // moveintogroup works on the focused window; put the focus back where
+        // the restore left it
+        if let Some(focused) = self.compositor.active_window()? {
+            actions.push(Action::FocusWindow(focused.address));
+        }
+        if !self.compositor.run(&actions)? {
+            warn!("Hyprland refused to put some restored windows back into their group");
+        }
+
+        Ok(())
+    }
+
+    // Floating geometry and fullscreen state lost by the move
+    fn geometry_actions(&self, window: &MinimizedWindow, restore_position: bool) -> Vec<Action> {
+        let mut actions = Vec::new();
+
+        if window.floating {
+            actions.push(Action::SetFloating(window.address.clone()));
+
+            if let Some(size) = window.size {
+                actions.push(Action::ResizeWindowPixel { address: window.address.clone(), size });
+            }
+            if let Some(position) = window.position.filter(|_| restore_position) {
+                actions.push(Action::MoveWindowPixel { address: window.address.clone(), position });
+            }
+        }
+
+        // The fullscreen dispatcher acts on the focused window
+        if self.config.restore_focus {
+            match window.fullscreen {
+                1 => actions.push(Action::Fullscreen(1)),
+                2 => actions.push(Action::Fullscreen(0)),
+                _ => {}
+            }
+        }
+
+        actions
+    }
+
+    /// A window got the focus (Hyprland's `activewindowv2` event). If it is
+    /// minimized, something asked to activate it (e.g. a link opened in a
+    /// minimized browser), so it is restored unless it is being peeked at or
+    /// `restore_on_activate` is off. Returns whether it was restored.
+    pub fn activated(&self, window_id: &str) -> Result<bool> {
+        let _lock = self.store.lock()?;
+
+        let minimized = self.windows()?.iter().any(|w| w.address == window_id)
+            && self.compositor.client(window_id)?.is_some_and(|c| self.is_minimized(&c));
+
+        let peeked = self.store.load_group(PEEK_GROUP)?;
+        if !minimized {
+            // Focus moved on, so the peek is over
+            if !peeked.is_empty() {
+                self.store.save_group(PEEK_GROUP, &[])?;
+            }
+            return Ok(false);
+        }
+        if !self.config.restores_on_activate() || peeked.iter().any(|a| a == window_id) {
+            return Ok(false);
+        }
+
+        debug!("Minimized window {} was activated, restoring it", window_id);
+        self.restore(window_id)?;
+        Ok(true)
+    }
+
+    /// A window asked for attention (Hyprland's `urgent` event): if it is
+    /// minimized, restore it, mark it or notify, per `on_urgent`. Returns
+    /// whether it was a minimized window.
+    pub fn mark_urgent(&self, window_id: &str) -> Result<bool> {
+        let _lock = self.store.lock()?;
+
+        let mut windows = self.windows()?;
+        let Some(window) = windows.iter_mut().find(|w| w.address == window_id) else {
+            return Ok(false);
+        };
+        if window.urgent {
+            return Ok(true);
+        }
+
+        match self.config.on_urgent {
+            UrgentAction::Ignore => {}
+            UrgentAction::Restore => self.restore(window_id)?,
+            action => {
+                window.urgent = true;
+                self.store.save(&windows)?;
+                self.effects.refresh_bars(&self.config);
+                if action == UrgentAction::Notify {
+                    self.effects.notify(window_id, true)?;
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Follow monitor changes: windows from an unplugged monitor are pointed at
+    /// the monitor their workspace moved to, and back at their own once it is
+    /// connected again. Returns the number of windows updated.
+    pub fn remap_monitors(&self) -> Result<usize> {
+        let _lock = self.store.lock()?;
+
+        let monitor_names = self.compositor.monitor_names()?;
+        let workspaces = self.compositor.workspaces()?;
+        let mut windows = self.windows()?;
+
+        let mut remapped = 0;
+        for window in &mut windows {
+            if let Some(home) = window.home_monitor.take_if(|home| monitor_names.contains(home)) {
+                window.monitor = Some(home);
+            } else if let Some(monitor) = window.monitor.take_if(|m| !monitor_names.contains(m)) {
+                window.home_monitor.get_or_insert(monitor);
+                window.monitor = workspaces
+                    .iter()
+                    .find(|ws| ws.id == window.workspace as i64)
+                    .map(|ws| ws.monitor.clone());
+            } else {
+                continue;
+            }
+            remapped += 1;
+        }
+
+        if remapped > 0 {
+            self.store.save(&windows)?;
+            info!("Remapped the monitor of {} windows", remapped);
+        }
+
+        Ok(remapped)
+    }
+
+    /// Minimize the most recently restored window again, with the metadata and
+    /// preview it had before. Returns its address.
+    pub fn undo_restore(&self) -> Result<Option<String>> {
+        let _lock = self.store.lock()?;
+
+        let Some(window) = self.store.load_last_restore()? else {
+            return Ok(None);
+        };
+        self.store.save_last_restore(None)?;
+
+        self.reminimize(window)
+    }
+
+    // Move a restored window back to its minimized workspace with the cache entry
+    // (metadata and preview) it had before
+    fn reminimize(&self, window: MinimizedWindow) -> Result<Option<String>> {
+        let _lock = self.store.lock()?;
+
+        // Closed or minimized again in the meantime
+        match self.compositor.client(&window.address)? {
+            Some(client) if !self.is_minimized(&client) => {}
+            _ => return Ok(None),
+        }
+
+        if !self.compositor.move_to_workspace_silent(&self.config.minimized_workspace_for(&window.class), &window.address)? {
+            return Ok(None);
+        }
+        self.compositor.tag_window(&window.address, MINHYPR_TAG, true)?;
+
+        let address = window.address.clone();
+        let mut windows = self.windows()?;
+        windows.push(window.clone());
+        self.park_hidden_processes(&mut windows)?;
+        self.store.save(&windows)?;
+        self.effects.refresh_bars(&self.config);
+        if self.config.history {
+            self.effects.record_history(EventKind::Minimize, &[&window]);
+        }
+        self.effects.run_hook(self.config.on_minimize.as_deref(), "minimize", &window);
+
+        Ok(Some(address))
+    }
+
+    /// Glance at a minimized window. Without a duration the special workspace
+    /// is shown with the window focused; with one the window is restored for
+    /// that long and then minimized again.
+    pub fn peek(&self, window_id: &str, duration: Option<Duration>) -> Result<bool> {
+        let Some(window) = self.windows()?.into_iter().find(|w| w.address == window_id) else {
+            return Err(Error::WindowNotFound(window_id.to_string()).into());
+        };
+
+        let Some(duration) = duration else {
+            // Focusing a window in a special workspace opens it as an overlay
+            self.store.save_group(PEEK_GROUP, &[window_id.to_string()])?;
+            return self.compositor.focus_window(window_id);
+        };
+
+        // The lock isn't held while waiting, so other commands keep working
+        self.restore(window_id)?;
+        thread::sleep(duration);
+        Ok(self.reminimize(window)?.is_some())
+    }
+
+    /// Close a window, resuming its process first if minhypr stopped it (a
+    /// stopped process can't answer the close request).
+    pub fn close(&self, window_id: &str) -> Result<bool> {
+        let windows = self.windows()?;
+        if let Some(window) = windows.iter().find(|w| w.address == window_id && w.suspended) {
+            self.effects.resume(window.pid);
+
+            let _lock = self.store.lock()?;
+            let mut windows = self.windows()?;
+            for other in windows.iter_mut().filter(|w| w.pid == window.pid) {
+                other.suspended = false;
+            }
+            self.store.save(&windows)?;
+        }
+
+        self.compositor.close_window(window_id)
+    }
+
+    /// Close a minimized window and forget it right away: its cache entry and
+    /// preview files go without waiting for the daemon to notice. Should the
+    /// app keep the window open (e.g. to ask about unsaved changes), resync
+    /// takes it back by its tag.
+    pub fn close_minimized(&self, window_id: &str) -> Result<()> {
+        let _lock = self.store.lock()?;
+
+        if !self.windows()?.iter().any(|w| w.address == window_id) {
+            return Err(Error::WindowNotFound(window_id.to_string()).into());
+        }
+        if !self.close(window_id)? {
+            return Err(io::Error::other(format!("Hyprland refused to close {}", window_id)));
+        }
+
+        // Already gone if the validation saw it close
+        let mut windows = self.store.load()?;
+        let Some(index) = windows.iter().position(|w| w.address == window_id) else {
+            return Ok(());
+        };
+        let window = windows.remove(index);
+        self.remove_previews(&window.address, window.preview_path.as_deref(), &windows)?;
+        self.store.save(&windows)?;
+        self.effects.refresh_bars(&self.config);
+        info!("Closed window: {} ({})", window.class, window.address);
+        Ok(())
+    }
+
+    /// Capture the thumbnails of minimized windows again, all of them or only
+    /// `window_id`, through toplevel export. Returns the refreshed addresses.
+    pub fn refresh_previews(&self, window_id: Option<&str>) -> Result<Vec<String>> {
+        let windows = self.windows()?;
+        if let Some(id) = window_id {
+            if !windows.iter().any(|w| w.address == id) {
+                return Err(Error::WindowNotFound(id.to_string()).into());
+            }
+        }
+
+        let mut refreshed = Vec::new();
+        let mut failure = None;
+        for window in windows.iter().filter(|w| window_id.is_none_or(|id| w.address == id)) {
+            if self.config.never_preview(&window.class, &window.original_title) {
+                continue;
+            }
+            let screenshot = match self.effects.capture_toplevel(self.store.preview_dir(), &window.address) {
+                Ok(screenshot) => screenshot,
+                Err(e) => {
+                    debug!("No new preview for {} ({}): {}", window.class, window.address, e);
+                    failure = Some(e);
+                    continue;
+                }
+            };
+            self.generate_preview(&window.address, &screenshot)?;
+            refreshed.push(window.address.clone());
+        }
+
+        // Nothing captured at all, e.g. without grim-hyprland
+        match failure {
+            Some(e) if refreshed.is_empty() => Err(e),
+            _ => Ok(refreshed),
+        }
+    }
+
+    /// Delete the preview files no minimized window uses anymore, and with
+    /// `max_age` also older ones, which the windows then go without. Returns
+    /// the number of files removed.
+    pub fn collect_previews(&self, max_age: Option<Duration>) -> Result<usize> {
+        let _lock = self.store.lock()?;
+
+        let mut windows = self.windows()?;
+        let mut last_restore = self.store.load_last_restore()?;
+        let referenced: Vec<String> = windows.iter().chain(last_restore.iter()).filter_map(|w| w.preview_path.clone()).collect();
+        let removed = preview::collect_garbage(self.store.preview_dir(), &referenced, max_age)?;
+
+        let gone = |window: &MinimizedWindow| window.preview_path.as_ref().is_some_and(|p| !Path::new(p).exists());
+        if windows.iter().any(gone) {
+            for window in windows.iter_mut().filter(|w| gone(w)) {
+                window.preview_path = None;
+            }
+            self.store.save(&windows)?;
+            self.effects.refresh_bars(&self.config);
+        }
+        if let Some(window) = last_restore.as_mut().filter(|w| gone(w)) {
+            window.preview_path = None;
+            self.store.save_last_restore(Some(window))?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Delete every preview file and forget the previews of the minimized
+    /// windows. Returns the number of files removed.
+    pub fn purge_previews(&self) -> Result<usize> {
+        let _lock = self.store.lock()?;
+
+        let removed = preview::remove_all_previews(self.store.preview_dir())?;
+
+        let mut windows = self.windows()?;
+        for window in &mut windows {
+            window.preview_path = None;
+        }
+        self.store.save(&windows)?;
+        self.store.save_last_restore(None)?;
+
+        Ok(removed)
+    }
+
+    /// Start over: optionally bring back every window in the minimized
+    /// workspaces first (tracked or not), then clear all state and previews.
+    /// Returns the number of windows brought back.
+    pub fn reset(&self, restore: bool) -> Result<usize> {
+        let _lock = self.store.lock()?;
+
+        let mut restored = 0;
+        if restore {
+            let tracked: Vec<String> = self.windows()?.into_iter().map(|w| w.address).collect();
+            restored += self.restore_many(&tracked)?.len();
+
+            // Not ours to place, so they go to the active workspace
+            let workspace = self.compositor.active_workspace()?.id.to_string();
+            let moves: Vec<Action> = self
+                .minimized_clients()?
+                .into_iter()
+                .map(|client| Action::MoveToWorkspace { workspace: workspace.clone(), address: client.address, silent: true })
+                .collect();
+            self.compositor.run(&moves)?;
+            restored += moves.len();
+        } else {
+            // Windows left hidden would stay stopped or muted forever
+            for window in self.windows()? {
+                if window.suspended {
+                    self.effects.resume(window.pid);
+                }
+                if window.muted {
+                    self.effects.set_muted(window.pid, false);
+                }
+            }
+        }
+
+        // Pins are settings rather than state, so they survive
+        let pins = self.store.load_group(PIN_GROUP)?;
+        self.store.clear()?;
+        self.store.save_group(PIN_GROUP, &pins)?;
+        preview::remove_all_previews(self.store.preview_dir())?;
+        self.effects.refresh_bars(&self.config);
+
+        Ok(restored)
+    }
+
+    pub fn restore_all(&self) -> Result<()> {
+        let addresses: Vec<String> = self.windows()?.into_iter().map(|w| w.address).collect();
+        self.restore_many(&addresses)?;
+
+        Ok(())
+    }
+
+    /// Restore the minimized windows whose class and title match the given
+    /// patterns, most recently minimized first, up to `limit` windows.
+    pub fn restore_matching(&self, class: Option<&Regex>, title: Option<&Regex>, limit: Option<usize>) -> Result<Vec<String>> {
+        let _lock = self.store.lock()?;
+
+        let windows = self.windows()?;
+        let matching: Vec<String> = windows_by_recency(&windows)
+            .into_iter()
+            .filter(|w| class.is_none_or(|re| re.is_match(&w.class)))
+            .filter(|w| title.is_none_or(|re| re.is_match(&w.original_title)))
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|w| w.address.clone())
+            .collect();
+
+        self.restore_many(&matching)?;
+
+        Ok(matching)
+    }
+
+    /// Restore the Nth window (1-based) in most-recently-minimized order.
+    pub fn restore_index(&self, index: usize) -> Result<Option<String>> {
+        let _lock = self.store.lock()?;
+
+        let windows = self.windows()?;
+        let Some(window) = index.checked_sub(1).and_then(|i| windows_by_recency(&windows).get(i).copied()) else {
+            return Ok(None);
+        };
+
+        let address = window.address.clone();
+        self.restore(&address)?;
+
+        Ok(Some(address))
+    }
+
+    /// Minimize the active window.
+    pub fn minimize_active(&self) -> Result<()> {
+        match self.compositor.active_window()? {
+            Some(client) => self.minimize(&client).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    /// Have the daemon restore minimized windows once `duration` has passed.
+    pub fn snooze(&self, addresses: &[String], duration: Duration) -> Result<()> {
+        let _lock = self.store.lock()?;
+
+        let restore_at = unix_timestamp_ms() + duration.as_millis() as u64;
+        let mut windows = self.windows()?;
+        for window in windows.iter_mut().filter(|w| addresses.contains(&w.address)) {
+            window.restore_at = Some(restore_at);
+        }
+        self.store.save(&windows)
+    }
+
+    /// Restore the snoozed windows whose timer has expired, returning them.
+    pub fn restore_due(&self) -> Result<Vec<MinimizedWindow>> {
+        let now = unix_timestamp_ms();
+        let due: Vec<MinimizedWindow> = self
+            .windows()?
+            .into_iter()
+            .filter(|w| w.restore_at.is_some_and(|at| at <= now))
+            .collect();
+
+        let addresses: Vec<String> = due.iter().map(|w| w.address.clone()).collect();
+        self.restore_many(&addresses)?;
+
+        Ok(due)
+    }
+
+    /// Minimize the active (or given) window, or restore it if already minimized.
+    pub fn toggle(&self, window_id: Option<&str>) -> Result<()> {
+        let _lock = self.store.lock()?;
+
+        let windows = self.windows()?;
+
+        let client = match window_id {
+            Some(id) => {
+                if windows.iter().any(|w| w.address == id) {
+                    return self.restore(id);
+                }
+                self.compositor.client(id)?
+            }
+            None => self.compositor.active_window()?,
+        };
+
+        match client {
+            // The focused window may itself be minimized (special workspace shown)
+            Some(client) if windows.iter().any(|w| w.address == client.address) => self.restore(&client.address),
+            Some(client) => self.minimize(&client).map(|_| ()),
+            None if window_id.is_none() => match most_recent_window(&windows) {
+                // Nothing focused: bring back the most recently minimized window
+                Some(window) => self.restore(&window.address),
+                None => Ok(()),
+            },
+            None => Err(Error::WindowNotFound(window_id.unwrap_or_default().to_string()).into()),
+        }
+    }
+
+    /// Toggle a class like a scratchpad: restore its most recently minimized
+    /// window, or else minimize its open one (the active one first), or else
+    /// launch `command`.
+    pub fn toggle_class(&self, class: &str, command: Option<&str>) -> Result<()> {
+        let _lock = self.store.lock()?;
+
+        let windows: Vec<MinimizedWindow> =
+            self.windows()?.into_iter().filter(|w| w.class.eq_ignore_ascii_case(class)).collect();
+        if let Some(window) = most_recent_window(&windows) {
+            return self.restore(&window.address);
+        }
+
+        let open = match self.compositor.active_window()? {
+            Some(active) if active.class.eq_ignore_ascii_case(class) => Some(active),
+            _ => self.compositor.clients()?.into_iter().find(|c| c.class.eq_ignore_ascii_case(class) && !self.is_minimized(c)),
+        };
+        match (open, command) {
+            (Some(client), _) => self.minimize(&client).map(|_| ()),
+            (None, Some(command)) => {
+                info!("No {} window, launching {}", class, command);
+                self.compositor.exec(command).map(|_| ())
+            }
+            (None, None) => Err(Error::WindowNotFound(class.to_string()).into()),
+        }
+    }
+
+    /// Toggle a `[scratchpads.<name>]` app: minimize its window when it's on the
+    /// current workspace, else bring it here floating at the configured size
+    /// and position, or launch it there when it isn't running.
+    pub fn scratch(&self, name: &str) -> Result<()> {
+        let _lock = self.store.lock()?;
+
+        let pad = self
+            .config
+            .scratchpads
+            .get(name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("No scratchpad {} in the config", name)))?;
+        if pad.class.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Scratchpad {} has no class", name)));
+        }
+
+        let workspace = self.compositor.active_workspace()?;
+        let monitor = self.compositor.monitors()?.into_iter().find(|m| m.focused).unwrap_or_default();
+        let logical = match monitor.logical_size() {
+            (0, _) | (_, 0) => (1920, 1080),
+            size => size,
+        };
+        let (size, (x, y)) = pad.geometry(logical);
+        let place = |address: &str| {
+            let address = address.to_string();
+            self.compositor.run(&[
+                Action::SetFloating(address.clone()),
+                Action::ResizeWindowPixel { address: address.clone(), size },
+                Action::MoveWindowPixel { address: address.clone(), position: (monitor.x + x, monitor.y + y) },
+                Action::FocusWindow(address),
+            ])
+        };
+
+        let windows: Vec<MinimizedWindow> =
+            self.windows()?.into_iter().filter(|w| w.class.eq_ignore_ascii_case(&pad.class)).collect();
+        if let Some(window) = most_recent_window(&windows) {
+            self.restore_to_workspace(&window.address, &workspace.name)?;
+            place(&window.address)?;
+            return Ok(());
+        }
+
+        let open = self.compositor.clients()?.into_iter().find(|c| c.class.eq_ignore_ascii_case(&pad.class) && !self.is_minimized(c));
+        match open {
+            Some(client) if client.workspace.id == workspace.id => self.minimize(&client).map(|_| ()),
+            Some(client) => {
+                self.compositor.move_to_workspace_silent(&workspace_selector(&workspace.name), &client.address)?;
+                place(&client.address).map(|_| ())
+            }
+            None if pad.command.is_empty() => Err(Error::WindowNotFound(pad.class.clone()).into()),
+            None => {
+                // Window rules place it before it's first shown; `move` is relative to the monitor
+                info!("Launching scratchpad {}: {}", name, pad.command);
+                let rules = format!("[float; size {} {}; move {} {}]", size.0, size.1, x, y);
+                self.compositor.exec(&format!("{} {}", rules, pad.command)).map(|_| ())
+            }
+        }
+    }
+
+    /// Swap the active window with the most recently minimized one, like alt-tab
+    /// between the foreground and the minimized stack. Returns the restored address.
+    pub fn cycle(&self) -> Result<Option<String>> {
+        let _lock = self.store.lock()?;
+
+        // Pick the target before the active window joins the stack
+        let windows = self.windows()?;
+        let Some(target) = most_recent_window(&windows).map(|w| w.address.clone()) else {
+            return Ok(None);
+        };
+
+        if let Some(active) = self.compositor.active_window()? {
+            if !self.is_minimized(&active) {
+                self.minimize(&active)?;
+            }
+        }
+
+        self.restore(&target)?;
+
+        Ok(Some(target))
+    }
+
+    // Workspace id and monitor name of the window, falling back to the active
+    // workspace when the window isn't on a regular workspace
+    fn window_location(&self, client: &Client) -> Result<(i32, Option<String>)> {
+        if client.workspace.id > 0 {
+            return Ok((client.workspace.id as i32, self.compositor.monitor_name(client.monitor)?));
+        }
+
+        // Get the current workspace and its monitor
+        let workspace = self.compositor.active_workspace()?;
+        if workspace.id == 0 {
+            // Default workspace if unable to get current one
+            return Ok((1, None));
+        }
+
+        Ok((workspace.id as i32, Some(workspace.monitor)))
+    }
+
+    /// Minimize a specific window by address; fails with `Error::WindowNotFound`
+    /// if no such window is open.
+    pub fn minimize_address(&self, address: &str) -> Result<bool> {
+        match self.compositor.client(address)? {
+            Some(client) => self.minimize(&client),
+            None => Err(Error::WindowNotFound(address.to_string()).into()),
+        }
+    }
+
+    /// Minimize every visible window whose class and title match the given patterns.
+    pub fn minimize_matching(&self, class: Option<&Regex>, title: Option<&Regex>) -> Result<Vec<String>> {
+        let clients: Vec<Client> = self
+            .compositor
+            .clients()?
+            .into_iter()
+            .filter(|c| !self.is_minimized(c))
+            .filter(|c| class.is_none_or(|re| re.is_match(&c.class)))
+            .filter(|c| title.is_none_or(|re| re.is_match(&c.title)))
+            .collect();
+
+        self.minimize_batch(&clients)
+    }
+
+    /// Minimize a window reported by `hyprctl`, returning whether it was moved.
+    pub fn minimize(&self, client: &Client) -> Result<bool> {
+        let _lock = self.store.lock()?;
+
+        let Some((window, screenshot)) = self.prepare_minimize(client, true)? else {
+            return Ok(false);
+        };
+
+        if self.limit_room()? == Some(0) {
+            warn!("Not minimizing {} ({}): {} windows are minimized already", window.class, window.address, self.config.max_minimized);
+            self.effects.notify_limit_reached(1, self.config.max_minimized);
+            if let Some(screenshot) = screenshot {
+                fs::remove_file(screenshot).ok();
+            }
+            return Ok(false);
+        }
+
+        // Move to special workspace (minimize)
+        if !self.compositor.run(&self.minimize_actions(&window))? {
+            warn!("Hyprland refused to move {} ({})", window.class, window.address);
+            if let Some(screenshot) = screenshot {
+                fs::remove_file(screenshot).ok();
+            }
+            return Ok(false);
+        }
+
+        self.finish_minimize(vec![(window, screenshot)])?;
+        self.make_room()?;
+
+        Ok(true)
+    }
+
+    /// Open windows that can be minimized: on regular workspaces and not
+    /// ignored by the rules.
+    pub fn open_clients(&self) -> Result<Vec<Client>> {
+        Ok(self
+            .compositor
+            .clients()?
+            .into_iter()
+            .filter(|c| c.workspace.id > 0 && !self.is_minimized(c) && !self.config.ignores(&c.class, &c.title))
+            .collect())
+    }
+
+    /// Minimize several windows with a single batched dispatch and cache update,
+    /// returning the addresses of the minimized windows.
+    pub fn minimize_batch(&self, clients: &[Client]) -> Result<Vec<String>> {
+        self.minimize_clients(clients, true)
+    }
+
+    // Move a window to its minimized workspace. Hyprland moves a grouped
+    // window's whole group along, unless it is taken out of the group first.
+    fn minimize_actions(&self, window: &MinimizedWindow) -> Vec<Action> {
+        let mut actions = Vec::new();
+        if !window.group.is_empty() && !self.config.minimize_groups {
+            actions.push(Action::MoveOutOfGroup(window.address.clone()));
+        }
+        actions.push(Action::MoveToWorkspace {
+            workspace: self.config.minimized_workspace_for(&window.class),
+            address: window.address.clone(),
+            silent: true,
+        });
+        actions
+    }
+
+    // Batched minimize, optionally without screenshots (useless once the
+    // screen is covered, e.g. by the lock screen)
+    fn minimize_clients(&self, clients: &[Client], capture: bool) -> Result<Vec<String>> {
+        let _lock = self.store.lock()?;
+
+        let pins = self.store.load_group(PIN_GROUP)?;
+        let mut prepared: Vec<(MinimizedWindow, Option<String>)> = Vec::new();
+        for client in clients {
+            if is_pinned(&pins, client) {
+                debug!("Not minimizing {} ({}): pinned", client.class, client.address);
+                continue;
+            }
+            // Already going along with its group
+            if self.config.minimize_groups && prepared.iter().any(|(window, _)| window.group.contains(&client.address)) {
+                continue;
+            }
+            // Screenshots must all be taken before anything moves
+            if let Some(entry) = self.prepare_minimize(client, capture)? {
+                prepared.push(entry);
+            }
+        }
+
+        if let Some(room) = self.limit_room()? {
+            if prepared.len() > room {
+                let refused = prepared.split_off(room);
+                warn!("Not minimizing {} windows: {} windows are minimized already", refused.len(), self.config.max_minimized);
+                self.effects.notify_limit_reached(refused.len(), self.config.max_minimized);
+                for screenshot in refused.into_iter().filter_map(|(_, screenshot)| screenshot) {
+                    fs::remove_file(screenshot).ok();
+                }
+            }
+        }
+
+        if prepared.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let moves: Vec<Action> = prepared.iter().flat_map(|(window, _)| self.minimize_actions(window)).collect();
+
+        if !self.compositor.run(&moves)? {
+            // Part of the batch may have gone through: track what actually moved
+            let hidden: Vec<String> = self.minimized_clients()?.into_iter().map(|c| c.address).collect();
+            let (moved, refused): (Vec<_>, Vec<_>) = prepared.into_iter().partition(|(window, _)| hidden.contains(&window.address));
+            warn!("Hyprland refused to minimize {} of {} windows", refused.len(), refused.len() + moved.len());
+            for screenshot in refused.into_iter().filter_map(|(_, screenshot)| screenshot) {
+                fs::remove_file(screenshot).ok();
+            }
+            prepared = moved;
+            if prepared.is_empty() {
+                return Ok(Vec::new());
+            }
+        }
+
+        let addresses = prepared.iter().map(|(window, _)| window.address.clone()).collect();
+        self.finish_minimize(prepared)?;
+        self.make_room()?;
+
+        Ok(addresses)
+    }
+
+    // How many more windows may be minimized, when `max_minimized` refuses
+    // minimizes past it
+    fn limit_room(&self) -> Result<Option<usize>> {
+        if self.config.max_minimized == 0 || self.config.on_limit != LimitAction::Refuse {
+            return Ok(None);
+        }
+        Ok(Some(self.config.max_minimized.saturating_sub(self.windows()?.len())))
+    }
+
+    // Restore or close the least recently minimized windows past `max_minimized`
+    fn make_room(&self) -> Result<()> {
+        let limit = self.config.max_minimized;
+        if limit == 0 || self.config.on_limit == LimitAction::Refuse {
+            return Ok(());
+        }
+
+        let windows = self.windows()?;
+        if windows.len() <= limit {
+            return Ok(());
+        }
+        let mut oldest = windows_by_recency(&windows);
+        oldest.reverse();
+        let evicted: Vec<String> = oldest[..windows.len() - limit].iter().map(|w| w.address.clone()).collect();
+
+        match self.config.on_limit {
+            LimitAction::Restore => {
+                info!("Restoring {} windows past the limit of {}", evicted.len(), limit);
+                self.restore_many(&evicted)?;
+            }
+            LimitAction::Close => {
+                info!("Closing {} windows past the limit of {}", evicted.len(), limit);
+                for address in &evicted {
+                    self.close_minimized(address)?;
+                }
+            }
+            LimitAction::Refuse => {}
+        }
+
+        Ok(())
+    }
+
+    /// Minimize every window on the active workspace, optionally keeping the active one.
+    pub fn minimize_workspace(&self, keep_active: bool) -> Result<Vec<String>> {
+        let workspace_id = self.compositor.active_workspace()?.id;
+        let active_address = match self.compositor.active_window()? {
+            Some(active) if keep_active => Some(active.address),
+            _ => None,
+        };
+
+        let clients: Vec<Client> = self
+            .compositor
+            .clients()?
+            .into_iter()
+            .filter(|c| c.workspace.id == workspace_id)
+            .filter(|c| active_address.as_ref() != Some(&c.address))
+            .collect();
+
+        self.minimize_batch(&clients)
+    }
+
+    /// Minimize the visible windows matching the patterns (without patterns,
+    /// the ones on the active workspace) under a stash name, adding to the
+    /// stash if it exists. Returns the newly minimized addresses.
+    pub fn stash(&self, name: &str, class: Option<&Regex>, title: Option<&Regex>) -> Result<Vec<String>> {
+        let _lock = self.store.lock()?;
+
+        let minimized = if class.is_none() && title.is_none() {
+            self.minimize_workspace(false)?
+        } else {
+            self.minimize_matching(class, title)?
+        };
+
+        self.add_to_group(&format!("{}{}", STASH_PREFIX, name), &minimized)?;
+
+        Ok(minimized)
+    }
+
+    /// Restore the windows of a stash that are still minimized and forget the
+    /// stash. Returns the restored addresses.
+    pub fn unstash(&self, name: &str) -> Result<Vec<String>> {
+        self.restore_group(&format!("{}{}", STASH_PREFIX, name))
+    }
+
+    /// Pin a window (by address) or a class, so that bulk minimizes
+    /// (`minimize-all`, `show-desktop`, focus mode, stashes, ...) leave it
+    /// alone. Returns false if it was already pinned.
+    pub fn pin(&self, target: &str) -> Result<bool> {
+        let _lock = self.store.lock()?;
+
+        let mut pins = self.store.load_group(PIN_GROUP)?;
+        if pins.iter().any(|p| p.eq_ignore_ascii_case(target)) {
+            return Ok(false);
+        }
+        pins.push(target.to_string());
+        self.store.save_group(PIN_GROUP, &pins)?;
+
+        Ok(true)
+    }
+
+    /// Unpin a window or class. Returns false if it wasn't pinned.
+    pub fn unpin(&self, target: &str) -> Result<bool> {
+        let _lock = self.store.lock()?;
+
+        let mut pins = self.store.load_group(PIN_GROUP)?;
+        let count = pins.len();
+        pins.retain(|p| !p.eq_ignore_ascii_case(target));
+        self.store.save_group(PIN_GROUP, &pins)?;
+
+        Ok(pins.len() != count)
+    }
+
+    /// Pinned addresses and classes, forgetting windows that were closed.
+    pub fn pins(&self) -> Result<Vec<String>> {
+        let _lock = self.store.lock()?;
+
+        let clients = self.compositor.clients()?;
+        let mut pins = self.store.load_group(PIN_GROUP)?;
+        pins.retain(|p| !is_address(p) || clients.iter().any(|c| c.address == *p));
+        self.store.save_group(PIN_GROUP, &pins)?;
+
+        Ok(pins)
+    }
+
+    /// Stash names with the number of their windows still minimized.
+    pub fn stashes(&self) -> Result<Vec<(String, usize)>> {
+        self.groups_with_prefix(STASH_PREFIX)
+    }
+
+    /// Minimize every window on a workspace (by id or name; the active one by
+    /// default) as a snapshot that `unstash_workspace` brings back in the same
+    /// layout order. Returns the minimized addresses.
+    pub fn stash_workspace(&self, workspace: Option<&str>) -> Result<Vec<String>> {
+        let _lock = self.store.lock()?;
+
+        let workspace = match workspace {
+            Some(workspace) => {
+                let found = self.compositor.workspaces()?.into_iter().find(|w| w.name == workspace || w.id.to_string() == workspace);
+                match found {
+                    Some(found) => found,
+                    // No windows, nothing to stash
+                    None => return Ok(Vec::new()),
+                }
+            }
+            None => self.compositor.active_workspace()?,
+        };
+
+        // Tiled windows in reading order, then the floating ones: restoring
+        // them in this order rebuilds a similar layout
+        let mut clients: Vec<Client> = self.compositor.clients()?.into_iter().filter(|c| c.workspace.id == workspace.id).collect();
+        clients.sort_by_key(|c| (c.floating, c.at.0, c.at.1));
+
+        let minimized = self.minimize_batch(&clients)?;
+        self.add_to_group(&format!("{}{}", WORKSPACE_STASH_PREFIX, workspace.name), &minimized)?;
+
+        Ok(minimized)
+    }
+
+    /// Restore the windows of a stashed workspace to that workspace, in the
+    /// order they had, and forget the stash. Returns the restored addresses.
+    pub fn unstash_workspace(&self, workspace: &str) -> Result<Vec<String>> {
+        let _lock = self.store.lock()?;
+
+        let group = format!("{}{}", WORKSPACE_STASH_PREFIX, workspace);
+        let addresses = self.store.load_group(&group)?;
+        // Named workspaces get a new id when recreated, so go by name
+        let restored = self.restore_windows(&addresses, Some(&workspace_selector(workspace)))?;
+        self.store.save_group(&group, &[])?;
+
+        Ok(restored)
+    }
+
+    /// Stashed workspaces with the number of their windows still minimized.
+    pub fn workspace_stashes(&self) -> Result<Vec<(String, usize)>> {
+        self.groups_with_prefix(WORKSPACE_STASH_PREFIX)
+    }
+
+    // Groups under a prefix, without it, with their number of minimized windows
+    fn groups_with_prefix(&self, prefix: &str) -> Result<Vec<(String, usize)>> {
+        let windows = self.windows()?;
+
+        let mut groups = Vec::new();
+        for group in self.store.group_names()? {
+            let Some(name) = group.strip_prefix(prefix) else {
+                continue;
+            };
+            let addresses = self.store.load_group(&group)?;
+            let count = addresses.iter().filter(|a| windows.iter().any(|w| &&w.address == a)).count();
+            groups.push((name.to_string(), count));
+        }
+
+        Ok(groups)
+    }
+
+    /// Focus mode on: minimize the visible windows of the configured
+    /// `distractions` classes and remember them. Calling it again while on
+    /// adds any new distractions. Returns the newly minimized addresses.
+    pub fn focus_on(&self) -> Result<Vec<String>> {
+        self.minimize_into_group(FOCUS_GROUP, true, |class| self.config.is_distraction(class))
+    }
+
+    /// Focus mode off: restore exactly the windows focus mode minimized that
+    /// are still minimized, leaving manually minimized ones alone.
+    pub fn focus_off(&self) -> Result<Vec<String>> {
+        self.restore_group(FOCUS_GROUP)
+    }
+
+    /// Whether focus mode is holding minimized windows.
+    pub fn focus_active(&self) -> Result<bool> {
+        Ok(!self.store.load_group(FOCUS_GROUP)?.is_empty())
+    }
+
+    /// Session went idle: minimize the windows of the `idle_classes`, e.g.
+    /// from hypridle's `on-timeout`.
+    pub fn idle_timeout(&self) -> Result<Vec<String>> {
+        self.minimize_into_group(IDLE_GROUP, true, |class| self.config.is_idle_class(class))
+    }
+
+    /// Session resumed: restore the windows hidden by `idle_timeout` that are
+    /// still minimized.
+    pub fn idle_resume(&self) -> Result<Vec<String>> {
+        self.restore_group(IDLE_GROUP)
+    }
+
+    /// Whether windows are hidden for idleness.
+    pub fn idle_active(&self) -> Result<bool> {
+        Ok(!self.store.load_group(IDLE_GROUP)?.is_empty())
+    }
+
+    /// Session locked: minimize the windows of the `lock_classes` (every
+    /// window when empty). The previously focused window is restored last on
+    /// unlock, so it gets the focus back.
+    pub fn lock_session(&self) -> Result<Vec<String>> {
+        let _lock = self.store.lock()?;
+
+        let active = self.compositor.active_window()?.map(|c| c.address);
+        let minimized = self.minimize_into_group(LOCK_GROUP, false, |class| self.config.hides_on_lock(class))?;
+
+        if let Some(active) = active.filter(|a| minimized.contains(a)) {
+            let mut group = self.store.load_group(LOCK_GROUP)?;
+            group.retain(|a| *a != active);
+            group.push(active);
+            self.store.save_group(LOCK_GROUP, &group)?;
+        }
+
+        Ok(minimized)
+    }
+
+    /// Session unlocked: restore the windows minimized by `lock_session`.
+    pub fn unlock_session(&self) -> Result<Vec<String>> {
+        self.restore_group(LOCK_GROUP)
+    }
+
+    /// Whether windows are hidden for a locked session.
+    pub fn lock_active(&self) -> Result<bool> {
+        Ok(!self.store.load_group(LOCK_GROUP)?.is_empty())
+    }
+
+    // Minimize the visible windows whose class matches, remembering them in a group
+    fn minimize_into_group(&self, group: &str, capture: bool, matches: impl Fn(&str) -> bool) -> Result<Vec<String>> {
+        let _lock = self.store.lock()?;
+
+        let clients: Vec<Client> = self
+            .compositor
+            .clients()?
+            .into_iter()
+            .filter(|c| !self.is_minimized(c) && matches(&c.class))
+            .collect();
+
+        let minimized = self.minimize_clients(&clients, capture)?;
+        self.add_to_group(group, &minimized)?;
+
+        Ok(minimized)
+    }
+
+    // Remember addresses in a group, after the ones already there
+    fn add_to_group(&self, group: &str, minimized: &[String]) -> Result<()> {
+        let _lock = self.store.lock()?;
+
+        let mut addresses = self.store.load_group(group)?;
+        for address in minimized {
+            if !addresses.contains(address) {
+                addresses.push(address.clone());
+            }
+        }
+        self.store.save_group(group, &addresses)
+    }
+
+    // Restore the windows of a group that are still minimized and drop the group
+    fn restore_group(&self, group: &str) -> Result<Vec<String>> {
+        let _lock = self.store.lock()?;
+
+        let addresses = self.store.load_group(group)?;
+        let restored = self.restore_many(&addresses)?;
+        self.store.save_group(group, &[])?;
+
+        Ok(restored)
+    }
+
+    /// Minimize every window on the focused monitor, or restore the set
+    /// minimized by the previous call. Returns whether windows were restored.
+    pub fn show_desktop(&self) -> Result<bool> {
+        let _lock = self.store.lock()?;
+
+        let desktop = self.store.load_group(SHOW_DESKTOP_GROUP)?;
+
+        if !desktop.is_empty() {
+            let windows = self.windows()?;
+            let still_minimized: Vec<String> = desktop
+                .into_iter()
+                .filter(|addr| windows.iter().any(|w| w.address == *addr))
+                .collect();
+
+            self.store.save_group(SHOW_DESKTOP_GROUP, &[])?;
+
+            if !still_minimized.is_empty() {
+                self.restore_many(&still_minimized)?;
+                return Ok(true);
+            }
+
+            // Everything was restored individually in the meantime: start over
+        }
+
+        let monitors = self.compositor.monitors()?;
+        let Some(workspace_id) = monitors.iter().find(|m| m.focused).map(|m| m.active_workspace.id) else {
+            return Ok(false);
+        };
+
+        let clients: Vec<Client> = self
+            .compositor
+            .clients()?
+            .into_iter()
+            .filter(|c| c.workspace.id == workspace_id)
+            .collect();
+
+        let minimized = self.minimize_batch(&clients)?;
+        self.store.save_group(SHOW_DESKTOP_GROUP, &minimized)?;
+
+        Ok(false)
+    }
+
+    // Cache entry for a window about to be minimized (or found minimized)
+    fn window_entry(&self, client: &Client) -> Result<MinimizedWindow> {
+        let (current_workspace, monitor) = self.window_location(client)?;
+
+        let (icon, icon_path) = icons::app_icon(&self.config, &client.class);
+
+        // Private windows never have their real title stored
+        let title = self.config.stored_title(&client.class, &client.title);
+
+        let mut window = MinimizedWindow {
+            address: client.address.clone(),
+            display_title: String::new(),
+            class: client.class.clone(),
+            original_title: title,
+            preview_path: None,
+            icon,
+            icon_path,
+            workspace: current_workspace,
+            monitor,
+            home_monitor: None,
+            floating: client.floating,
+            position: Some(client.at),
+            size: Some(client.size),
+            fullscreen: client.fullscreen_state(),
+            minimized_at: unix_timestamp_ms(),
+            pid: client.pid,
+            suspended: false,
+            muted: false,
+            restore_at: None,
+            urgent: false,
+            group: client.group_members(),
+        };
+        window.display_title = self.config.display_title(&window);
+
+        Ok(window)
+    }
+
+    // Build the cache entry and grab the raw screenshot while the window is
+    // still visible; thumbnails are generated after the window is moved
+    fn prepare_minimize(&self, client: &Client, capture: bool) -> Result<Option<(MinimizedWindow, Option<String>)>> {
+        // Windows excluded by the rules (wofi menus by default)
+        if client.address.is_empty() || self.config.ignores(&client.class, &client.title) {
+            debug!("Not minimizing {} ({}): ignored by a rule", client.class, client.address);
+            return Ok(None);
+        }
+
+        let mut window = self.window_entry(client)?;
+
+        let screenshot = if !capture || self.config.never_preview(&client.class, &client.title) {
+            None
+        } else {
+            match self.capture_preview(client) {
+                Ok(screenshot) => Some(screenshot),
+                // Keep the menus consistent with a stand-in thumbnail
+                Err(e) => {
+                    debug!("No preview for {} ({}): {}", client.class, client.address, e);
+                    window.preview_path = preview::generate_placeholder(
+                        self.store.preview_dir(),
+                        &window.class,
+                        window.icon_path.as_deref(),
+                        &self.config.preview,
+                    )
+                    .inspect_err(|e| debug!("No placeholder for {}: {}", window.class, e))
+                    .ok();
+                    None
+                }
+            }
+        };
+
+        Ok(Some((window, screenshot)))
+    }
+
+    // Screenshot a window, by itself when grim supports toplevel export
+    fn capture_preview(&self, client: &Client) -> Result<String> {
+        let preview_dir = self.store.preview_dir();
+        if self.config.capture != CaptureMethod::Geometry {
+            match self.effects.capture_toplevel(preview_dir, &client.address) {
+                Ok(path) => return Ok(path),
+                Err(e) if self.config.capture == CaptureMethod::Toplevel => return Err(e),
+                Err(e) => debug!("Falling back to a screen area capture: {}", e),
+            }
+        }
+
+        let (x, y) = client.at;
+        let (width, height) = client.size;
+        let geometry = format!("{},{} {}x{}", x, y, width, height);
+        self.effects.capture_area(preview_dir, &client.address, &geometry)
+    }
+
+    // Mute and/or stop the processes with those options enabled whose windows
+    // are now all minimized; one visible window keeps the whole process going
+    fn park_hidden_processes(&self, windows: &mut [MinimizedWindow]) -> Result<()> {
+        let wants_mute = |w: &MinimizedWindow| !w.muted && self.config.mute_audio_for(&w.class);
+        let wants_suspend = |w: &MinimizedWindow| !w.suspended && self.config.suspend_for(&w.class);
+
+        let mut pids: Vec<i32> = windows
+            .iter()
+            .filter(|w| w.pid > 0 && (wants_mute(w) || wants_suspend(w)))
+            .map(|w| w.pid)
+            .collect();
+        if pids.is_empty() {
+            return Ok(());
+        }
+        pids.sort_unstable();
+        pids.dedup();
+
+        let clients = self.compositor.clients()?;
+        for pid in pids {
+            if clients.iter().any(|c| c.pid == pid && !self.is_minimized(c)) {
+                continue;
+            }
+
+            // Mute first, a stopped process still has its streams open
+            let mute = windows.iter().any(|w| w.pid == pid && wants_mute(w)) && self.effects.set_muted(pid, true);
+            let stop = windows.iter().any(|w| w.pid == pid && wants_suspend(w)) && self.effects.stop(pid);
+
+            for window in windows.iter_mut().filter(|w| w.pid == pid) {
+                window.muted |= mute;
+                window.suspended |= stop;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish_minimize(&self, minimized: Vec<(MinimizedWindow, Option<String>)>) -> Result<()> {
+        // Mark the windows as ours; older Hyprland versions without tags just
+        // refuse, which is fine
+        let tags: Vec<Action> = minimized
+            .iter()
+            .map(|(window, _)| Action::Tag { address: window.address.clone(), tag: MINHYPR_TAG.to_string(), add: true })
+            .collect();
+        self.compositor.run(&tags)?;
+
+        // Update list of minimized windows
+        let mut windows = self.windows()?;
+        let mut screenshots = Vec::new();
+        let mut notifications = Vec::new();
+        let mut minimized_now = Vec::new();
+        for (window, screenshot) in minimized {
+            if let Some(screenshot) = screenshot {
+                screenshots.push((window.address.clone(), screenshot));
+            }
+            if self.config.notifications_for(&window.class) {
+                notifications.push(window.address.clone());
+            }
+            minimized_now.push(window.clone());
+            windows.push(window);
+        }
+        self.park_hidden_processes(&mut windows)?;
+        self.store.save(&windows)?;
+        self.effects.refresh_bars(&self.config);
+
+        if self.config.history {
+            self.effects.record_history(EventKind::Minimize, &minimized_now.iter().collect::<Vec<_>>());
+        }
+        for window in &minimized_now {
+            self.effects.run_hook(self.config.on_minimize.as_deref(), "minimize", window);
+        }
+
+        // Build the thumbnails in detached children, the windows are already hidden
+        for (address, screenshot) in screenshots {
+            if self.effects.spawn_preview_job(&address, &screenshot).is_err() {
+                fs::remove_file(screenshot).ok();
+            }
+        }
+
+        for address in notifications {
+            self.effects.notify(&address, false).ok();
+        }
+
+        Ok(())
+    }
+
+    /// Generate thumbnails from a raw screenshot and attach them to the window.
+    pub fn generate_preview(&self, window_id: &str, screenshot: &str) -> Result<()> {
+        let _lock = self.store.lock()?;
+
+        let mut windows = self.windows()?;
+        let icon_path = windows.iter().find(|w| w.address == window_id).and_then(|w| w.icon_path.clone());
+        let thumb_path =
+            preview::generate_window_preview(self.store.preview_dir(), screenshot, icon_path.as_deref(), &self.config.preview)?;
+
+        let Some(index) = windows.iter().position(|w| w.address == window_id) else {
+            // Closed or restored meanwhile
+            return self.remove_previews(window_id, Some(&thumb_path), &windows);
+        };
+
+        let previous = windows[index].preview_path.replace(thumb_path);
+        self.store.save(&windows)?;
+        if previous != windows[index].preview_path {
+            self.remove_previews(window_id, previous.as_deref(), &windows)?;
+        }
+
+        Ok(())
+    }
+
+    // Delete a window's screenshot and thumbnails, unless an identical
+    // capture shares them with a window in `kept` or the one kept for undo
+    fn remove_previews(&self, address: &str, thumb_path: Option<&str>, kept: &[MinimizedWindow]) -> Result<()> {
+        let last_restore = self.store.load_last_restore()?;
+        let shared = |thumb_path: &str| {
+            kept.iter().chain(last_restore.iter()).any(|w| w.preview_path.as_deref() == Some(thumb_path))
+        };
+        preview::remove_previews(self.store.preview_dir(), address, thumb_path.filter(|p| !shared(p)));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{
+        env, fs,
+        path::PathBuf,
+        process,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+    use crate::{
+        hyprland::WorkspaceRef,
+        mock::{Effect, MockCompositor, MockEffects},
+    };
+
+    // A state directory removed when the test ends
+    pub(crate) struct TempDir(PathBuf);
+
+    impl TempDir {
+        pub(crate) fn new() -> Self {
+            static COUNT: AtomicUsize = AtomicUsize::new(0);
+            let dir = env::temp_dir().join(format!("minhypr-test-{}-{}", process::id(), COUNT.fetch_add(1, Ordering::SeqCst)));
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        pub(crate) fn path(&self) -> String {
+            self.0.display().to_string()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            fs::remove_dir_all(&self.0).ok();
+        }
+    }
+
+    // A manager over the mock compositor, with a fresh store in `dir`
+    pub(crate) fn manager(dir: &TempDir) -> (Manager<MockCompositor>, MockEffects) {
+        let store = StateStore::in_dir(&dir.0);
+        store.init().unwrap();
+        let effects = MockEffects::new();
+        let manager = Manager::with_compositor(MockCompositor::new(), store, Config::default()).with_effects(effects.clone());
+        (manager, effects)
+    }
+
+    fn client(manager: &Manager<MockCompositor>, address: &str) -> Client {
+        manager.compositor.client(address).unwrap().unwrap()
+    }
+
+    // A window in the minimized workspace, as after a Hyprland restart
+    fn hidden_client(address: &str, class: &str, title: &str, tagged: bool) -> Client {
+        Client {
+            address: address.to_string(),
+            workspace: WorkspaceRef { id: -99, name: String::from("special:minimized") },
+            class: class.to_string(),
+            title: title.to_string(),
+            tags: if tagged { vec![format!("{}*", MINHYPR_TAG)] } else { Vec::new() },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn minimize_hides_tags_and_tracks_the_window() {
+        let dir = TempDir::new();
+        let (manager, effects) = manager(&dir);
+        manager.compositor.add_window("0x1", "kitty", "Terminal", 1);
+
+        assert!(manager.minimize_address("0x1").unwrap());
+
+        let hidden = client(&manager, "0x1");
+        assert_eq!(hidden.workspace.name, "special:minimized");
+        assert!(hidden.has_tag(MINHYPR_TAG));
+
+        let windows = manager.windows().unwrap();
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].address, "0x1");
+        assert_eq!(windows[0].class, "kitty");
+        assert_eq!(windows[0].workspace, 1);
+        assert!(effects.effects().contains(&Effect::RefreshBars));
+    }
+
+    #[test]
+    fn minimize_skips_ignored_windows() {
+        let dir = TempDir::new();
+        let (manager, _) = manager(&dir);
+        manager.compositor.add_window("0x1", "wofi", "wofi", 1);
+
+        assert!(!manager.minimize_address("0x1").unwrap());
+        assert_eq!(client(&manager, "0x1").workspace.id, 1);
+        assert!(manager.windows().unwrap().is_empty());
+    }
+
+    #[test]
+    fn partly_refused_batches_track_the_windows_that_moved() {
+        let dir = TempDir::new();
+        let (manager, _) = manager(&dir);
+        manager.compositor.add_window("0x1", "kitty", "One", 1);
+        manager.compositor.add_window("0x2", "firefox", "Two", 1);
+        let clients = manager.compositor.clients().unwrap();
+
+        // Closed before its move, so only that one fails
+        manager.compositor.close("0x2");
+        assert_eq!(manager.minimize_batch(&clients).unwrap(), ["0x1"]);
+        assert_eq!(manager.windows().unwrap().iter().map(|w| w.address.as_str()).collect::<Vec<_>>(), ["0x1"]);
+
+        manager.compositor.add_window("0x3", "foot", "Three", 1);
+        manager.compositor.refuse_actions(true);
+        assert!(manager.minimize_batch(&[client(&manager, "0x3")]).unwrap().is_empty());
+        assert_eq!(manager.windows().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn restore_returns_the_window_to_its_workspace() {
+        let dir = TempDir::new();
+        let (manager, effects) = manager(&dir);
+        manager.compositor.add_window("0x1", "kitty", "Terminal", 3);
+        manager.minimize_address("0x1").unwrap();
+
+        manager.restore("0x1").unwrap();
+
+        let restored = client(&manager, "0x1");
+        assert_eq!(restored.workspace.id, 3);
+        assert!(!restored.has_tag(MINHYPR_TAG));
+        assert_eq!(manager.compositor.active_window().unwrap().map(|c| c.address), Some(String::from("0x1")));
+        assert!(manager.windows().unwrap().is_empty());
+        assert!(effects.effects().contains(&Effect::History { event: EventKind::Restore, addresses: vec![String::from("0x1")] }));
+    }
+
+    #[test]
+    fn restore_of_an_unknown_window_is_not_found() {
+        let dir = TempDir::new();
+        let (manager, _) = manager(&dir);
+
+        let error = manager.restore("0xdead").unwrap_err();
+        assert!(matches!(Error::of(&error), Some(Error::WindowNotFound(_))));
+    }
+
+    #[test]
+    fn restore_resumes_and_unmutes_parked_processes() {
+        let dir = TempDir::new();
+        let (mut manager, effects) = manager(&dir);
+        manager.config.suspend = true;
+        manager.config.mute_audio = true;
+        manager.compositor.add_client(Client {
+            address: String::from("0x1"),
+            class: String::from("mpv"),
+            workspace: WorkspaceRef { id: 1, name: String::from("1") },
+            pid: 42,
+            ..Default::default()
+        });
+
+        manager.minimize_address("0x1").unwrap();
+        let windows = manager.windows().unwrap();
+        assert!(windows[0].suspended && windows[0].muted);
+
+        manager.restore("0x1").unwrap();
+        let effects = effects.effects();
+        assert!(effects.contains(&Effect::Resume(42)));
+        assert!(effects.contains(&Effect::Mute { pid: 42, muted: false }));
+    }
+
+    #[test]
+    fn validation_drops_closed_windows() {
+        let dir = TempDir::new();
+        let (manager, _) = manager(&dir);
+        manager.compositor.add_window("0x1", "kitty", "One", 1);
+        manager.compositor.add_window("0x2", "firefox", "Two", 1);
+        manager.minimize_address("0x1").unwrap();
+        manager.minimize_address("0x2").unwrap();
+
+        manager.compositor.close("0x1");
+
+        let windows = manager.windows().unwrap();
+        assert_eq!(windows.iter().map(|w| w.address.as_str()).collect::<Vec<_>>(), ["0x2"]);
+        // The cache itself was fixed, not just the answer
+        assert_eq!(manager.store.load().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn validation_drops_windows_moved_out_of_the_minimized_workspace() {
+        let dir = TempDir::new();
+        let (manager, _) = manager(&dir);
+        manager.compositor.add_window("0x1", "kitty", "One", 1);
+        manager.minimize_address("0x1").unwrap();
+
+        manager.compositor.move_to_workspace("2", "0x1").unwrap();
+
+        assert!(manager.windows().unwrap().is_empty());
+        assert!(manager.store.load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn resync_readopts_windows_with_new_addresses() {
+        let dir = TempDir::new();
+        let (manager, _) = manager(&dir);
+        manager.compositor.add_window("0x1", "firefox", "Docs", 2);
+        manager.minimize_address("0x1").unwrap();
+
+        // Hyprland restarted: same window, new address
+        manager.compositor.close("0x1");
+        manager.compositor.add_client(hidden_client("0x9", "firefox", "Docs", false));
+
+        let windows = manager.resync().unwrap();
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].address, "0x9");
+        assert_eq!(windows[0].workspace, 2);
+    }
+
+    #[test]
+    fn resync_recovers_tagged_windows_missing_from_the_cache() {
+        let dir = TempDir::new();
+        let (manager, _) = manager(&dir);
+        manager.compositor.add_client(hidden_client("0x1", "kitty", "Terminal", true));
+        manager.compositor.add_client(hidden_client("0x2", "scratch", "Not ours", false));
+
+        let windows = manager.resync().unwrap();
+        assert_eq!(windows.iter().map(|w| w.address.as_str()).collect::<Vec<_>>(), ["0x1"]);
+    }
+
+    #[test]
+    fn resync_drops_windows_that_are_gone() {
+        let dir = TempDir::new();
+        let (manager, _) = manager(&dir);
+        manager.compositor.add_window("0x1", "kitty", "Terminal", 1);
+        manager.minimize_address("0x1").unwrap();
+        manager.compositor.close("0x1");
+
+        assert!(manager.resync().unwrap().is_empty());
+        assert!(manager.store.load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn menus_sort_group_and_pin_windows() {
+        let dir = TempDir::new();
+        let (mut manager, _) = manager(&dir);
+        for (address, class, workspace) in [("0x1", "kitty", 2), ("0x2", "firefox", 1), ("0x3", "Alacritty", 2), ("0x4", "zathura", 3)] {
+            manager.compositor.add_window(address, class, "Title", workspace);
+            manager.minimize_address(address).unwrap();
+        }
+        manager.config.menu.sort = MenuSort::Class;
+        manager.config.menu.group_by = MenuGroup::Workspace;
+        manager.config.rules.insert(
+            String::from("reader"),
+            crate::config::Rule { class: Some(String::from("zathura")), menu_top: true, ..Default::default() },
+        );
+
+        let windows = manager.windows().unwrap();
+        let ordered = manager.menu_order(&windows);
+        assert_eq!(ordered.iter().map(|w| w.class.as_str()).collect::<Vec<_>>(), ["zathura", "firefox", "Alacritty", "kitty"]);
+        let groups: Vec<Option<String>> = ordered.iter().map(|w| manager.menu_group(w)).collect();
+        assert_eq!(groups, [Some("Pinned"), Some("Workspace 1"), Some("Workspace 2"), Some("Workspace 2")].map(|g| g.map(String::from)));
+
+        manager.config.menu.group_by = MenuGroup::None;
+        assert_eq!(manager.menu_group(ordered[0]), None);
+    }
+
+    #[test]
+    fn remap_follows_monitors_coming_and_going() {
+        let dir = TempDir::new();
+        let (manager, _) = manager(&dir);
+        manager.compositor.add_monitor("MOCK-2", 2);
+        let on_second = |address: &str| Client {
+            address: address.to_string(),
+            workspace: WorkspaceRef { id: 2, name: String::from("2") },
+            monitor: 1,
+            class: String::from("kitty"),
+            ..Default::default()
+        };
+        manager.compositor.add_client(on_second("0x1"));
+        manager.compositor.add_client(on_second("0x2"));
+        manager.minimize_address("0x1").unwrap();
+        assert_eq!(manager.windows().unwrap()[0].monitor.as_deref(), Some("MOCK-2"));
+
+        // Unplugged: workspace 2 moved to the remaining monitor
+        manager.compositor.remove_monitor("MOCK-2");
+        assert_eq!(manager.remap_monitors().unwrap(), 1);
+        let window = &manager.windows().unwrap()[0];
+        assert_eq!(window.monitor.as_deref(), Some("MOCK-1"));
+        assert_eq!(window.home_monitor.as_deref(), Some("MOCK-2"));
+
+        // Plugged back in: home again
+        manager.compositor.add_monitor("MOCK-2", 3);
+        assert_eq!(manager.remap_monitors().unwrap(), 1);
+        let window = &manager.windows().unwrap()[0];
+        assert_eq!(window.monitor.as_deref(), Some("MOCK-2"));
+        assert_eq!(window.home_monitor, None);
+        assert_eq!(manager.remap_monitors().unwrap(), 0);
+    }
+
+    #[test]
+    fn failed_queries_keep_the_cache() {
+        let dir = TempDir::new();
+        let (manager, _) = manager(&dir);
+        manager.compositor.add_window("0x1", "kitty", "Terminal", 1);
+        manager.minimize_address("0x1").unwrap();
+
+        manager.compositor.fail_queries(true);
+        assert!(manager.windows().is_err());
+        assert!(manager.resync().is_err());
+        assert_eq!(manager.store.load().unwrap().len(), 1);
+
+        manager.compositor.fail_queries(false);
+        assert_eq!(manager.windows().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,167 @@
+use std::{
+    io::{self, Result, Write},
+    process::{Command, Stdio},
+};
+
+use crate::{
+    compositor::Compositor,
+    config::Launcher,
+    hyprland::Client,
+    manager::Manager,
+};
+
+/// Default format of each menu line; the address keeps lines unique.
+pub const DEFAULT_MENU_FORMAT: &str = "{icon} {class} - {title}  [{address}]";
+
+// Pipe lines into a dmenu-compatible command; returns the selected line, or
+// None if the menu was cancelled
+fn run_menu(command: &str, lines: &[String]) -> Result<Option<String>> {
+    let mut child = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The menu may exit before reading everything
+        stdin.write_all(format!("{}\n", lines.join("\n")).as_bytes()).ok();
+    }
+
+    let output = child.wait_with_output()?;
+    // Non-zero exit: the menu was cancelled
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let selection = String::from_utf8_lossy(&output.stdout);
+    Ok(Some(selection.lines().next().unwrap_or_default().trim_end().to_string()))
+}
+
+/// Pipe the minimized windows into a dmenu-compatible command (fzf, dmenu,
+/// tofi, ...), one line each, and restore the selected one. Returns the
+/// restored address, or `None` if nothing was selected.
+pub fn pick(manager: &Manager, command: &str, format: &str) -> Result<Option<String>> {
+    let address = choose(manager, command, format)?;
+    if let Some(address) = &address {
+        manager.restore(address)?;
+    }
+    Ok(address)
+}
+
+/// Like [`pick`], but only returns the selected window's address.
+pub fn choose(manager: &Manager, command: &str, format: &str) -> Result<Option<String>> {
+    let windows = manager.windows()?;
+    if windows.is_empty() {
+        info!("No minimized windows");
+        return Ok(None);
+    }
+
+    let windows = manager.menu_order(&windows);
+    let lines: Vec<String> = windows.iter().map(|w| manager.config.format_window(format, w).replace('\n', " ")).collect();
+
+    let Some(selection) = run_menu(command, &lines)? else {
+        return Ok(None);
+    };
+    let selection = selection.as_str();
+
+    // Match the line back to its window, or accept a bare address
+    let address = lines
+        .iter()
+        .position(|line| line.trim_end() == selection)
+        .map(|i| windows[i].address.clone())
+        .or_else(|| windows.iter().find(|w| w.address == selection.trim()).map(|w| w.address.clone()));
+
+    if address.is_none() && !selection.is_empty() {
+        info!("No minimized window matches: {}", selection);
+    }
+    Ok(address)
+}
+
+/// Let the user click one of the windows on screen with slurp. Returns `None`
+/// when the selection is cancelled.
+pub fn pick_window(manager: &Manager) -> Result<Option<Client>> {
+    let shown: Vec<i64> = manager.compositor.monitors()?.iter().map(|m| m.active_workspace.id).collect();
+    let mut clients: Vec<Client> = manager
+        .compositor
+        .clients()?
+        .into_iter()
+        .filter(|c| shown.contains(&c.workspace.id) && !manager.is_minimized(c) && c.size.0 > 0 && c.size.1 > 0)
+        .collect();
+    if clients.is_empty() {
+        info!("No windows on screen");
+        return Ok(None);
+    }
+    // Floating windows are drawn over tiled ones, so they should win where they overlap
+    clients.sort_by_key(|c| !c.floating);
+
+    // slurp -r only allows picking one of the given boxes, printing its label
+    let boxes: Vec<String> =
+        clients.iter().map(|c| format!("{},{} {}x{} {}", c.at.0, c.at.1, c.size.0, c.size.1, c.address)).collect();
+    let mut child = Command::new("slurp")
+        .args(["-r", "-f", "%l"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => io::Error::new(e.kind(), "slurp is not installed"),
+            _ => e,
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(format!("{}\n", boxes.join("\n")).as_bytes())?;
+    }
+
+    let output = child.wait_with_output()?;
+    // Non-zero exit: the selection was cancelled (Escape)
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let address = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok(clients.into_iter().find(|c| c.address == address))
+}
+
+// No icons in bemenu, so the glyph is left out
+const BEMENU_FORMAT: &str = "{class} - {title}  [{address}]";
+
+/// Restore menu with bemenu: case-insensitive matching and a prompt.
+pub fn show_bemenu(manager: &Manager) -> Result<Option<String>> {
+    pick(manager, "bemenu -i -l 10 -p 'Restore:'", BEMENU_FORMAT)
+}
+
+/// Menu of the minimized windows with Rofi or bemenu, as a plain list; the
+/// selected one is closed. Returns its address.
+pub fn close_menu(manager: &Manager, launcher: Launcher) -> Result<Option<String>> {
+    let address = match launcher {
+        Launcher::Rofi => choose(manager, "rofi -dmenu -i -p 'Close:'", DEFAULT_MENU_FORMAT)?,
+        Launcher::Bemenu => choose(manager, "bemenu -i -l 10 -p 'Close:'", BEMENU_FORMAT)?,
+    };
+    if let Some(address) = &address {
+        manager.close_minimized(address)?;
+    }
+    Ok(address)
+}
+
+/// Minimize menu with bemenu: the open windows, one line each; the selected
+/// one is minimized. Returns its address.
+pub fn show_minimize_bemenu(manager: &Manager) -> Result<Option<String>> {
+    let clients = manager.open_clients()?;
+    if clients.is_empty() {
+        info!("No windows to minimize");
+        return Ok(None);
+    }
+
+    let lines: Vec<String> = clients
+        .iter()
+        .map(|c| {
+            let title = manager.config.rewrite_title(&c.class, &manager.config.stored_title(&c.class, &c.title));
+            format!("{} - {}  [{}]", c.class, title.replace('\n', " "), c.address)
+        })
+        .collect();
+    let Some(selection) = run_menu("bemenu -i -l 10 -p 'Minimize:'", &lines)? else {
+        return Ok(None);
+    };
+
+    match lines.iter().position(|line| line.trim_end() == selection).map(|i| &clients[i]) {
+        Some(client) if manager.minimize(client)? => Ok(Some(client.address.clone())),
+        _ => Ok(None),
+    }
+}
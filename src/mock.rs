@@ -0,0 +1,479 @@
+use std::{
+    io::{self, Result},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{
+    compositor::{Action, Compositor},
+    config::Config,
+    effects::Effects,
+    history::EventKind,
+    hyprland::{Client, Monitor, Workspace, WorkspaceRef},
+    window::MinimizedWindow,
+};
+
+#[derive(Default)]
+struct MockState {
+    clients: Vec<Client>,
+    monitors: Vec<Monitor>,
+    active: Option<String>,
+    // Special and named workspaces get ids in order of first use
+    named: Vec<String>,
+    actions: Vec<Action>,
+    refuse: bool,
+    unreachable: bool,
+}
+
+impl MockState {
+    fn workspace_ref(&mut self, workspace: &str) -> WorkspaceRef {
+        if let Ok(id) = workspace.parse::<i64>() {
+            return WorkspaceRef { id, name: id.to_string() };
+        }
+
+        let name = workspace.strip_prefix("name:").unwrap_or(workspace).to_string();
+        let index = match self.named.iter().position(|n| *n == name) {
+            Some(index) => index,
+            None => {
+                self.named.push(name.clone());
+                self.named.len() - 1
+            }
+        } as i64;
+
+        // Like Hyprland: special workspaces have negative ids
+        let id = if name.starts_with("special:") { -99 - index } else { 1000 + index };
+        WorkspaceRef { id, name }
+    }
+
+    fn focused_monitor(&mut self) -> Option<&mut Monitor> {
+        self.monitors.iter_mut().find(|m| m.focused)
+    }
+
+    // Focusing a window on a regular workspace shows that workspace
+    fn focus(&mut self, address: &str) -> bool {
+        let Some(client) = self.clients.iter().find(|c| c.address == address) else {
+            return false;
+        };
+        let workspace = client.workspace.clone();
+        let monitor = client.monitor;
+
+        self.active = Some(address.to_string());
+        if workspace.id > 0 {
+            for m in &mut self.monitors {
+                m.focused = m.id == monitor;
+                if m.focused {
+                    m.active_workspace = workspace.clone();
+                }
+            }
+        }
+        true
+    }
+
+    fn client_mut(&mut self, address: &str) -> Option<&mut Client> {
+        self.clients.iter_mut().find(|c| c.address == address)
+    }
+
+    fn apply(&mut self, action: &Action) -> bool {
+        match action {
+            Action::MoveToWorkspace { workspace, address, silent } => {
+                let workspace = self.workspace_ref(workspace);
+                let Some(client) = self.client_mut(address) else {
+                    return false;
+                };
+                // Like Hyprland, the window's group goes along
+                let group = client.group_members();
+                client.workspace = workspace.clone();
+                for member in self.clients.iter_mut().filter(|c| group.contains(&c.address)) {
+                    member.workspace = workspace.clone();
+                }
+                if !silent {
+                    self.focus(address);
+                } else if self.active.as_deref() == Some(address) {
+                    self.active = None;
+                }
+                true
+            }
+            Action::FocusWindow(address) => self.focus(address),
+            Action::FocusMonitor(name) => {
+                if !self.monitors.iter().any(|m| m.name == *name) {
+                    return false;
+                }
+                for m in &mut self.monitors {
+                    m.focused = m.name == *name;
+                }
+                true
+            }
+            Action::SetFloating(address) => self.client_mut(address).map(|c| c.floating = true).is_some(),
+            Action::MoveWindowPixel { address, position } => self.client_mut(address).map(|c| c.at = *position).is_some(),
+            Action::ResizeWindowPixel { address, size } => self.client_mut(address).map(|c| c.size = *size).is_some(),
+            Action::Fullscreen(mode) => {
+                let Some(address) = self.active.clone() else {
+                    return false;
+                };
+                let state = if *mode == 1 { 1 } else { 2 };
+                self.client_mut(&address)
+                    .map(|c| {
+                        let toggled = if c.fullscreen_state() == state { 0 } else { state };
+                        c.fullscreen = toggled.into();
+                    })
+                    .is_some()
+            }
+            Action::Tag { address, tag, add } => self
+                .client_mut(address)
+                .map(|c| {
+                    c.tags.retain(|t| t.trim_end_matches('*') != tag);
+                    if *add {
+                        // Tags set with tagwindow are dynamic
+                        c.tags.push(format!("{}*", tag));
+                    }
+                })
+                .is_some(),
+            Action::CloseWindow(address) => self.close(address),
+            // Nothing is launched; the action log shows the command
+            Action::Exec(_) => true,
+            Action::MoveOutOfGroup(address) => {
+                if self.client_mut(address).is_none_or(|c| c.grouped.is_empty()) {
+                    return false;
+                }
+                self.ungroup(address);
+                true
+            }
+            // Joins the group of another window on its workspace; the mock
+            // has no layout to pick one by direction
+            Action::MoveIntoGroup(_) => {
+                let Some(active) = self.active.clone() else {
+                    return false;
+                };
+                let Some(workspace) = self.client_mut(&active).map(|c| c.workspace.id) else {
+                    return false;
+                };
+                let Some(group) = self
+                    .clients
+                    .iter()
+                    .find(|c| c.address != active && c.workspace.id == workspace && !c.grouped.is_empty())
+                    .map(|c| c.grouped.clone())
+                else {
+                    return false;
+                };
+                self.ungroup(&active);
+                self.set_group(group.into_iter().chain([active]).collect());
+                true
+            }
+        }
+    }
+
+    fn set_group(&mut self, group: Vec<String>) {
+        for client in self.clients.iter_mut().filter(|c| group.contains(&c.address)) {
+            client.grouped = group.clone();
+        }
+    }
+
+    fn ungroup(&mut self, address: &str) {
+        for client in &mut self.clients {
+            if client.address == address {
+                client.grouped.clear();
+            } else {
+                client.grouped.retain(|a| a != address);
+            }
+        }
+    }
+
+    fn close(&mut self, address: &str) -> bool {
+        self.ungroup(address);
+        let count = self.clients.len();
+        self.clients.retain(|c| c.address != address);
+        if self.active.as_deref() == Some(address) {
+            self.active = None;
+        }
+        self.clients.len() != count
+    }
+}
+
+/// In-memory compositor for tests: a set of windows on one monitor
+/// (`MOCK-1`, workspace 1) that [`Action`]s move around, with a log of the
+/// actions run.
+#[derive(Default)]
+pub struct MockCompositor {
+    state: Mutex<MockState>,
+}
+
+impl MockCompositor {
+    pub fn new() -> Self {
+        let state = MockState {
+            monitors: vec![Monitor {
+                id: 0,
+                name: String::from("MOCK-1"),
+                focused: true,
+                active_workspace: WorkspaceRef { id: 1, name: String::from("1") },
+                width: 1920,
+                height: 1080,
+                scale: 1.0,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        MockCompositor { state: Mutex::new(state) }
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // The state for a query, or the error Hyprland being gone gives
+    fn queried(&self) -> Result<MutexGuard<'_, MockState>> {
+        let state = self.state();
+        if state.unreachable {
+            return Err(io::Error::other("hyprctl clients failed: HYPRLAND_INSTANCE_SIGNATURE was not set"));
+        }
+        Ok(state)
+    }
+
+    /// Add a 1920x1080 monitor right of the others, showing `workspace`.
+    pub fn add_monitor(&self, name: &str, workspace: i64) {
+        let mut state = self.state();
+        let id = state.monitors.iter().map(|m| m.id + 1).max().unwrap_or(0);
+        let x = state.monitors.iter().map(|m| m.x + m.width).max().unwrap_or(0);
+        state.monitors.push(Monitor {
+            id,
+            name: name.to_string(),
+            focused: false,
+            active_workspace: WorkspaceRef { id: workspace, name: workspace.to_string() },
+            x,
+            width: 1920,
+            height: 1080,
+            scale: 1.0,
+            ..Default::default()
+        });
+    }
+
+    /// Unplug a monitor: like Hyprland, its windows (and so their workspaces)
+    /// move to the first remaining monitor.
+    pub fn remove_monitor(&self, name: &str) {
+        let mut state = self.state();
+        let Some(index) = state.monitors.iter().position(|m| m.name == name) else {
+            return;
+        };
+        let removed = state.monitors.remove(index);
+
+        let Some(fallback) = state.monitors.first_mut() else {
+            return;
+        };
+        fallback.focused |= removed.focused;
+        let fallback = fallback.id;
+        for client in state.clients.iter_mut().filter(|c| c.monitor == removed.id) {
+            client.monitor = fallback;
+        }
+    }
+
+    /// Open a window on a workspace of the focused monitor and focus it.
+    pub fn add_window(&self, address: &str, class: &str, title: &str, workspace: i64) -> Client {
+        let mut state = self.state();
+        let monitor = state.focused_monitor().map_or(0, |m| m.id);
+        let client = Client {
+            address: address.to_string(),
+            at: (0, 0),
+            size: (800, 600),
+            workspace: WorkspaceRef { id: workspace, name: workspace.to_string() },
+            monitor,
+            class: class.to_string(),
+            title: title.to_string(),
+            ..Default::default()
+        };
+        state.clients.push(client.clone());
+        state.focus(address);
+        client
+    }
+
+    /// Add a window as given, without focusing it.
+    pub fn add_client(&self, client: Client) {
+        self.state().clients.push(client);
+    }
+
+    /// Put windows in one Hyprland group (tabs), in that order.
+    pub fn group(&self, addresses: &[&str]) {
+        self.state().set_group(addresses.iter().map(|a| a.to_string()).collect());
+    }
+
+    /// Close a window as if the user did, returning whether it existed.
+    pub fn close(&self, address: &str) -> bool {
+        self.state().close(address)
+    }
+
+    /// Focus a window, or nothing.
+    pub fn set_active(&self, address: Option<&str>) {
+        let mut state = self.state();
+        match address {
+            Some(address) => {
+                state.focus(address);
+            }
+            None => state.active = None,
+        }
+    }
+
+    /// Make every following action fail, like Hyprland refusing a dispatch.
+    pub fn refuse_actions(&self, refuse: bool) {
+        self.state().refuse = refuse;
+    }
+
+    /// Make every following query fail, like Hyprland going away mid-session.
+    pub fn fail_queries(&self, fail: bool) {
+        self.state().unreachable = fail;
+    }
+
+    /// The actions run so far, oldest first.
+    pub fn actions(&self) -> Vec<Action> {
+        self.state().actions.clone()
+    }
+}
+
+impl Compositor for MockCompositor {
+    fn clients(&self) -> Result<Vec<Client>> {
+        Ok(self.queried()?.clients.clone())
+    }
+
+    fn active_window(&self) -> Result<Option<Client>> {
+        let state = self.queried()?;
+        Ok(state.active.as_ref().and_then(|address| state.clients.iter().find(|c| c.address == *address)).cloned())
+    }
+
+    fn active_workspace(&self) -> Result<Workspace> {
+        let state = self.queried()?;
+        let Some(monitor) = state.monitors.iter().find(|m| m.focused) else {
+            return Ok(Workspace::default());
+        };
+
+        Ok(Workspace {
+            id: monitor.active_workspace.id,
+            name: monitor.active_workspace.name.clone(),
+            monitor: monitor.name.clone(),
+            windows: state.clients.iter().filter(|c| c.workspace.id == monitor.active_workspace.id).count() as u32,
+        })
+    }
+
+    fn workspaces(&self) -> Result<Vec<Workspace>> {
+        let state = self.queried()?;
+        let mut workspaces: Vec<Workspace> = Vec::new();
+
+        let shown = state.monitors.iter().map(|m| (m.active_workspace.clone(), m.id));
+        let used = state.clients.iter().map(|c| (c.workspace.clone(), c.monitor));
+        for (workspace, monitor) in shown.chain(used) {
+            if workspaces.iter().any(|w| w.id == workspace.id) {
+                continue;
+            }
+            workspaces.push(Workspace {
+                id: workspace.id,
+                windows: state.clients.iter().filter(|c| c.workspace.id == workspace.id).count() as u32,
+                name: workspace.name,
+                monitor: state.monitors.iter().find(|m| m.id == monitor).map(|m| m.name.clone()).unwrap_or_default(),
+            });
+        }
+
+        Ok(workspaces)
+    }
+
+    fn monitors(&self) -> Result<Vec<Monitor>> {
+        Ok(self.queried()?.monitors.clone())
+    }
+
+    fn run(&self, actions: &[Action]) -> Result<bool> {
+        let mut state = self.state();
+        let mut ok = true;
+
+        for action in actions {
+            state.actions.push(action.clone());
+            ok &= !state.refuse && state.apply(action);
+        }
+
+        Ok(ok)
+    }
+}
+
+/// A side effect [`MockEffects`] recorded instead of carrying out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Effect {
+    RefreshBars,
+    Hook { event: String, address: String },
+    History { event: EventKind, addresses: Vec<String> },
+    Notify { address: String, urgent: bool },
+    LimitReached { refused: usize, limit: usize },
+    Mute { pid: i32, muted: bool },
+    Stop(i32),
+    Resume(i32),
+    PreviewJob(String),
+}
+
+/// [`Effects`] for tests: records them, and succeeds at muting, stopping and
+/// resuming. There's no screen, so captures fail (minimizing then stores a
+/// placeholder thumbnail). Clones share the record, so a test can keep one
+/// while the manager owns another.
+#[derive(Clone, Default)]
+pub struct MockEffects {
+    effects: Arc<Mutex<Vec<Effect>>>,
+}
+
+impl MockEffects {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(&self, effect: Effect) {
+        self.effects.lock().unwrap_or_else(|e| e.into_inner()).push(effect);
+    }
+
+    /// The effects so far, oldest first.
+    pub fn effects(&self) -> Vec<Effect> {
+        self.effects.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl Effects for MockEffects {
+    fn refresh_bars(&self, _config: &Config) {
+        self.push(Effect::RefreshBars);
+    }
+
+    fn run_hook(&self, command: Option<&str>, event: &str, window: &MinimizedWindow) {
+        if command.is_some_and(|command| !command.trim().is_empty()) {
+            self.push(Effect::Hook { event: event.to_string(), address: window.address.clone() });
+        }
+    }
+
+    fn record_history(&self, event: EventKind, windows: &[&MinimizedWindow]) {
+        self.push(Effect::History { event, addresses: windows.iter().map(|w| w.address.clone()).collect() });
+    }
+
+    fn notify(&self, address: &str, urgent: bool) -> Result<()> {
+        self.push(Effect::Notify { address: address.to_string(), urgent });
+        Ok(())
+    }
+
+    fn notify_limit_reached(&self, refused: usize, limit: usize) {
+        self.push(Effect::LimitReached { refused, limit });
+    }
+
+    fn set_muted(&self, pid: i32, muted: bool) -> bool {
+        self.push(Effect::Mute { pid, muted });
+        true
+    }
+
+    fn stop(&self, pid: i32) -> bool {
+        self.push(Effect::Stop(pid));
+        true
+    }
+
+    fn resume(&self, pid: i32) -> bool {
+        self.push(Effect::Resume(pid));
+        true
+    }
+
+    fn capture_toplevel(&self, _preview_dir: &str, _address: &str) -> Result<String> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "no screen in the mock"))
+    }
+
+    fn capture_area(&self, _preview_dir: &str, _address: &str, _geometry: &str) -> Result<String> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "no screen in the mock"))
+    }
+
+    fn spawn_preview_job(&self, address: &str, _screenshot: &str) -> Result<()> {
+        self.push(Effect::PreviewJob(address.to_string()));
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    io::Result,
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use crate::{hyprland::follow_instance, manager::Manager, preview::preview_worker, window::MinimizedWindow};
+
+/// Show the "minimized" (or, with `urgent`, "wants attention") notification
+/// in a detached `minhypr notify` child, so it can wait for the click without
+/// holding up the caller.
+pub fn spawn_notification(address: &str, urgent: bool) -> Result<()> {
+    let mut command = Command::new(preview_worker());
+    command.arg("notify");
+    if urgent {
+        command.arg("--urgent");
+    }
+    follow_instance(&mut command)
+        .arg(address)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    Ok(())
+}
+
+/// Notify that a window was minimized, or with `urgent` that a minimized
+/// window wants attention, with a "Restore" action that restores it when
+/// clicked. Blocks until the notification is dismissed.
+pub fn notify_minimized(manager: &Manager, address: &str, urgent: bool) -> Result<()> {
+    // The thumbnail is built by another child; give it a moment to show up
+    let mut window = None;
+    for _ in 0..10 {
+        window = manager.windows()?.into_iter().find(|w| w.address == address);
+        if window.as_ref().is_none_or(|w| w.preview_path.is_some()) {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    let Some(window) = window else {
+        return Ok(());
+    };
+
+    let image = window
+        .preview_path
+        .clone()
+        .or_else(|| window.icon_path.clone())
+        .unwrap_or_else(|| window.class.to_lowercase());
+
+    let (summary, urgency) = if urgent {
+        (format!("{} wants attention", window.class), "--urgency=critical")
+    } else {
+        (format!("Minimized {}", window.class), "--urgency=normal")
+    };
+
+    // notify-send prints the name of the invoked action; --wait needs libnotify 0.7.9+
+    let output = Command::new("notify-send")
+        .args(["--app-name=minhypr", "--wait", "--action=restore=Restore", urgency, "--icon", &image])
+        .arg(summary)
+        .arg(&window.original_title)
+        .output()?;
+
+    if String::from_utf8_lossy(&output.stdout).trim() == "restore" {
+        manager.restore(address)?;
+    }
+
+    Ok(())
+}
+
+/// Tell that a snoozed window is back.
+pub fn notify_snooze_over(window: &MinimizedWindow) {
+    let icon = window.icon_path.clone().unwrap_or_else(|| window.class.to_lowercase());
+
+    Command::new("notify-send")
+        .args(["--app-name=minhypr", "--icon", &icon])
+        .arg(format!("{} is back", window.class))
+        .arg(&window.original_title)
+        .stdout(Stdio::null())
+        .status()
+        .ok();
+}
+
+/// Tell that windows weren't minimized because `max_minimized` windows already are.
+pub fn notify_limit_reached(refused: usize, limit: usize) {
+    let summary = match refused {
+        1 => String::from("Not minimized"),
+        n => format!("{} windows not minimized", n),
+    };
+
+    Command::new("notify-send")
+        .args(["--app-name=minhypr", "--icon", "dialog-warning"])
+        .arg(summary)
+        .arg(format!("{} windows are minimized already, the most minhypr keeps", limit))
+        .stdout(Stdio::null())
+        .status()
+        .ok();
+}
@@ -0,0 +1,126 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use image::{imageops, imageops::FilterType, DynamicImage, Rgba, RgbaImage};
+
+// 5x7 bitmap glyphs, one row per byte with the leftmost pixel in bit 4; no
+// font is available without extra dependencies
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        'A' => [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'B' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110],
+        'C' => [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110],
+        'D' => [0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110],
+        'E' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111],
+        'F' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000],
+        'G' => [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111],
+        'H' => [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'I' => [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        'J' => [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100],
+        'K' => [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001],
+        'L' => [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111],
+        'M' => [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001],
+        'N' => [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001],
+        'O' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'P' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000],
+        'Q' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101],
+        'R' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001],
+        'S' => [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110],
+        'T' => [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+        'U' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'V' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+        'W' => [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010],
+        'X' => [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001],
+        'Y' => [0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100],
+        'Z' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111],
+        '0' => [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+        '3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+        '7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+        '8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+        '9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+        '-' => [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000],
+        '.' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100],
+        '_' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111],
+        _ => [0; 7],
+    }
+}
+
+const TEXT_COLOR: Rgba<u8> = Rgba([235, 235, 235, 255]);
+
+// Draw text with its top left corner at (x, y), each glyph pixel `scale` wide
+fn draw_text(canvas: &mut RgbaImage, text: &str, x: u32, y: u32, scale: u32) {
+    for (i, c) in text.chars().enumerate() {
+        let left = x + i as u32 * 6 * scale;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for column in 0..5 {
+                if bits & (0b10000 >> column) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let (px, py) = (left + column * scale + dx, y + row as u32 * scale + dy);
+                        if px < canvas.width() && py < canvas.height() {
+                            canvas.put_pixel(px, py, TEXT_COLOR);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Width of text drawn at `scale`, without the trailing gap
+fn text_width(chars: usize, scale: u32) -> u32 {
+    (chars as u32 * 6).saturating_sub(1) * scale
+}
+
+/// Stand-in thumbnail for a window that couldn't be captured: the app icon
+/// (or the class initial) over a color picked from the class, with the class
+/// name below.
+pub fn render(class: &str, icon_path: Option<&str>, width: u32, height: u32) -> DynamicImage {
+    // Darkish, so the light text stays readable
+    let mut hasher = DefaultHasher::new();
+    class.to_lowercase().hash(&mut hasher);
+    let hash = hasher.finish();
+    let channel = |shift: u32| 40 + ((hash >> shift) & 0x3f) as u8;
+    let mut canvas = RgbaImage::from_pixel(width, height, Rgba([channel(0), channel(8), channel(16), 255]));
+
+    // The class name along the bottom, cut to fit; reverse-DNS classes
+    // (org.mozilla.firefox) by their last part
+    let class = class.rsplit('.').find(|part| !part.is_empty()).unwrap_or(class);
+    let text_scale = (height / 75).max(1);
+    let max_chars = ((width.saturating_sub(8 * text_scale) / text_scale + 1) / 6) as usize;
+    let mut name: String = class.chars().take(max_chars).collect();
+    if class.chars().count() > max_chars && max_chars > 1 {
+        name = class.chars().take(max_chars - 1).collect::<String>() + ".";
+    }
+    let name_width = text_width(name.chars().count(), text_scale);
+    let name_y = height.saturating_sub(11 * text_scale);
+    draw_text(&mut canvas, &name, width.saturating_sub(name_width) / 2, name_y, text_scale);
+
+    // The icon, or the initial, centered in the space above it; icon themes
+    // also hold SVGs, which can't be loaded here
+    let area = name_y.saturating_sub(4 * text_scale);
+    let side = (area * 2 / 3).min(width * 2 / 3).max(1);
+    match icon_path.and_then(|path| image::open(path).ok()) {
+        Some(icon) => {
+            let icon = icon.resize(side, side, FilterType::Lanczos3).to_rgba8();
+            let x = (width - icon.width()) / 2;
+            let y = area.saturating_sub(icon.height()) / 2 + 2 * text_scale;
+            imageops::overlay(&mut canvas, &icon, x as i64, y as i64);
+        }
+        None => {
+            let initial: String = class.chars().take(1).collect();
+            let scale = (side / 7).max(1);
+            let x = width.saturating_sub(text_width(1, scale)) / 2;
+            let y = area.saturating_sub(7 * scale) / 2 + 2 * text_scale;
+            draw_text(&mut canvas, &initial, x, y, scale);
+        }
+    }
+
+    DynamicImage::ImageRgba8(canvas)
+}
@@ -0,0 +1,283 @@
+use std::{
+    env,
+    fs::{self, File},
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{self, BufWriter, Result},
+    path::Path,
+    process::Command,
+    time::Duration,
+};
+
+use image::{
+    codecs::{jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder},
+    imageops,
+    imageops::FilterType,
+    DynamicImage, Rgba, RgbaImage,
+};
+
+use crate::{
+    config::{PreviewConfig, PreviewFormat},
+    hyprland::follow_instance,
+    placeholder,
+};
+
+pub fn capture_window_screenshot(preview_dir: &str, window_id: &str, geometry: &str) -> Result<String> {
+    let preview_path = format!("{}/{}.png", preview_dir, window_id);
+
+    // Capture screenshot with grim (must happen while the window is still visible)
+    let output = Command::new("grim")
+        .args(["-g", geometry, &preview_path])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(io::Error::other("grim failed to capture the window"));
+    }
+
+    Ok(preview_path)
+}
+
+/// Capture the contents of a window alone, through Hyprland's toplevel export
+/// protocol, which also works while it is hidden. Needs grim-hyprland's `-w`.
+pub fn capture_window_toplevel(preview_dir: &str, window_id: &str) -> Result<String> {
+    let preview_path = format!("{}/{}.png", preview_dir, window_id);
+
+    let output = Command::new("grim")
+        .args(["-w", window_id, &preview_path])
+        .output()?;
+
+    if !output.status.success() {
+        fs::remove_file(&preview_path).ok();
+        return Err(io::Error::other(format!(
+            "grim failed to export the window: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(preview_path)
+}
+
+/// Build the thumbnail and icon of a screenshot, and delete the screenshot.
+/// Files are named by a hash of the capture and settings, so identical
+/// captures share them. Returns the thumbnail path.
+pub fn generate_window_preview(
+    preview_dir: &str,
+    preview_path: &str,
+    icon_path: Option<&str>,
+    settings: &PreviewConfig,
+) -> Result<String> {
+    let image = image::open(preview_path).map_err(io::Error::other)?;
+
+    let mut hasher = DefaultHasher::new();
+    (image.width(), image.height()).hash(&mut hasher);
+    image.as_bytes().hash(&mut hasher);
+    // The placeholder already shows the icon
+    let badge = icon_path.filter(|_| settings.badge);
+    badge.hash(&mut hasher);
+    let thumb_path = write_previews(&image, badge, hasher, preview_dir, settings)?;
+
+    // Save storage space by removing the original
+    fs::remove_file(preview_path)?;
+
+    Ok(thumb_path)
+}
+
+/// Build a stand-in thumbnail and icon from the app icon and class name, for
+/// windows that couldn't be captured. Returns the thumbnail path.
+pub fn generate_placeholder(preview_dir: &str, class: &str, icon_path: Option<&str>, settings: &PreviewConfig) -> Result<String> {
+    let mut hasher = DefaultHasher::new();
+    ("placeholder", class, icon_path).hash(&mut hasher);
+    let image = placeholder::render(class, icon_path, settings.width, settings.height);
+    write_previews(&image, None, hasher, preview_dir, settings)
+}
+
+// Create a thumbnail for the menu (scaled to fill, center-cropped) and a
+// smaller icon for Rofi, named by the hash of the source and settings
+fn write_previews(
+    image: &DynamicImage,
+    badge: Option<&str>,
+    mut hasher: DefaultHasher,
+    preview_dir: &str,
+    settings: &PreviewConfig,
+) -> Result<String> {
+    settings.hash(&mut hasher);
+    let name = format!("{}/{:016x}", preview_dir, hasher.finish());
+
+    let mut thumb = image.resize_to_fill(settings.width, settings.height, FilterType::Lanczos3).to_rgba8();
+    decorate(&mut thumb, badge, settings);
+    let thumb = DynamicImage::ImageRgba8(thumb);
+    let thumb_path = save_preview(&thumb, &format!("{}.thumb", name), settings)?;
+    let icon = image.resize_to_fill(settings.icon_size, settings.icon_size, FilterType::Lanczos3);
+    save_preview(&icon, &format!("{}.icon", name), settings)?;
+
+    Ok(thumb_path)
+}
+
+// Write an image as `<stem>.<extension>`, unless an identical capture already did
+fn save_preview(image: &DynamicImage, stem: &str, settings: &PreviewConfig) -> Result<String> {
+    let path = format!("{}.{}", stem, settings.format.extension());
+    if Path::new(&path).exists() {
+        return Ok(path);
+    }
+
+    let file = BufWriter::new(File::create(&path)?);
+    let encoded = match settings.format {
+        PreviewFormat::Png => image.write_with_encoder(PngEncoder::new(file)),
+        // JPEG has no alpha channel
+        PreviewFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(file, settings.quality.clamp(1, 100))),
+        // The WebP encoder is lossless only, so `quality` doesn't apply
+        PreviewFormat::Webp => DynamicImage::ImageRgba8(image.to_rgba8()).write_with_encoder(WebPEncoder::new_lossless(file)),
+    };
+    if let Err(e) = encoded {
+        fs::remove_file(&path).ok();
+        return Err(io::Error::other(format!("could not encode {}: {}", path, e)));
+    }
+    Ok(path)
+}
+
+// Add the app icon badge, the border and the rounded corners to a thumbnail
+fn decorate(thumb: &mut RgbaImage, badge: Option<&str>, settings: &PreviewConfig) {
+    let (width, height) = thumb.dimensions();
+
+    // Icon themes also hold SVGs, which can't be loaded here
+    if let Some(icon) = badge.and_then(|path| image::open(path).ok()) {
+        let side = (width.min(height) / 3).max(1);
+        let icon = icon.resize(side, side, FilterType::Lanczos3).to_rgba8();
+        let margin = side / 8 + settings.border_width;
+        let x = width.saturating_sub(icon.width() + margin);
+        let y = height.saturating_sub(icon.height() + margin);
+        imageops::overlay(thumb, &icon, x as i64, y as i64);
+    }
+
+    let radius = settings.corner_radius.min(width / 2).min(height / 2) as f32;
+    let border = settings.border_width as f32;
+    if radius == 0.0 && border == 0.0 {
+        return;
+    }
+    let border_color = parse_color(&settings.border_color).unwrap_or(Rgba([76, 86, 106, 255]));
+
+    for (x, y, pixel) in thumb.enumerate_pixels_mut() {
+        let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+        // Distance inward from the rounded rectangle's edge
+        let (cx, cy) = (px.clamp(radius, width as f32 - radius), py.clamp(radius, height as f32 - radius));
+        let inside = if (cx, cy) != (px, py) {
+            radius - ((px - cx).powi(2) + (py - cy).powi(2)).sqrt()
+        } else {
+            px.min(py).min(width as f32 - px).min(height as f32 - py)
+        };
+
+        if inside < 0.0 {
+            *pixel = Rgba([0, 0, 0, 0]);
+        } else if inside < border {
+            *pixel = border_color;
+        }
+    }
+}
+
+// `#rrggbb` to an opaque color
+fn parse_color(color: &str) -> Option<Rgba<u8>> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(Rgba([channel(0)?, channel(2)?, channel(4)?, 255]))
+}
+
+/// Delete the raw screenshot of a window and, if given, its thumbnail and icon.
+pub fn remove_previews(preview_dir: &str, window_id: &str, thumb_path: Option<&str>) {
+    fs::remove_file(format!("{}/{}.png", preview_dir, window_id)).ok();
+    if let Some(thumb_path) = thumb_path {
+        fs::remove_file(thumb_path).ok();
+        if let Some((stem, extension)) = thumb_path.rsplit_once(".thumb.") {
+            fs::remove_file(format!("{}.icon.{}", stem, extension)).ok();
+        }
+    }
+}
+
+// Files younger than this may belong to a minimize still under way
+const GC_GRACE: Duration = Duration::from_secs(60);
+
+/// Delete the preview files not in `referenced` (thumbnail paths; their icons
+/// go along), and with `max_age` also the referenced ones older than that.
+/// Returns how many files were removed.
+pub fn collect_garbage(preview_dir: &str, referenced: &[String], max_age: Option<Duration>) -> Result<usize> {
+    let kept: Vec<String> = referenced
+        .iter()
+        .flat_map(|thumb| {
+            let icon = thumb.rsplit_once(".thumb.").map(|(stem, extension)| format!("{}.icon.{}", stem, extension));
+            [Some(thumb.clone()), icon]
+        })
+        .flatten()
+        .collect();
+
+    let mut removed = 0;
+    for entry in fs::read_dir(preview_dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let Ok(age) = entry.metadata().and_then(|m| m.modified()).map(|time| time.elapsed().unwrap_or_default()) else {
+            continue;
+        };
+        if !path.is_file() || age < GC_GRACE {
+            continue;
+        }
+
+        let referenced = kept.iter().any(|p| Path::new(p) == path);
+        let expired = max_age.is_some_and(|max_age| age > max_age);
+        if (!referenced || expired) && fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Delete every file in the preview directory, returning how many were removed.
+pub fn remove_all_previews(preview_dir: &str) -> Result<usize> {
+    let mut removed = 0;
+    for entry in fs::read_dir(preview_dir)? {
+        let path = entry?.path();
+        if path.is_file() && fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+// The preview worker is the minhypr binary itself; when embedded in another
+// program, fall back to the one in PATH
+pub(crate) fn preview_worker() -> String {
+    env::current_exe()
+        .ok()
+        .filter(|exe| exe.file_name().is_some_and(|name| name == "minhypr"))
+        .map(|exe| exe.to_string_lossy().to_string())
+        .unwrap_or_else(|| String::from("minhypr"))
+}
+
+/// Process a screenshot into thumbnails in a detached `minhypr generate-preview` child.
+pub fn spawn_preview_job(window_id: &str, screenshot: &str) -> Result<()> {
+    follow_instance(Command::new(preview_worker()).args(["generate-preview", window_id, screenshot])).spawn()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manager::tests::TempDir;
+
+    #[test]
+    fn previews_are_encoded_in_the_configured_format() {
+        let dir = TempDir::new();
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(8, 6, Rgba([40, 80, 120, 255])));
+
+        for (format, magic) in [(PreviewFormat::Png, &b"\x89PNG"[..]), (PreviewFormat::Jpeg, b"\xff\xd8\xff"), (PreviewFormat::Webp, b"RIFF")] {
+            let settings = PreviewConfig { format, ..Default::default() };
+            let path = save_preview(&image, &format!("{}/thumb", dir.path()), &settings).unwrap();
+
+            assert!(path.ends_with(format.extension()));
+            assert!(fs::read(&path).unwrap().starts_with(magic));
+            let decoded = image::open(&path).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (8, 6));
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::{fs, path::Path, process::Command, thread, time::SystemTime};
+
+use serde_json::json;
+
+use crate::{
+    config::{Config, RefreshMethod},
+    control,
+    hyprland::follow_instance,
+    icons::expand_home,
+    state::state_root,
+    waybar::signal_waybar,
+};
+
+/// File written by the `file` refresh method.
+pub fn refresh_file(config: &Config) -> String {
+    match &config.refresh.file {
+        Some(file) if !file.is_empty() => expand_home(file),
+        _ => format!("{}/changed", state_root()),
+    }
+}
+
+// Write the time, so inotify watchers see a modification
+fn touch(path: &str) {
+    let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or_default();
+    if let Some(dir) = Path::new(path).parent() {
+        fs::create_dir_all(dir).ok();
+    }
+    if let Err(e) = fs::write(path, format!("{}\n", now)) {
+        warn!("Could not write {}: {}", path, e);
+    }
+}
+
+// The daemon answers on another thread, even when it's handling the request
+// that got here; without a daemon there are no subscribers to tell
+fn notify_daemon() {
+    control::send_request(&json!({"command": "refresh"})).ok();
+}
+
+fn run_command(command: Option<&str>) {
+    let Some(command) = command.filter(|command| !command.trim().is_empty()) else {
+        warn!("The command refresh method needs a command in [refresh]");
+        return;
+    };
+    match follow_instance(&mut Command::new("sh")).args(["-c", command]).env("MINHYPR_EVENT", "refresh").spawn() {
+        Ok(mut child) => {
+            thread::spawn(move || child.wait());
+        }
+        Err(e) => warn!("Failed to run the refresh command: {}", e),
+    }
+}
+
+/// Tell bars the minimized set changed, by each of the `[refresh]` methods.
+pub fn refresh_bars(config: &Config) {
+    for method in &config.refresh.methods {
+        match method {
+            RefreshMethod::Signal => signal_waybar(config.waybar.signal),
+            RefreshMethod::File => touch(&refresh_file(config)),
+            RefreshMethod::Socket => notify_daemon(),
+            RefreshMethod::Command => run_command(config.refresh.command.as_deref()),
+        }
+    }
+}
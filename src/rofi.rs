@@ -0,0 +1,805 @@
+use std::{
+    env, fs,
+    io::{Result, Write},
+    process::{Command, Stdio},
+    thread,
+};
+
+use crate::{
+    compositor::Compositor,
+    config::{CaptureMethod, RofiLayout, RofiTheme},
+    hooks::shell_quote,
+    hyprland::Client,
+    icons,
+    manager::Manager,
+    preview::{self, preview_worker},
+    theme::{self, Palette},
+    window::MinimizedWindow,
+};
+
+// Regular workspaces that exist, plus 1 to 10
+fn workspace_choices(manager: &Manager) -> Result<Vec<String>> {
+    let mut workspaces: Vec<(i64, String)> = manager
+        .compositor
+        .workspaces()?
+        .into_iter()
+        .filter(|w| !w.name.starts_with("special:"))
+        .map(|w| (w.id, w.name))
+        .collect();
+    for id in 1..=10 {
+        if !workspaces.iter().any(|(existing, _)| *existing == id) {
+            workspaces.push((id, id.to_string()));
+        }
+    }
+    workspaces.sort();
+    Ok(workspaces.into_iter().map(|(_, name)| name).collect())
+}
+
+// Ask for a workspace with rofi; any name can be typed
+fn prompt_workspace(manager: &Manager) -> Result<Option<String>> {
+    let mut prompt = Command::new("rofi")
+        .args(["-dmenu", "-p", "Workspace:"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    if let Some(ref mut stdin) = prompt.stdin {
+        stdin.write_all(format!("{}\n", workspace_choices(manager)?.join("\n")).as_bytes())?;
+    }
+
+    let output = prompt.wait_with_output()?;
+    let workspace = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok(Some(workspace).filter(|w| output.status.success() && !w.is_empty()))
+}
+
+// Menu rows in the manager's menu order; with grouping, a header row
+// (without a window) starts each group
+fn menu_rows<'a>(manager: &Manager, windows: &'a [MinimizedWindow]) -> Vec<(String, Option<&'a MinimizedWindow>)> {
+    let mut rows = Vec::new();
+    let mut last_header = None;
+    for window in manager.menu_order(windows) {
+        let header = manager.menu_group(window);
+        if header.is_some() && header != last_header {
+            rows.push((format!("── {} ──", header.as_deref().unwrap_or_default()), None));
+            last_header = header;
+        }
+        rows.push((manager.config.menu_entry(window), Some(window)));
+    }
+    rows
+}
+
+// Theme of the menus for this run, with the configured colors; returns its path
+fn write_menu_theme(manager: &Manager) -> Result<String> {
+    let rofi_dir = format!("{}/rofi", manager.store.cache_dir());
+    fs::create_dir_all(&rofi_dir)?;
+    let rofi_config = format!("{}/minhypr.rasi", rofi_dir);
+    let palette = theme::palette_or_default(manager.config.rofi_theme);
+    let config_content = r#"
+configuration {
+    modi: "window";
+    display-window: "Minimized Windows";
+    window-format: "{icon} {t}";
+    window-thumbnail: true;
+    show-icons: true;
+    drun-display-format: "{name}";
+    fullscreen: false;
+    sidebar-mode: false;
+}
+"#
+    .to_string()
+        + &format!(
+            "\n* {{\n    background-color: {};\n    text-color: {};\n    border-color: {};\n    selected-background: {};\n    selected-text: {};\n}}\n",
+            palette.background, palette.foreground, palette.border, palette.background_alt, palette.selected
+        )
+        + r#"
+window {
+    width: 800px;
+    border: 2px;
+    border-radius: 6px;
+    padding: 12px;
+}
+
+element {
+    padding: 8px 12px;
+    border-radius: 4px;
+    spacing: 8px;
+}
+
+element selected {
+    background-color: @selected-background;
+    text-color: @selected-text;
+}
+
+element-icon {
+    size: 32px;
+}
+
+element-text {
+    vertical-align: 0.5;
+}
+"#;
+    fs::write(&rofi_config, config_content)?;
+    Ok(rofi_config)
+}
+
+/// Show the Rofi menu to pick a window to restore.
+pub fn show_restore_menu(manager: &Manager) -> Result<()> {
+    info!("Starting restoration menu with Rofi...");
+    
+    let windows = manager.windows()?;
+    
+    if windows.is_empty() {
+        info!("No minimized windows");
+        return Ok(());
+    }
+
+    let rofi_config = write_menu_theme(manager)?;
+
+    // Rofi runs `minhypr show-rofi --script` itself for the entries and the selection
+    let rows = menu_rows(manager, &windows);
+    let output = Command::new("rofi")
+        .args([
+            "-show", "minimized",
+            "-modi", &format!("minimized:{} show-rofi --script", shell_quote(&preview_worker())),
+            "-theme", &rofi_config,
+            "-no-fixed-num-lines",
+            "-no-click-to-exit",
+            "-kb-custom-1", "Alt+w", // restore to another workspace
+            "-kb-custom-2", "Alt+x", // close
+            "-theme-str", &layout_theme(manager.config.rofi_layout, windows.len()),
+        ])
+        .output()?;
+    
+    if !output.status.success() {
+        // Fallback to simple Rofi if advanced configuration fails
+        let mut items = String::new();
+        for (entry, window) in &rows {
+            match window {
+                Some(_) => items.push_str(&format!("{}\n", entry)),
+                None => items.push_str(&format!("{}\0nonselectable\x1ftrue\n", entry)),
+            }
+        }
+
+        let mut selection = Command::new("rofi")
+            .args([
+                "-dmenu",
+                "-p", "Restore window:",
+                "-i", // case insensitive matching
+                "-no-custom",
+                "-kb-custom-1", "Alt+w", // restore to another workspace
+                "-kb-custom-2", "Alt+x", // close
+                "-format", "i" // print the index of the selection
+            ])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        if let Some(ref mut stdin) = selection.stdin {
+            stdin.write_all(items.as_bytes())?;
+        }
+
+        let output = selection.wait_with_output()?;
+        let selection = String::from_utf8_lossy(&output.stdout);
+        let selection = selection.trim();
+
+        if let Some((_, Some(window))) = selection.parse::<usize>().ok().and_then(|i| rows.get(i)) {
+            // Custom keybinding n exits with 9 + n
+            match output.status.code() {
+                Some(10) => {
+                    if let Some(workspace) = prompt_workspace(manager)? {
+                        manager.restore_to_workspace(&window.address, &workspace)?;
+                    }
+                }
+                Some(11) => manager.close_minimized(&window.address)?,
+                _ => manager.restore(&window.address)?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Screenshots of open windows for the minimize menu, taken in parallel into
+// `dir`; None where a window can't be captured or rules forbid it. A screen
+// area capture only shows windows on the workspaces on screen.
+fn live_thumbnails(manager: &Manager, clients: &[Client], dir: &str) -> Result<Vec<Option<String>>> {
+    let shown: Vec<i64> = manager.compositor.monitors()?.iter().map(|m| m.active_workspace.id).collect();
+    let capture = manager.config.capture;
+
+    Ok(thread::scope(|scope| {
+        let captures: Vec<_> = clients
+            .iter()
+            .map(|client| {
+                let allowed = !manager.config.never_preview(&client.class, &client.title);
+                let visible = shown.contains(&client.workspace.id);
+                scope.spawn(move || {
+                    if !allowed {
+                        return None;
+                    }
+                    if capture != CaptureMethod::Geometry {
+                        match preview::capture_window_toplevel(dir, &client.address) {
+                            Ok(path) => return Some(path),
+                            Err(_) if capture == CaptureMethod::Toplevel => return None,
+                            Err(_) => {}
+                        }
+                    }
+                    let geometry = format!("{},{} {}x{}", client.at.0, client.at.1, client.size.0, client.size.1);
+                    visible.then(|| preview::capture_window_screenshot(dir, &client.address, &geometry).ok()).flatten()
+                })
+            })
+            .collect();
+        captures.into_iter().map(|capture| capture.join().ok().flatten()).collect()
+    }))
+}
+
+/// Rofi menu of the open windows, with live thumbnails; the selected ones
+/// (Shift+Enter marks several) are minimized. Returns their addresses.
+pub fn show_minimize_menu(manager: &Manager) -> Result<Vec<String>> {
+    let clients = manager.open_clients()?;
+    if clients.is_empty() {
+        info!("No windows to minimize");
+        return Ok(Vec::new());
+    }
+
+    // Fresh captures every time, deleted once the menu closes
+    let live_dir = format!("{}/rofi/live", manager.store.cache_dir());
+    fs::remove_dir_all(&live_dir).ok();
+    fs::create_dir_all(&live_dir)?;
+    let thumbnails = live_thumbnails(manager, &clients, &live_dir)?;
+
+    let mut entries = String::new();
+    for (client, thumbnail) in clients.iter().zip(&thumbnails) {
+        let title = manager.config.stored_title(&client.class, &client.title);
+        let title = manager.config.rewrite_title(&client.class, &title);
+        let icon = thumbnail.clone().or_else(|| icons::app_icon(&manager.config, &client.class).1).unwrap_or_else(|| client.class.to_lowercase());
+        entries.push_str(&format!(
+            "{} - {}  ·  workspace {}\0icon\x1f{}\n",
+            client.class,
+            title.replace('\n', " "),
+            client.workspace.name,
+            icon
+        ));
+    }
+
+    let theme = write_menu_theme(manager)?;
+    let mut layout = layout_theme(manager.config.rofi_layout, clients.len());
+    if manager.config.rofi_layout == RofiLayout::List {
+        layout.push_str(" element-icon {size: 64px;}");
+    }
+    let mut rofi = Command::new("rofi")
+        .args([
+            "-dmenu",
+            "-p", "Minimize:",
+            "-i",
+            "-show-icons",
+            "-no-custom",
+            "-multi-select",
+            // Print the indexes of the selection, titles may repeat
+            "-format", "i",
+            "-theme", &theme,
+            "-theme-str", &layout,
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    if let Some(ref mut stdin) = rofi.stdin {
+        stdin.write_all(entries.as_bytes())?;
+    }
+    let output = rofi.wait_with_output()?;
+    fs::remove_dir_all(&live_dir).ok();
+
+    let selected: Vec<Client> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.trim().parse::<usize>().ok())
+        .filter_map(|i| clients.get(i).cloned())
+        .collect();
+    if selected.is_empty() {
+        return Ok(Vec::new());
+    }
+    manager.minimize_batch(&selected)
+}
+
+// Full-screen grid of large thumbnails, one column per window up to a square-ish layout
+fn expose_theme(count: usize, palette: &Palette) -> String {
+    let columns = ((count as f64).sqrt().ceil() as usize).clamp(1, 6);
+
+    format!(
+        r#"
+window {{ fullscreen: true; background-color: {background}E6; padding: 5%; }}
+mainbox {{ children: [ inputbar, listview ]; background-color: transparent; }}
+inputbar {{ children: [ entry ]; padding: 12px; margin: 0 0 24px 0; background-color: {background_alt}; border-radius: 6px; }}
+entry {{ placeholder: "Filter minimized windows"; text-color: {foreground}; background-color: transparent; }}
+listview {{ columns: {columns}; lines: {lines}; spacing: 24px; fixed-columns: true; flow: horizontal; background-color: transparent; }}
+element {{ orientation: vertical; padding: 16px; spacing: 12px; border-radius: 8px; background-color: {background_alt}; text-color: {foreground}; }}
+element selected {{ background-color: {selected}; text-color: {background}; }}
+element-icon {{ size: 240px; background-color: transparent; }}
+element-text {{ horizontal-align: 0.5; background-color: transparent; text-color: inherit; }}
+"#,
+        columns = columns,
+        lines = count.div_ceil(columns),
+        background = palette.background,
+        background_alt = palette.background_alt,
+        foreground = palette.foreground,
+        selected = palette.selected,
+    )
+}
+
+// Width and arrangement of the restore menu; the grid shows up to four big
+// thumbnails per row
+fn layout_theme(layout: RofiLayout, count: usize) -> String {
+    match layout {
+        RofiLayout::List => String::from("window {width: 650px;}"),
+        RofiLayout::Grid => {
+            let columns = count.clamp(1, 4);
+            format!(
+                "window {{width: {}px;}} listview {{columns: {}; lines: 2; flow: horizontal; fixed-columns: true; spacing: 12px;}} \
+                 element {{orientation: vertical; padding: 12px; spacing: 8px;}} element-icon {{size: 200px;}} \
+                 element-text {{horizontal-align: 0.5;}}",
+                columns * 240 + 40,
+                columns
+            )
+        }
+    }
+}
+
+/// Mission-control style overview: every minimized window's thumbnail in a
+/// full-screen Rofi grid; the selected one is restored.
+pub fn show_expose(manager: &Manager) -> Result<()> {
+    let windows = manager.windows()?;
+
+    if windows.is_empty() {
+        info!("No minimized windows");
+        return Ok(());
+    }
+
+    let ordered = manager.menu_order(&windows);
+    let mut entries = String::new();
+    for window in &ordered {
+        let icon = window
+            .preview_path
+            .clone()
+            .or_else(|| window.icon_path.clone())
+            .unwrap_or_else(|| window.class.to_lowercase());
+        entries.push_str(&format!(
+            "{} - {}\0icon\x1f{}\n",
+            window.class,
+            window.original_title.replace('\n', " "),
+            icon
+        ));
+    }
+
+    let mut rofi = Command::new("rofi")
+        .args([
+            "-dmenu",
+            "-i",
+            "-show-icons",
+            "-no-custom",
+            "-kb-custom-1", "Alt+x", // close
+            // Print the index of the selection, titles may repeat
+            "-format", "i",
+            "-theme-str", &expose_theme(windows.len(), &theme::palette_or_default(manager.config.rofi_theme)),
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    if let Some(ref mut stdin) = rofi.stdin {
+        stdin.write_all(entries.as_bytes())?;
+    }
+
+    let output = rofi.wait_with_output()?;
+    let selection = String::from_utf8_lossy(&output.stdout);
+
+    if let Some(window) = selection.trim().parse::<usize>().ok().and_then(|i| ordered.get(i)) {
+        // Custom keybinding 1 exits with 10
+        if output.status.code() == Some(10) {
+            manager.close_minimized(&window.address)?;
+        } else {
+            manager.restore(&window.address)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Write the Rofi theme and the menu scripts to `~/.config/minhypr`, with the
+/// colors of `theme`.
+pub fn generate_rofi_config(theme: RofiTheme, layout: RofiLayout) -> Result<()> {
+    let home = env::var("HOME").unwrap_or_else(|_| String::from("/tmp"));
+    let config_dir = format!("{}/.config/minhypr", home);
+    fs::create_dir_all(&config_dir)?;
+    
+    // Generate Rofi theme file
+    let rofi_theme = format!("{}/minhypr.rasi", config_dir);
+    let theme_content = r#"/**
+ * MinHypr Rofi Theme
+ */
+
+configuration {
+    modi: "window";
+    display-window: "Minimized Windows";
+    window-format: "{icon} {t}";
+    window-thumbnail: true;
+    show-icons: true;
+    drun-display-format: "{name}";
+    fullscreen: false;
+    sidebar-mode: false;
+}
+
+"#
+    .to_string()
+        + &theme::palette(theme)?.rasi_variables()
+        + r#"
+window {
+    width: 650px;
+    border: 2px;
+    border-color: @border;
+    border-radius: 6px;
+    padding: 12px;
+    background-color: @background;
+}
+
+mainbox {
+    border: 0;
+    padding: 0;
+}
+
+message {
+    border: 2px 0px 0px;
+    border-color: @border;
+    padding: 10px;
+}
+
+textbox {
+    text-color: @foreground;
+}
+
+inputbar {
+    children: [ prompt, textbox-prompt-colon, entry, case-indicator ];
+    padding: 12px;
+}
+
+prompt {
+    text-color: @selected;
+}
+
+textbox-prompt-colon {
+    expand: false;
+    str: ":";
+    margin: 0px 4px 0px 0px;
+    text-color: @foreground;
+}
+
+entry {
+    text-color: @foreground;
+}
+
+case-indicator {
+    text-color: @foreground;
+}
+
+listview {
+    fixed-height: 0;
+    border: 2px 0px 0px;
+    border-color: @border;
+    spacing: 4px;
+    scrollbar: true;
+    padding: 10px 5px 0px;
+}
+
+element {
+    border: 0;
+    border-radius: 4px;
+    padding: 8px 12px;
+}
+
+element normal.normal {
+    background-color: inherit;
+    text-color: @foreground;
+}
+
+element selected.normal {
+    background-color: @background-alt;
+    text-color: @selected;
+}
+
+element-icon {
+    size: 42px;
+    margin: 0 8px 0 0;
+}
+
+element-text {
+    background-color: inherit;
+    text-color: inherit;
+    vertical-align: 0.5;
+}
+
+scrollbar {
+    width: 4px;
+    border: 0;
+    handle-width: 8px;
+    padding: 0;
+    handle-color: @border;
+}
+
+button {
+    text-color: @foreground;
+    border: 2px 0px 0px;
+    border-color: @border;
+    border-radius: 4px;
+}
+
+button selected {
+    background-color: @background-alt;
+    text-color: @selected;
+}
+"#;
+    fs::write(&rofi_theme, theme_content)?;
+    
+    // Generate portable launch script
+    let rofi_script = format!("{}/launch-menu.sh", config_dir);
+    let script_content = r#"#!/bin/bash
+
+# Script to launch the Rofi menu for minimized windows
+# Generated by MinHypr - Portable Version
+
+# Find minhypr executable
+if [ -x "$HOME/.local/bin/minhypr" ]; then
+    MINHYPR="$HOME/.local/bin/minhypr"
+elif [ -x "/usr/local/bin/minhypr" ]; then
+    MINHYPR="/usr/local/bin/minhypr"
+elif [ -x "/usr/bin/minhypr" ]; then
+    MINHYPR="/usr/bin/minhypr"
+elif command -v minhypr &> /dev/null; then
+    MINHYPR="minhypr"
+else
+    notify-send "Error" "Unable to find minhypr executable"
+    exit 1
+fi
+
+# Configure theme
+THEME="$HOME/.config/minhypr/minhypr.rasi"
+
+# Execute Rofi with configurations. Shift+Enter marks several windows,
+# Enter restores all marked ones (or the highlighted one), Alt+x closes
+# them and Alt+p peeks at the highlighted one without restoring it
+SELECTION=$($MINHYPR show-rofi | rofi \
+  -dmenu \
+  -multi-select \
+  -i \
+  -show-icons \
+  -p "Minimized Windows" \
+  -mesg "Enter: restore   Shift+Enter: mark   Alt+x: close   Alt+p: peek" \
+  -kb-custom-1 "Alt+x" \
+  -kb-custom-2 "Alt+p" \
+  -theme "$THEME" \
+  -no-fixed-num-lines \
+  -theme-str "window {width: 650px;}")
+RETV=$?
+
+# Rofi's exit code tells which key was used (10 = kb-custom-1, ...)
+[ -n "$SELECTION" ] && ROFI_RETV=$RETV $MINHYPR show-rofi "$SELECTION"
+"#
+    .replace("window {width: 650px;}", &layout_theme(layout, 4));
+
+    fs::write(&rofi_script, script_content)?;
+    Command::new("chmod").args(["+x", &rofi_script]).output()?;
+    
+    // Generate simple backup script (in case Rofi fails)
+    let simple_script = format!("{}/simple-menu.sh", config_dir);
+    let simple_content = r#"#!/bin/bash
+
+# Simple script to show and restore minimized windows
+# Works as a backup in case Rofi has problems
+
+# Find minhypr executable
+if [ -x "$HOME/.local/bin/minhypr" ]; then
+    MINHYPR="$HOME/.local/bin/minhypr"
+elif [ -x "/usr/local/bin/minhypr" ]; then
+    MINHYPR="/usr/local/bin/minhypr"
+elif [ -x "/usr/bin/minhypr" ]; then
+    MINHYPR="/usr/bin/minhypr"
+elif command -v minhypr &> /dev/null; then
+    MINHYPR="minhypr"
+else
+    notify-send "Error" "Unable to find minhypr executable"
+    exit 1
+fi
+
+# Check if there are minimized windows
+if [ "$($MINHYPR show --format '{count}')" = "0" ]; then
+    notify-send "MinHypr" "No minimized windows"
+    exit 0
+fi
+
+# Use simple Rofi to show the list of windows
+$MINHYPR restore
+"#;
+    
+    fs::write(&simple_script, simple_content)?;
+    Command::new("chmod").args(["+x", &simple_script]).output()?;
+    
+    // Generate script to restore all windows
+    let restore_script = format!("{}/restore-all.sh", config_dir);
+    let restore_content = r#"#!/bin/bash
+
+# Script to restore all minimized windows
+
+# Find minhypr executable
+if [ -x "$HOME/.local/bin/minhypr" ]; then
+    MINHYPR="$HOME/.local/bin/minhypr"
+elif [ -x "/usr/local/bin/minhypr" ]; then
+    MINHYPR="/usr/local/bin/minhypr"
+elif [ -x "/usr/bin/minhypr" ]; then
+    MINHYPR="/usr/bin/minhypr"
+elif command -v minhypr &> /dev/null; then
+    MINHYPR="minhypr"
+else
+    notify-send "Error" "Unable to find minhypr executable"
+    exit 1
+fi
+
+$MINHYPR restore-all
+"#;
+    
+    fs::write(&restore_script, restore_content)?;
+    Command::new("chmod").args(["+x", &restore_script]).output()?;
+    
+    info!("Rofi configuration generated in: {}", config_dir);
+    info!("Available scripts:");
+    info!("  {}/launch-menu.sh - Full Rofi menu", config_dir);
+    info!("  {}/simple-menu.sh - Simple menu (in case Rofi fails)", config_dir);
+    info!("  {}/restore-all.sh - Restores all windows", config_dir);
+    
+    info!("\nYou can add these shortcuts to your Hyprland:");
+    info!("  bind = ALT SHIFT, M, exec, $HOME/.config/minhypr/launch-menu.sh");
+    info!("  bind = ALT CTRL, M, exec, $HOME/.config/minhypr/simple-menu.sh");
+    info!("  bind = ALT SHIFT, R, exec, $HOME/.config/minhypr/restore-all.sh");
+    
+    Ok(())
+}
+
+// Addresses of the selected entries: Rofi passes the selected lines (one
+// argument, or several lines at once with -multi-select), each ending in
+// "info<address>", or the address alone in $ROFI_INFO in script mode
+fn selected_addresses(selection: &[String]) -> Vec<String> {
+    let mut addresses: Vec<String> = selection
+        .iter()
+        .flat_map(|arg| arg.lines())
+        .filter_map(|line| line.rsplit_once(" info").map(|(_, address)| address.trim().to_string()))
+        .filter(|address| !address.is_empty())
+        .collect();
+
+    if addresses.is_empty() {
+        if let Ok(address) = env::var("ROFI_INFO") {
+            if !address.is_empty() {
+                addresses.push(address);
+            }
+        }
+    }
+
+    addresses.dedup();
+    addresses
+}
+
+// Icon of a window's row: its preview, else its app icon; Rofi resolves
+// bare icon names itself
+fn row_icon(window: &MinimizedWindow) -> String {
+    window.preview_path.clone().or_else(|| window.icon_path.clone()).unwrap_or_else(|| window.class.to_lowercase())
+}
+
+// A Rofi script-mode row: text, then options after \0, separated by \x1f
+fn script_row(text: &str, options: &[(&str, &str)]) -> String {
+    let clean = |value: &str| value.replace(['\n', '\0', '\x1f'], " ");
+    let mut row = clean(text);
+    for (i, (key, value)) in options.iter().enumerate() {
+        row.push(if i == 0 { '\0' } else { '\x1f' });
+        row.push_str(&format!("{}\x1f{}", key, clean(value)));
+    }
+    row
+}
+
+/// Rofi script mode (`rofi -modi "minimized:minhypr show-rofi --script"`):
+/// print the entries, or act on the selection Rofi passes back, told apart
+/// by `ROFI_RETV`. Printing nothing after a selection closes Rofi.
+pub fn run_script_mode(manager: &Manager, selection: &[String]) -> Result<()> {
+    // 0 = first call, 1 = entry selected, 2 = custom input, 10 = kb-custom-1, 11 = kb-custom-2
+    let retv = env::var("ROFI_RETV").ok().and_then(|retv| retv.parse::<i32>().ok()).unwrap_or(0);
+    let info = env::var("ROFI_INFO").unwrap_or_default();
+    // Set by the workspace prompt: the window to restore
+    let data = env::var("ROFI_DATA").unwrap_or_default();
+    let input = selection.join(" ");
+
+    match retv {
+        1 | 2 if !data.is_empty() && !input.trim().is_empty() => {
+            manager.restore_to_workspace(&data, input.trim())?;
+        }
+        1 if !info.is_empty() => manager.restore(&info)?,
+        // Alt+w: pick the workspace to restore the window to, or type one
+        10 if !info.is_empty() => {
+            println!("{}", script_row("", &[("prompt", "Workspace")]));
+            println!("{}", script_row("", &[("data", &info)]));
+            println!("{}", script_row("", &[("no-custom", "false")]));
+            for workspace in workspace_choices(manager)? {
+                println!("{}", script_row(&workspace, &[]));
+            }
+        }
+        // Alt+x: close the window and list the others again, until none is left
+        11 if !info.is_empty() => {
+            manager.close_minimized(&info)?;
+            if !manager.windows()?.is_empty() {
+                print_script_entries(manager)?;
+            }
+        }
+        0 => print_script_entries(manager)?,
+        _ => {}
+    }
+
+    Ok(())
+}
+
+// The restore menu's entries in script mode
+fn print_script_entries(manager: &Manager) -> Result<()> {
+    println!("{}", script_row("", &[("prompt", "Minimized Windows")]));
+    println!("{}", script_row("", &[("message", "Enter: restore   Alt+w: to workspace   Alt+x: close")]));
+    println!("{}", script_row("", &[("no-custom", "true")]));
+    println!("{}", script_row("", &[("use-hot-keys", "true")]));
+
+    let windows = manager.windows()?;
+    for (entry, window) in menu_rows(manager, &windows) {
+        let row = match window {
+            Some(window) => script_row(&entry, &[("icon", &row_icon(window)), ("info", &window.address)]),
+            None => script_row(&entry, &[("nonselectable", "true")]),
+        };
+        println!("{}", row);
+    }
+    Ok(())
+}
+
+/// Rofi entry point: print the entries, or restore the selected windows when
+/// called with Rofi's selection.
+pub fn show_rofi_menu(manager: &Manager, selection: &[String]) -> Result<()> {
+    if !selection.is_empty() {
+        // 0 = normal selection (dmenu exit code), 1 = selection in script mode,
+        // 10 + n = kb-custom-(n + 1)
+        let action = env::var("ROFI_RETV").ok().and_then(|retv| retv.parse::<i32>().ok()).unwrap_or(0);
+
+        for address in selected_addresses(selection) {
+            match action {
+                // kb-custom-1: close the window instead of restoring it
+                10 => {
+                    manager.close_minimized(&address)?;
+                }
+                // kb-custom-2: show the window in the special workspace overlay
+                11 => {
+                    manager.peek(&address, None)?;
+                    break;
+                }
+                _ => manager.restore(&address)?,
+            }
+        }
+        return Ok(());
+    }
+
+    let windows = manager.windows()?;
+    
+    if windows.is_empty() {
+        println!("INFO: No minimized windows");
+        return Ok(());
+    }
+
+    // windows() already leaves out (and prunes, under the lock) the closed ones.
+    // In dmenu mode Rofi hands back only the text, so it ends in "info<address>"
+    for (entry, window) in menu_rows(manager, &windows) {
+        let row = match window {
+            Some(window) => {
+                script_row(&format!("{} info{}", entry, window.address), &[("icon", &row_icon(window)), ("info", &window.address)])
+            }
+            None => script_row(&entry, &[("nonselectable", "true")]),
+        };
+        println!("{}", row);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,464 @@
+use std::io::{self, ErrorKind};
+
+use serde_json::{json, Map, Value};
+
+use crate::{compositor::Compositor, control, error::exit_code, manager::Manager};
+
+/// Version of the JSON-RPC protocol, bumped on incompatible changes.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+// JSON-RPC 2.0 error codes; minhypr's own failures are -32000 minus the
+// exit status the command would have (-32002 for a window not found)
+pub const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const FAILURE: i64 = -32000;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Address {
+    None,
+    Optional,
+    Required,
+}
+
+// Methods with their `address` parameter and description
+const METHODS: [(&str, Address, &str); 16] = [
+    ("version", Address::None, "Protocol and minhypr versions"),
+    ("ping", Address::None, "Answers \"pong\""),
+    ("list", Address::None, "The minimized windows"),
+    ("minimize", Address::Optional, "Minimize the window, or the active one; true if it was minimized"),
+    ("minimize_all", Address::None, "Minimize every window of the active workspace; the minimized addresses"),
+    ("minimize_others", Address::None, "Minimize the other windows of the active workspace; the minimized addresses"),
+    ("show_desktop", Address::None, "Minimize every window, or restore them when done again; true if they were restored"),
+    ("toggle", Address::Optional, "Minimize or restore the window, or the active one"),
+    ("restore", Address::Required, "Restore a minimized window"),
+    ("restore_last", Address::None, "Restore the most recently minimized window; its address, or null"),
+    ("restore_all", Address::None, "Restore every minimized window"),
+    ("close", Address::Required, "Close a minimized window"),
+    ("cycle", Address::None, "Restore the last window and minimize the active one; the restored address, or null"),
+    ("undo", Address::None, "Minimize the last restored window again; its address, or null"),
+    ("refresh", Address::None, "Push a windows_changed notification to the subscribers"),
+    (
+        "subscribe",
+        Address::None,
+        "Keep the connection open and receive a windows_changed notification whenever the minimized set changes",
+    ),
+];
+
+/// Whether a request is JSON-RPC rather than the plain `command` protocol,
+/// which has no batches: any array is a JSON-RPC batch, valid or not.
+pub fn is_rpc(request: &Value) -> bool {
+    match request {
+        Value::Array(_) => true,
+        _ => request.get("jsonrpc").is_some(),
+    }
+}
+
+// A well-formed request without an id gets no answer, not even an error;
+// anything else without one (e.g. `1` or `{"command": ...}` in a batch) is an
+// invalid request, answered with a null id
+fn is_notification(request: &Value) -> bool {
+    request.get("id").is_none() && request["jsonrpc"] == "2.0" && request["method"].is_string()
+}
+
+/// An error response; `id` is null when the request couldn't be read.
+pub fn error_response(id: Value, code: i64, message: &str, data: Option<Value>) -> Value {
+    let mut error = json!({"code": code, "message": message});
+    if let Some(data) = data {
+        error["data"] = data;
+    }
+    json!({"jsonrpc": "2.0", "id": id, "error": error})
+}
+
+/// A notification for the subscribers: `windows_changed` with the count and windows.
+pub fn notification(method: &str, params: Value) -> Value {
+    json!({"jsonrpc": "2.0", "method": method, "params": params})
+}
+
+/// Answer to a JSON-RPC line.
+pub enum Reply {
+    /// The response, or none for notifications (requests without an `id`).
+    Response(Option<Value>),
+    /// A `subscribe` request: acknowledge it (unless it is a notification),
+    /// then stream notifications.
+    Subscribe(Option<Value>),
+}
+
+// The `address` parameter, by name or as the only positional one
+fn address_param(params: Option<&Value>) -> Result<Option<&str>, &'static str> {
+    let address = match params {
+        None | Some(Value::Null) => None,
+        Some(Value::Object(params)) => params.get("address"),
+        Some(Value::Array(params)) if params.len() <= 1 => params.first(),
+        Some(_) => return Err("params must be an object with an address"),
+    };
+    match address {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(address)) if !address.is_empty() => Ok(Some(address)),
+        Some(_) => Err("address must be a window address"),
+    }
+}
+
+// A single request; Err holds the error response
+fn call<C: Compositor>(manager: &Manager<C>, request: &Value) -> Result<(Value, Option<Value>), Value> {
+    if !request.is_object() {
+        return Err(error_response(Value::Null, INVALID_REQUEST, "request must be an object", None));
+    }
+    let id = request.get("id").cloned();
+    if id.as_ref().is_some_and(|id| !(id.is_string() || id.is_number() || id.is_null())) {
+        return Err(error_response(Value::Null, INVALID_REQUEST, "id must be a string, a number or null", None));
+    }
+    let reply_id = id.clone().unwrap_or(Value::Null);
+    let invalid = |message: &str| error_response(reply_id.clone(), INVALID_REQUEST, message, None);
+
+    if request["jsonrpc"] != "2.0" {
+        return Err(invalid("jsonrpc must be \"2.0\""));
+    }
+    let Some(name) = request["method"].as_str() else {
+        return Err(invalid("method must be a string"));
+    };
+    let Some((_, takes_address, _)) = METHODS.iter().find(|(method, _, _)| *method == name) else {
+        return Err(error_response(reply_id, METHOD_NOT_FOUND, &format!("unknown method: {}", name), None));
+    };
+
+    let address = address_param(request.get("params"))
+        .map_err(|message| error_response(reply_id.clone(), INVALID_PARAMS, message, None))?;
+    match (takes_address, address) {
+        (Address::Required, None) => {
+            return Err(error_response(reply_id, INVALID_PARAMS, &format!("{} needs an address", name), None));
+        }
+        (Address::None, Some(_)) => {
+            return Err(error_response(reply_id, INVALID_PARAMS, &format!("{} takes no address", name), None));
+        }
+        _ => {}
+    }
+
+    let result = match name {
+        "version" => Ok(json!({"protocol": PROTOCOL_VERSION, "minhypr": env!("CARGO_PKG_VERSION")})),
+        // The control socket streams the notifications after the answer
+        "subscribe" => Ok(json!({"protocol": PROTOCOL_VERSION})),
+        _ => control::handle_request(manager, &json!({"command": name, "address": address})),
+    };
+    match result {
+        Ok(result) => Ok((reply_id, id.map(|_| result))),
+        Err(e) => Err(failure(reply_id, &e)),
+    }
+}
+
+fn failure(id: Value, error: &io::Error) -> Value {
+    let code = exit_code(error);
+    if error.kind() == ErrorKind::InvalidInput {
+        return error_response(id, INVALID_PARAMS, &error.to_string(), None);
+    }
+    error_response(id, FAILURE - code as i64, &error.to_string(), Some(json!({"exit_code": code})))
+}
+
+// Response to one request of a line or batch; None for notifications
+fn respond<C: Compositor>(manager: &Manager<C>, request: &Value) -> Option<Value> {
+    let notification = is_notification(request);
+    let response = match call(manager, request) {
+        Ok((id, result)) => result.map(|result| json!({"jsonrpc": "2.0", "id": id, "result": result})),
+        Err(error) => Some(error),
+    };
+    // Notifications get no answer, not even for errors
+    response.filter(|_| !notification)
+}
+
+/// Handle a JSON-RPC request or batch.
+pub fn handle<C: Compositor>(manager: &Manager<C>, request: &Value) -> Reply {
+    match request {
+        Value::Array(batch) if batch.is_empty() => {
+            Reply::Response(Some(error_response(Value::Null, INVALID_REQUEST, "empty batch", None)))
+        }
+        Value::Array(batch) => {
+            let responses: Vec<Value> = batch
+                .iter()
+                .filter_map(|request| {
+                    if request["method"] == "subscribe" {
+                        let id = request.get("id").cloned().unwrap_or(Value::Null);
+                        let error = error_response(id, INVALID_REQUEST, "subscribe can't be batched", None);
+                        return Some(error).filter(|_| !is_notification(request));
+                    }
+                    respond(manager, request)
+                })
+                .collect();
+            // A batch of notifications gets no answer at all
+            Reply::Response(Some(responses).filter(|responses| !responses.is_empty()).map(Value::Array))
+        }
+        _ if request["method"] == "subscribe" => match call(manager, request) {
+            Ok((id, result)) => Reply::Subscribe(result.map(|result| json!({"jsonrpc": "2.0", "id": id, "result": result}))),
+            Err(error) => Reply::Response(Some(error).filter(|_| !is_notification(request))),
+        },
+        _ => Reply::Response(respond(manager, request)),
+    }
+}
+
+// JSON schema of a minimized window, as `list` and the notifications carry it
+fn window_schema() -> Value {
+    let string = json!({"type": "string"});
+    let optional_string = json!({"type": ["string", "null"]});
+    json!({
+        "type": "object",
+        "required": ["address", "class", "display_title", "original_title", "icon", "workspace", "minimized_at"],
+        "properties": {
+            "address": {"type": "string", "description": "Hyprland window address, e.g. 0x55d3a8f0e1b0"},
+            "class": string,
+            "display_title": {"type": "string", "description": "Title as minhypr shows it (title_format, rewrites)"},
+            "original_title": string,
+            "icon": {"type": "string", "description": "Nerd Font glyph"},
+            "icon_path": optional_string,
+            "preview_path": optional_string,
+            "workspace": {"type": "integer", "description": "Workspace it was minimized from"},
+            "monitor": optional_string,
+            "minimized_at": {"type": "integer", "description": "Unix time in milliseconds"},
+            "urgent": {"type": "boolean"},
+            "restore_at": {"type": ["integer", "null"], "description": "Snooze end, Unix time in milliseconds"},
+            "group": {"type": "array", "items": string},
+        },
+    })
+}
+
+// Result of each method
+fn result_schema(method: &str) -> Value {
+    match method {
+        "version" => json!({"type": "object", "properties": {"protocol": {"type": "integer"}, "minhypr": {"type": "string"}}}),
+        "ping" => json!({"const": "pong"}),
+        "list" => json!({"type": "array", "items": {"$ref": "#/components/schemas/Window"}}),
+        "minimize" => json!({"type": ["boolean", "null"]}),
+        "minimize_all" | "minimize_others" => json!({"type": "array", "items": {"type": "string"}}),
+        "show_desktop" => json!({"type": "boolean"}),
+        "restore_last" | "cycle" | "undo" => json!({"type": ["string", "null"]}),
+        "subscribe" => json!({"type": "object", "properties": {"protocol": {"type": "integer"}}}),
+        _ => json!({"type": "null"}),
+    }
+}
+
+/// The protocol as an OpenRPC document, for `minhypr schema`.
+pub fn schema() -> Value {
+    let methods: Vec<Value> = METHODS
+        .iter()
+        .map(|(name, address, summary)| {
+            let params = match address {
+                Address::None => json!([]),
+                _ => json!([{
+                    "name": "address",
+                    "required": *address == Address::Required,
+                    "schema": {"type": "string"},
+                }]),
+            };
+            json!({
+                "name": name,
+                "summary": summary,
+                "paramStructure": "either",
+                "params": params,
+                "result": {"name": "result", "schema": result_schema(name)},
+            })
+        })
+        .collect();
+
+    let mut errors = Map::new();
+    for (name, code, message) in [
+        ("ParseError", PARSE_ERROR, "Parse error"),
+        ("InvalidRequest", INVALID_REQUEST, "Invalid request"),
+        ("MethodNotFound", METHOD_NOT_FOUND, "Method not found"),
+        ("InvalidParams", INVALID_PARAMS, "Invalid params"),
+        ("Failed", FAILURE - 1, "The command failed"),
+        ("WindowNotFound", FAILURE - 2, "Window not found"),
+        ("HyprlandUnavailable", FAILURE - 3, "Hyprland unavailable"),
+    ] {
+        errors.insert(name.to_string(), json!({"code": code, "message": message}));
+    }
+
+    json!({
+        "openrpc": "1.2.6",
+        "info": {
+            "title": "minhypr",
+            "version": PROTOCOL_VERSION.to_string(),
+            "description": "JSON-RPC 2.0 over the `minhypr daemon` control socket, one message per line. \
+                Failures carry the exit status of the equivalent command in `data.exit_code`.",
+        },
+        "servers": [{"name": "control socket", "url": format!("unix://{}", control::socket_path().display())}],
+        "methods": methods,
+        "components": {
+            "schemas": {
+                "Window": window_schema(),
+                "WindowsChanged": {
+                    "description": "Params of the windows_changed notification sent to subscribers",
+                    "type": "object",
+                    "properties": {
+                        "count": {"type": "integer"},
+                        "windows": {"type": "array", "items": {"$ref": "#/components/schemas/Window"}},
+                    },
+                },
+            },
+            "errors": errors,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manager::tests::{manager, TempDir};
+
+    fn response(reply: Reply) -> Option<Value> {
+        match reply {
+            Reply::Response(response) => response,
+            Reply::Subscribe(_) => panic!("unexpected subscription"),
+        }
+    }
+
+    fn error_code(response: &Value) -> i64 {
+        response["error"]["code"].as_i64().unwrap()
+    }
+
+    #[test]
+    fn single_requests_and_notifications() {
+        let dir = TempDir::new();
+        let (manager, _) = manager(&dir);
+        manager.compositor.add_window("0x1", "kitty", "Terminal", 1);
+
+        let ping = response(handle(&manager, &json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}))).unwrap();
+        assert_eq!(ping, json!({"jsonrpc": "2.0", "id": 1, "result": "pong"}));
+
+        // Notifications run but get no answer
+        assert!(response(handle(&manager, &json!({"jsonrpc": "2.0", "method": "minimize", "params": ["0x1"]}))).is_none());
+        let list = response(handle(&manager, &json!({"jsonrpc": "2.0", "id": "a", "method": "list"}))).unwrap();
+        assert_eq!(list["id"], "a");
+        assert_eq!(list["result"][0]["address"], "0x1");
+
+        assert!(matches!(
+            handle(&manager, &json!({"jsonrpc": "2.0", "id": 2, "method": "subscribe"})),
+            Reply::Subscribe(Some(_))
+        ));
+    }
+
+    #[test]
+    fn error_codes() {
+        let dir = TempDir::new();
+        let (manager, _) = manager(&dir);
+        let code = |request: Value| error_code(&response(handle(&manager, &request)).unwrap());
+
+        assert_eq!(code(json!({"jsonrpc": "1.0", "id": 1, "method": "ping"})), INVALID_REQUEST);
+        assert_eq!(code(json!({"jsonrpc": "2.0", "id": 1, "method": 5})), INVALID_REQUEST);
+        assert_eq!(code(json!({"jsonrpc": "2.0", "id": {}, "method": "ping"})), INVALID_REQUEST);
+        assert_eq!(code(json!({"jsonrpc": "2.0", "id": 1, "method": "nope"})), METHOD_NOT_FOUND);
+        assert_eq!(code(json!({"jsonrpc": "2.0", "id": 1, "method": "restore"})), INVALID_PARAMS);
+        assert_eq!(code(json!({"jsonrpc": "2.0", "id": 1, "method": "ping", "params": {"address": "0x1"}})), INVALID_PARAMS);
+        assert_eq!(code(json!({"jsonrpc": "2.0", "id": 1, "method": "close", "params": 3})), INVALID_PARAMS);
+
+        let missing = response(handle(&manager, &json!({"jsonrpc": "2.0", "id": 1, "method": "restore", "params": ["0x9"]}))).unwrap();
+        assert_eq!(error_code(&missing), FAILURE - 2);
+        assert_eq!(missing["error"]["data"]["exit_code"], 2);
+
+        // Errors of notifications are dropped too
+        assert!(response(handle(&manager, &json!({"jsonrpc": "2.0", "method": "nope"}))).is_none());
+    }
+
+    #[test]
+    fn batches() {
+        let dir = TempDir::new();
+        let (manager, _) = manager(&dir);
+
+        let batch = json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "ping"},
+            {"jsonrpc": "2.0", "method": "refresh"},
+            {"jsonrpc": "2.0", "id": 2, "method": "nope"},
+            {"jsonrpc": "2.0", "id": 3, "method": "subscribe"},
+        ]);
+        let responses = response(handle(&manager, &batch)).unwrap();
+        let responses = responses.as_array().unwrap();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["result"], "pong");
+        assert_eq!(error_code(&responses[1]), METHOD_NOT_FOUND);
+        assert_eq!(error_code(&responses[2]), INVALID_REQUEST);
+
+        // Only notifications: nothing to send back
+        assert!(response(handle(&manager, &json!([{"jsonrpc": "2.0", "method": "ping"}]))).is_none());
+
+        let empty = response(handle(&manager, &json!([]))).unwrap();
+        assert_eq!(error_code(&empty), INVALID_REQUEST);
+    }
+
+    #[test]
+    fn invalid_batch_entries_each_get_an_error() {
+        let dir = TempDir::new();
+        let (manager, _) = manager(&dir);
+
+        for batch in [json!([1]), json!([1, "two", null]), json!([{"command": "ping"}, {"jsonrpc": "2.0", "id": 7, "method": "ping"}])] {
+            assert!(is_rpc(&batch), "{} is a batch", batch);
+        }
+
+        let responses = response(handle(&manager, &json!([1, "two", null]))).unwrap();
+        assert_eq!(responses.as_array().unwrap().len(), 3);
+        for response in responses.as_array().unwrap() {
+            assert_eq!(response["id"], Value::Null);
+            assert_eq!(error_code(response), INVALID_REQUEST);
+        }
+
+        // Mixed: the plain-protocol entry is invalid, the rest still runs
+        let responses = response(handle(&manager, &json!([{"command": "ping"}, {"jsonrpc": "2.0", "id": 7, "method": "ping"}]))).unwrap();
+        assert_eq!(error_code(&responses[0]), INVALID_REQUEST);
+        assert_eq!(responses[1]["result"], "pong");
+    }
+
+    // Whether a value fits the subset of JSON schema `result_schema` uses
+    fn fits(value: &Value, schema: &Value) -> bool {
+        if schema.get("$ref").is_some() {
+            return value.is_object();
+        }
+        if let Some(constant) = schema.get("const") {
+            return value == constant;
+        }
+        let type_name = match value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        };
+        let typed = match &schema["type"] {
+            Value::String(t) => t == type_name,
+            Value::Array(types) => types.iter().any(|t| t == type_name),
+            _ => false,
+        };
+        typed
+            && match (value, schema.get("items")) {
+                (Value::Array(items), Some(item_schema)) => items.iter().all(|item| fits(item, item_schema)),
+                _ => true,
+            }
+    }
+
+    #[test]
+    fn results_match_the_schema() {
+        for (method, _, _) in METHODS {
+            let dir = TempDir::new();
+            let (manager, _) = manager(&dir);
+            manager.compositor.add_window("0x3", "foot", "Minimized", 1);
+            manager.minimize_address("0x3").unwrap();
+            manager.compositor.add_window("0x1", "kitty", "Terminal", 1);
+            manager.compositor.add_window("0x2", "firefox", "Browser", 1);
+
+            let params = match method {
+                "minimize" | "toggle" => json!(["0x1"]),
+                "restore" | "close" => json!(["0x3"]),
+                _ => Value::Null,
+            };
+            let request = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+            let response = match handle(&manager, &request) {
+                Reply::Response(response) | Reply::Subscribe(response) => response.unwrap(),
+            };
+            let result = response.get("result").unwrap_or_else(|| panic!("{} failed: {}", method, response));
+            assert!(fits(result, &result_schema(method)), "{} returned {} for {}", method, result, result_schema(method));
+        }
+    }
+
+    #[test]
+    fn plain_requests_are_not_rpc() {
+        assert!(!is_rpc(&json!({"command": "list"})));
+        assert!(is_rpc(&json!({"jsonrpc": "2.0", "method": "list"})));
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    env, fs,
+    io::{self, Result},
+    path::{Path, PathBuf},
+};
+
+// Modifier names Hyprland accepts in binds
+const MODIFIERS: [&str; 12] = ["SHIFT", "CAPS", "CTRL", "CONTROL", "ALT", "MOD2", "MOD3", "SUPER", "WIN", "LOGO", "MOD4", "MOD5"];
+
+fn home() -> String {
+    env::var("HOME").unwrap_or_else(|_| String::from("/tmp"))
+}
+
+// Modifiers are separated by spaces or underscores ("SUPER SHIFT", "SUPER_SHIFT")
+fn check_modifiers(modifiers: &str) -> Result<String> {
+    let names: Vec<String> = modifiers.split([' ', '_', '+']).filter(|name| !name.is_empty()).map(str::to_uppercase).collect();
+    if names.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "empty modifier"));
+    }
+    if let Some(name) = names.iter().find(|name| !MODIFIERS.contains(&name.as_str())) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unknown modifier {} (use {})", name, MODIFIERS.join(", ")),
+        ));
+    }
+    Ok(names.join(" "))
+}
+
+/// Hyprland's config directory (`~/.config/hypr`).
+pub fn hypr_config_dir() -> PathBuf {
+    let config_home = match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(home()).join(".config"),
+    };
+    config_home.join("hypr")
+}
+
+/// Where `minhypr setup hyprland --write` puts the binds.
+pub fn hyprland_config_path() -> PathBuf {
+    hypr_config_dir().join("conf.d/minhypr.conf")
+}
+
+/// Where `minhypr setup submap --write` puts the submap.
+pub fn submap_config_path() -> PathBuf {
+    hypr_config_dir().join("conf.d/minhypr-submap.conf")
+}
+
+// `path` as written in the configs, under ~
+fn display_path(path: &Path) -> String {
+    path.display().to_string().replacen(&home(), "~", 1)
+}
+
+// The Rofi menu script when `minhypr setup-rofi` generated it
+fn menu_command() -> &'static str {
+    let launch_menu = format!("{}/.config/minhypr/launch-menu.sh", home());
+    if Path::new(&launch_menu).exists() {
+        "~/.config/minhypr/launch-menu.sh"
+    } else {
+        "minhypr restore"
+    }
+}
+
+/// Hyprland config with the minhypr binds, using `modifier` for the main ones
+/// and `modifier extra` for the others, plus the daemon and a Waybar module
+/// (commented out, it belongs in Waybar's config).
+pub fn hyprland_config(modifier: &str, extra: &str) -> Result<String> {
+    let modifier = check_modifiers(modifier)?;
+    let extra = check_modifiers(extra)?;
+    if modifier == extra {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "the extra modifier must differ from the main one"));
+    }
+
+    let menu = menu_command();
+
+    Ok(format!(
+        r#"# minhypr binds, generated by `minhypr setup hyprland`; run it again to regenerate.
+# Load them from hyprland.conf with: source = {path}
+
+$minhypr_mod = {modifier}
+$minhypr_extra = {extra}
+
+exec-once = minhypr daemon
+
+bind = $minhypr_mod, M, exec, minhypr minimize                    # Minimize the active window
+bind = $minhypr_mod $minhypr_extra, M, exec, {menu}    # Menu to restore windows
+bind = $minhypr_mod $minhypr_extra, R, exec, minhypr restore-all  # Restore every window
+bind = $minhypr_mod, N, exec, minhypr cycle                       # Restore the last window, minimize the active one
+bind = $minhypr_mod $minhypr_extra, T, exec, minhypr toggle       # Minimize or restore the active window
+
+# Waybar module: add this to Waybar's config and "custom/minhypr" to a modules list
+#
+# "custom/minhypr": {{
+#     "exec": "minhypr watch",
+#     "return-type": "json",
+#     "format": "{{}}",
+#     "on-click": "{menu}",
+#     "on-click-right": "minhypr restore-all"
+# }}
+"#,
+        path = display_path(&hyprland_config_path()),
+    ))
+}
+
+/// A Hyprland submap ("minimize mode") entered with `modifier`+`key`, where
+/// single keys restore the windows by recency (1 to 9), restore them all,
+/// cycle, minimize or open the menu, and leave the mode.
+pub fn submap_config(modifier: &str, key: &str) -> Result<String> {
+    let modifier = check_modifiers(modifier)?;
+    if key.trim().is_empty() || key.contains(',') {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid key: {:?}", key)));
+    }
+
+    // Each key runs its command, then a second bind on it leaves the mode
+    let mut binds = String::new();
+    let mut bind = |key: &str, command: &str, description: &str| {
+        binds.push_str(&format!("bind = , {}, exec, {}  # {}\nbind = , {}, submap, reset\n", key, command, description, key));
+    };
+    for slot in 1..=9 {
+        bind(&slot.to_string(), &format!("minhypr restore --index {}", slot), &format!("Restore window {} of `minhypr list --numbered`", slot));
+    }
+    bind("A", "minhypr restore-all", "Restore every window");
+    bind("N", "minhypr cycle", "Restore the last window, minimize the active one");
+    bind("M", "minhypr minimize", "Minimize the active window");
+    bind("T", "minhypr toggle", "Minimize or restore the active window");
+    bind("space", menu_command(), "Menu to restore windows");
+
+    Ok(format!(
+        r#"# minhypr minimize mode, generated by `minhypr setup submap`; run it again to regenerate.
+# Load it from hyprland.conf with: source = {path}
+#
+# {modifier} + {key} enters the mode. Then 1-9 restore the windows by recency
+# (1 is the last one minimized), A restores all, N cycles, M minimizes, T
+# toggles and Space opens the menu; each leaves the mode, as does Escape.
+
+bind = {modifier}, {key}, submap, minhypr
+
+submap = minhypr
+{binds}bind = , escape, submap, reset
+submap = reset
+"#,
+        path = display_path(&submap_config_path()),
+    ))
+}
+
+/// Write a generated Hyprland config to `path` (e.g.
+/// [`hyprland_config_path`]), replacing an earlier one.
+pub fn write_hyprland_config(path: &Path, content: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, content)?;
+    info!("Hyprland config written to {}", path.display());
+
+    // Hyprland only reads conf.d files that hyprland.conf sources
+    let sourced = fs::read_to_string(hypr_config_dir().join("hyprland.conf"))
+        .ok()
+        .is_some_and(|content| content.lines().any(|line| line.trim_start().starts_with("source") && line.contains("conf.d")));
+    if !sourced {
+        info!("Add this line to hyprland.conf to load it: source = {}", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,341 @@
+use std::{
+    collections::HashMap,
+    env,
+    fs::{self, DirBuilder, File, OpenOptions},
+    io::{ErrorKind, Result, Write},
+    os::unix::fs::{DirBuilderExt, MetadataExt},
+    path::Path,
+    sync::{Condvar, Mutex},
+    thread::{self, ThreadId},
+};
+
+use crate::{hyprland, window::MinimizedWindow};
+
+// Per-user base directory: $XDG_RUNTIME_DIR is a user-only tmpfs
+pub(crate) fn state_root() -> String {
+    match env::var("XDG_RUNTIME_DIR") {
+        Ok(dir) => format!("{}/minhypr", dir),
+        Err(_) => {
+            let uid = fs::metadata("/proc/self").map(|m| m.uid()).unwrap_or(0);
+            format!("/tmp/minhypr-{}", uid)
+        }
+    }
+}
+
+// Rename, falling back to copy + remove across filesystems (/tmp -> /run)
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+// Write to a temporary file and rename it over the target, so readers never
+// see a partially written file
+fn write_atomic(path: &str, content: &str) -> Result<()> {
+    let tmp_path = format!("{}.tmp", path);
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)
+}
+
+// The lock is re-entrant per thread, so nested operations (e.g. restore
+// validating the cache) don't deadlock on the file lock they already hold
+#[derive(Default)]
+struct LockState {
+    owner: Option<ThreadId>,
+    depth: usize,
+    file: Option<File>,
+}
+
+/// Exclusive access to the state, released when dropped.
+pub struct StateLock<'a> {
+    store: &'a StateStore,
+}
+
+impl Drop for StateLock<'_> {
+    fn drop(&mut self) {
+        let mut state = self.store.lock_state.lock().unwrap_or_else(|e| e.into_inner());
+        state.depth -= 1;
+
+        if state.depth == 0 {
+            // Closing the file releases the file lock
+            state.file = None;
+            state.owner = None;
+            self.store.lock_released.notify_one();
+        }
+    }
+}
+
+/// On-disk storage for the list of minimized windows and their previews.
+pub struct StateStore {
+    cache_dir: String,
+    cache_file: String,
+    groups_file: String,
+    last_restore_file: String,
+    preview_dir: String,
+    lock_state: Mutex<LockState>,
+    lock_released: Condvar,
+}
+
+impl Default for StateStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateStore {
+    /// Store for the Hyprland instance minhypr is running under.
+    pub fn new() -> Self {
+        let signature = hyprland::instance_signature().unwrap_or_else(|| String::from("default"));
+        Self::for_instance(&signature)
+    }
+
+    /// Store namespaced by user (`$XDG_RUNTIME_DIR`) and Hyprland instance.
+    pub fn for_instance(signature: &str) -> Self {
+        let root = format!("{}/{}", state_root(), signature);
+        Self::with_dirs(format!("{}/state", root), format!("{}/previews", root))
+    }
+
+    /// Store kept under an arbitrary directory, e.g. a temporary one in tests.
+    pub fn in_dir(root: &Path) -> Self {
+        let root = root.display();
+        Self::with_dirs(format!("{}/state", root), format!("{}/previews", root))
+    }
+
+    // Shared location used by earlier versions, migrated on init
+    fn legacy() -> Self {
+        Self::with_dirs(String::from("/tmp/minhypr-state"), String::from("/tmp/minhypr-previews"))
+    }
+
+    fn with_dirs(cache_dir: String, preview_dir: String) -> Self {
+        let cache_file = format!("{}/windows.json", cache_dir);
+        let groups_file = format!("{}/groups.json", cache_dir);
+        let last_restore_file = format!("{}/last_restore.json", cache_dir);
+
+        StateStore {
+            cache_dir,
+            cache_file,
+            groups_file,
+            last_restore_file,
+            preview_dir,
+            lock_state: Mutex::new(LockState::default()),
+            lock_released: Condvar::new(),
+        }
+    }
+
+    pub fn cache_dir(&self) -> &str {
+        &self.cache_dir
+    }
+
+    pub fn cache_file(&self) -> &str {
+        &self.cache_file
+    }
+
+    pub fn preview_dir(&self) -> &str {
+        &self.preview_dir
+    }
+
+    /// Create the cache and preview directories (user-only) and an empty cache
+    /// file, migrating state left in the old shared /tmp location.
+    pub fn init(&self) -> Result<()> {
+        let mut builder = DirBuilder::new();
+        builder.recursive(true).mode(0o700);
+        builder.create(&self.cache_dir)?;
+        builder.create(&self.preview_dir)?;
+
+        let _lock = self.lock()?;
+
+        let legacy = StateStore::legacy();
+        if legacy.owned_by_current_user() {
+            self.migrate_from(&legacy)?;
+        }
+
+        if !Path::new(&self.cache_file).exists() {
+            self.save(&[])?;
+        }
+
+        Ok(())
+    }
+
+    fn owned_by_current_user(&self) -> bool {
+        match (fs::metadata(&self.cache_dir), fs::metadata("/proc/self")) {
+            (Ok(dir), Ok(me)) => dir.uid() == me.uid(),
+            _ => false,
+        }
+    }
+
+    /// Move the state of another store (old location or previous Hyprland
+    /// instance) into this one, unless this one already tracks windows.
+    pub fn migrate_from(&self, other: &StateStore) -> Result<bool> {
+        if other.cache_file == self.cache_file || !self.load()?.is_empty() {
+            return Ok(false);
+        }
+
+        let mut windows = other.load()?;
+        if windows.is_empty() {
+            return Ok(false);
+        }
+
+        // Previews keep their file names, only the directory changes
+        if let Ok(entries) = fs::read_dir(&other.preview_dir) {
+            for entry in entries.filter_map(|e| e.ok()) {
+                let target = Path::new(&self.preview_dir).join(entry.file_name());
+                move_file(&entry.path(), &target)?;
+            }
+        }
+
+        for window in &mut windows {
+            if let Some(preview) = &window.preview_path {
+                window.preview_path = Some(preview.replace(&other.preview_dir, &self.preview_dir));
+            }
+        }
+
+        self.save(&windows)?;
+        write_atomic(&self.groups_file, &serde_json::to_string(&other.load_groups()?)?)?;
+
+        fs::remove_file(&other.cache_file).ok();
+        fs::remove_file(&other.groups_file).ok();
+
+        Ok(true)
+    }
+
+    /// Lock the state against other minhypr processes for a read-modify-write.
+    pub fn lock(&self) -> Result<StateLock<'_>> {
+        let me = thread::current().id();
+        let mut state = self.lock_state.lock().unwrap_or_else(|e| e.into_inner());
+
+        while state.owner.is_some_and(|owner| owner != me) {
+            state = self.lock_released.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+
+        if state.depth == 0 {
+            let file = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(format!("{}/lock", self.cache_dir))?;
+            file.lock()?;
+
+            state.file = Some(file);
+            state.owner = Some(me);
+        }
+        state.depth += 1;
+
+        Ok(StateLock { store: self })
+    }
+
+    fn backup_file(&self) -> String {
+        format!("{}.bak", self.cache_file)
+    }
+
+    /// Read the cached windows as stored, without checking them against Hyprland.
+    /// A corrupted cache is restored from the backup of the previous write.
+    pub fn load(&self) -> Result<Vec<MinimizedWindow>> {
+        if !Path::new(&self.cache_file).exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.cache_file)?;
+        if let Ok(windows) = serde_json::from_str::<Vec<MinimizedWindow>>(&content) {
+            return Ok(windows);
+        }
+
+        let backup = fs::read_to_string(self.backup_file()).unwrap_or_default();
+        match serde_json::from_str::<Vec<MinimizedWindow>>(&backup) {
+            Ok(windows) => {
+                warn!("Cache file {} is corrupted, restored from backup", self.cache_file);
+                write_atomic(&self.cache_file, &backup)?;
+                Ok(windows)
+            }
+            Err(_) => {
+                // Keep the broken file for inspection and start over
+                let corrupt_file = format!("{}.corrupt", self.cache_file);
+                warn!(
+                    "Cache file {} is corrupted and no valid backup exists, moved to {}",
+                    self.cache_file, corrupt_file
+                );
+                fs::rename(&self.cache_file, corrupt_file)?;
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    pub fn save(&self, windows: &[MinimizedWindow]) -> Result<()> {
+        let json = serde_json::to_string(windows)?;
+
+        // Keep the last good version around for recovery
+        if Path::new(&self.cache_file).exists() {
+            fs::copy(&self.cache_file, self.backup_file())?;
+        }
+
+        write_atomic(&self.cache_file, &json)
+    }
+
+    // Named sets of window addresses (e.g. the show-desktop set), kept apart
+    // from the minimized list so individual restores don't affect them
+    fn load_groups(&self) -> Result<HashMap<String, Vec<String>>> {
+        if !Path::new(&self.groups_file).exists() {
+            return Ok(HashMap::new());
+        }
+
+        let content = fs::read_to_string(&self.groups_file)?;
+        Ok(serde_json::from_str(&content).unwrap_or_default())
+    }
+
+    /// Names of the stored groups, sorted.
+    pub fn group_names(&self) -> Result<Vec<String>> {
+        let mut names: Vec<String> = self.load_groups()?.into_keys().collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Addresses stored under a group name.
+    pub fn load_group(&self, name: &str) -> Result<Vec<String>> {
+        Ok(self.load_groups()?.remove(name).unwrap_or_default())
+    }
+
+    /// Replace a group; an empty list removes it.
+    pub fn save_group(&self, name: &str, addresses: &[String]) -> Result<()> {
+        let mut groups = self.load_groups()?;
+
+        if addresses.is_empty() {
+            groups.remove(name);
+        } else {
+            groups.insert(name.to_string(), addresses.to_vec());
+        }
+
+        write_atomic(&self.groups_file, &serde_json::to_string(&groups)?)
+    }
+
+    /// The most recently restored window, as it was stored while minimized.
+    pub fn load_last_restore(&self) -> Result<Option<MinimizedWindow>> {
+        if !Path::new(&self.last_restore_file).exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&self.last_restore_file)?;
+        Ok(serde_json::from_str(&content).ok())
+    }
+
+    /// Forget everything: the minimized list, the groups and the last restore.
+    pub fn clear(&self) -> Result<()> {
+        self.save(&[])?;
+        fs::remove_file(self.backup_file()).ok();
+        fs::remove_file(&self.groups_file).ok();
+        self.save_last_restore(None)
+    }
+
+    /// Remember a restored window for undo, or forget it with `None`.
+    pub fn save_last_restore(&self, window: Option<&MinimizedWindow>) -> Result<()> {
+        match window {
+            Some(window) => write_atomic(&self.last_restore_file, &serde_json::to_string(window)?),
+            None => match fs::remove_file(&self.last_restore_file) {
+                Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            },
+        }
+    }
+}
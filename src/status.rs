@@ -0,0 +1,73 @@
+use std::{collections::BTreeMap, io::Result};
+
+use crate::{events::watch_changes, manager::Manager, window::MinimizedWindow};
+
+/// Status line for ironbar's script module.
+pub const IRONBAR_FORMAT: &str = "󰘸 {count}";
+
+/// Number of minimized windows per class, sorted by class.
+pub fn class_counts(windows: &[MinimizedWindow]) -> BTreeMap<&str, usize> {
+    let mut counts = BTreeMap::new();
+    for window in windows {
+        *counts.entry(window.class.as_str()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Render a status template with `{count}`, `{classes}` ("firefox 2, kitty 1")
+/// and `{icons}` (one glyph per window) placeholders.
+pub fn format_status(template: &str, windows: &[MinimizedWindow]) -> String {
+    let classes: Vec<String> = class_counts(windows)
+        .into_iter()
+        .map(|(class, count)| format!("{} {}", class, count))
+        .collect();
+    let icons: Vec<&str> = windows.iter().map(|w| w.icon.as_str()).collect();
+
+    template
+        .replace("{count}", &windows.len().to_string())
+        .replace("{classes}", &classes.join(", "))
+        .replace("{icons}", &icons.join(" "))
+}
+
+// Tag names are used as-is in the yambar config, keep them simple
+fn tag_name(class: &str) -> String {
+    class
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
+
+/// A yambar script module transaction: `count`, `has_windows`, `classes` and
+/// one `count_<class>` tag per class, terminated by an empty line.
+pub fn yambar_tags(windows: &[MinimizedWindow]) -> String {
+    let mut lines = vec![
+        format!("count|int|{}", windows.len()),
+        format!("has_windows|bool|{}", !windows.is_empty()),
+        format!("classes|string|{}", format_status("{classes}", windows)),
+    ];
+    for (class, count) in class_counts(windows) {
+        lines.push(format!("count_{}|int|{}", tag_name(class), count));
+    }
+
+    // println! adds the line ending the transaction
+    format!("{}\n", lines.join("\n"))
+}
+
+/// Print `render`'s status once, or keep running with `watch` and print it
+/// again whenever it changes.
+pub fn print_status(manager: &Manager, watch: bool, render: impl Fn(&[MinimizedWindow]) -> String) -> Result<()> {
+    if !watch {
+        println!("{}", render(&manager.windows()?));
+        return Ok(());
+    }
+
+    let mut last = String::new();
+    watch_changes(manager, || {
+        let status = render(&manager.windows()?);
+        if status != last {
+            println!("{}", status);
+            last = status;
+        }
+        Ok(())
+    })
+}
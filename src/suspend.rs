@@ -0,0 +1,24 @@
+use std::process::{Command, Stdio};
+
+// Signal a process with kill(1); failures (process gone, not ours) are ignored
+fn signal(pid: i32, signal: &str) -> bool {
+    if pid <= 0 {
+        return false;
+    }
+
+    Command::new("kill")
+        .args(["-s", signal, &pid.to_string()])
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Stop a process (SIGSTOP) so it uses no CPU while its windows are hidden.
+pub fn stop(pid: i32) -> bool {
+    signal(pid, "STOP")
+}
+
+/// Let a stopped process run again (SIGCONT).
+pub fn resume(pid: i32) -> bool {
+    signal(pid, "CONT")
+}
@@ -0,0 +1,118 @@
+use std::{
+    env, fs,
+    io::{self, Result},
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use crate::config::RofiTheme;
+
+/// Colors of the Rofi menus, as `#rrggbb`.
+#[derive(Clone, Debug)]
+pub struct Palette {
+    pub background: String,
+    pub background_alt: String,
+    pub foreground: String,
+    pub selected: String,
+    pub active: String,
+    pub urgent: String,
+    pub border: String,
+}
+
+const NORD: [&str; 7] = ["#2E3440", "#3B4252", "#ECEFF4", "#88C0D0", "#A3BE8C", "#BF616A", "#4C566A"];
+
+impl Palette {
+    fn preset(colors: [&str; 7]) -> Self {
+        let [background, background_alt, foreground, selected, active, urgent, border] = colors.map(String::from);
+        Palette { background, background_alt, foreground, selected, active, urgent, border }
+    }
+
+    /// The `* { ... }` block of a .rasi theme defining the colors as variables.
+    pub fn rasi_variables(&self) -> String {
+        format!(
+            "* {{\n    background:     {};\n    background-alt: {};\n    foreground:     {};\n    selected:       {};\n    active:         {};\n    urgent:         {};\n    border:         {};\n}}\n",
+            self.background, self.background_alt, self.foreground, self.selected, self.active, self.urgent, self.border
+        )
+    }
+}
+
+fn cache_home() -> PathBuf {
+    env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env::var("HOME").unwrap_or_default()).join(".cache"))
+}
+
+fn read_json(path: &Path, fix: &str) -> Result<Value> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not read {}: {} ({})", path.display(), e, fix)))?;
+    serde_json::from_str(&content).map_err(io::Error::other)
+}
+
+// pywal's colors.json: `special` background and foreground plus color0-15
+fn wal_palette() -> Result<Palette> {
+    let path = cache_home().join("wal/colors.json");
+    let colors = read_json(&path, "run wal first")?;
+    let color = |section: &str, name: &str| {
+        colors[section][name]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| io::Error::other(format!("{} has no {}.{}", path.display(), section, name)))
+    };
+
+    Ok(Palette {
+        background: color("special", "background")?,
+        background_alt: color("colors", "color0")?,
+        foreground: color("special", "foreground")?,
+        selected: color("colors", "color4")?,
+        active: color("colors", "color2")?,
+        urgent: color("colors", "color1")?,
+        border: color("colors", "color8")?,
+    })
+}
+
+// `matugen --json hex` output; older versions group the colors by scheme
+// (colors.dark.primary), newer ones by color (colors.primary.dark)
+fn matugen_palette() -> Result<Palette> {
+    let path = cache_home().join("matugen/colors.json");
+    let colors = read_json(&path, "save `matugen image <wallpaper> --json hex` there")?;
+    let color = |name: &str| {
+        [&colors["colors"]["dark"][name], &colors["colors"][name]["dark"]]
+            .into_iter()
+            .find_map(|value| value.as_str())
+            .map(String::from)
+            .ok_or_else(|| io::Error::other(format!("{} has no dark {} color", path.display(), name)))
+    };
+
+    Ok(Palette {
+        background: color("surface")?,
+        background_alt: color("surface_container_high")?,
+        foreground: color("on_surface")?,
+        selected: color("primary")?,
+        active: color("tertiary")?,
+        urgent: color("error")?,
+        border: color("outline_variant")?,
+    })
+}
+
+/// The colors of a theme; `wal` and `matugen` are read from their generated
+/// color files.
+pub fn palette(theme: RofiTheme) -> Result<Palette> {
+    Ok(match theme {
+        RofiTheme::Nord => Palette::preset(NORD),
+        RofiTheme::Catppuccin => Palette::preset(["#1E1E2E", "#313244", "#CDD6F4", "#89B4FA", "#A6E3A1", "#F38BA8", "#45475A"]),
+        RofiTheme::Gruvbox => Palette::preset(["#282828", "#3C3836", "#EBDBB2", "#FABD2F", "#B8BB26", "#FB4934", "#504945"]),
+        RofiTheme::Dracula => Palette::preset(["#282A36", "#44475A", "#F8F8F2", "#BD93F9", "#50FA7B", "#FF5555", "#6272A4"]),
+        RofiTheme::Wal => wal_palette()?,
+        RofiTheme::Matugen => matugen_palette()?,
+    })
+}
+
+/// The colors of a theme, falling back to Nord when the color file of `wal`
+/// or `matugen` can't be read, so the menus always open.
+pub fn palette_or_default(theme: RofiTheme) -> Palette {
+    palette(theme).unwrap_or_else(|e| {
+        warn!("{}", e);
+        Palette::preset(NORD)
+    })
+}
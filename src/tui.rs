@@ -0,0 +1,226 @@
+use std::{
+    io::{self, Read, Result, Write},
+    process::{Command, Stdio},
+};
+
+use crate::{
+    manager::Manager,
+    window::{rank_matches, MinimizedWindow},
+};
+
+enum Key {
+    Char(char),
+    Backspace,
+    Enter,
+    Escape,
+    Up,
+    Down,
+    CtrlA,
+    CtrlD,
+    Delete,
+}
+
+// Run stty on the controlling terminal
+fn stty(args: &[&str]) -> Result<String> {
+    let output = Command::new("stty").args(args).stdin(Stdio::inherit()).output()?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// Raw mode plus the alternate screen, both undone when dropped (also on errors)
+struct RawTerminal {
+    saved: String,
+}
+
+impl RawTerminal {
+    fn enter() -> Result<Self> {
+        let saved = stty(&["-g"])?;
+        // Reads time out after 100ms so a lone Esc can be told from an escape sequence
+        stty(&["raw", "-echo", "min", "0", "time", "1"])?;
+        print!("\x1b[?1049h\x1b[?25l");
+        io::stdout().flush()?;
+        Ok(RawTerminal { saved })
+    }
+
+    fn size(&self) -> (usize, usize) {
+        let size = stty(&["size"]).unwrap_or_default();
+        let mut parts = size.split_whitespace().filter_map(|n| n.parse().ok());
+        match (parts.next(), parts.next()) {
+            (Some(rows), Some(cols)) if rows > 0 && cols > 0 => (rows, cols),
+            _ => (24, 80),
+        }
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        io::stdout().flush().ok();
+        stty(&[&self.saved]).ok();
+    }
+}
+
+fn read_byte(stdin: &mut io::Stdin) -> Result<Option<u8>> {
+    let mut byte = [0u8];
+    match stdin.read(&mut byte)? {
+        0 => Ok(None),
+        _ => Ok(Some(byte[0])),
+    }
+}
+
+// Block until a key is pressed
+fn read_key(stdin: &mut io::Stdin) -> Result<Key> {
+    let first = loop {
+        if let Some(byte) = read_byte(stdin)? {
+            break byte;
+        }
+    };
+
+    let key = match first {
+        b'\r' | b'\n' => Key::Enter,
+        0x7f | 0x08 => Key::Backspace,
+        0x01 => Key::CtrlA,
+        0x04 => Key::CtrlD,
+        // Ctrl-N, Ctrl-P and Ctrl-C
+        0x0e => Key::Down,
+        0x10 => Key::Up,
+        0x03 => Key::Escape,
+        0x1b => match (read_byte(stdin)?, read_byte(stdin)?) {
+            (Some(b'['), Some(b'A')) => Key::Up,
+            (Some(b'['), Some(b'B')) => Key::Down,
+            (Some(b'['), Some(b'3')) => {
+                read_byte(stdin)?; // trailing '~'
+                Key::Delete
+            }
+            (None, _) => Key::Escape,
+            _ => Key::Char('\0'),
+        },
+        byte if byte < 0x80 => Key::Char(byte as char),
+        byte => {
+            // Rest of a UTF-8 sequence
+            let len = match byte {
+                0xf0.. => 4,
+                0xe0.. => 3,
+                _ => 2,
+            };
+            let mut bytes = vec![byte];
+            for _ in 1..len {
+                bytes.extend(read_byte(stdin)?);
+            }
+            Key::Char(String::from_utf8_lossy(&bytes).chars().next().unwrap_or('\0'))
+        }
+    };
+
+    Ok(key)
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return format!("{:width$}", text, width = width);
+    }
+    let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn render(terminal: &RawTerminal, query: &str, windows: &[&MinimizedWindow], selected: usize, total: usize) -> Result<()> {
+    let (rows, cols) = terminal.size();
+    let mut out = String::from("\x1b[H\x1b[2J");
+
+    out.push_str(&format!("\x1b[1m> \x1b[0m{}\r\n", query));
+    out.push_str(&format!(
+        "\x1b[2m{}\x1b[0m\r\n",
+        truncate(&format!("  {:<20} {:>4}  {}", "CLASS", "WS", "TITLE"), cols)
+    ));
+
+    // Keep the selection visible when the list is taller than the screen
+    let visible = rows.saturating_sub(4).max(1);
+    let offset = selected.saturating_sub(visible - 1);
+
+    for (i, window) in windows.iter().enumerate().skip(offset).take(visible) {
+        let line = format!(
+            "{} {} {:>4}  {}",
+            window.icon,
+            truncate(&window.class, 20),
+            window.workspace,
+            window.original_title
+        );
+        let line = truncate(&line, cols);
+        if i == selected {
+            out.push_str(&format!("\x1b[7m{}\x1b[0m\r\n", line));
+        } else {
+            out.push_str(&format!("{}\r\n", line));
+        }
+    }
+
+    out.push_str(&format!(
+        "\x1b[{};1H\x1b[2m{}\x1b[0m",
+        rows,
+        truncate(
+            &format!(
+                "{}/{}  enter: restore  ctrl-a: restore all  ctrl-d/del: close  esc: quit",
+                windows.len(),
+                total
+            ),
+            cols
+        )
+    ));
+
+    print!("{}", out);
+    io::stdout().flush()
+}
+
+/// Interactive terminal picker: type to filter, Enter restores the selected
+/// window, Ctrl-A restores all, Ctrl-D closes the selected window.
+pub fn run_tui(manager: &Manager) -> Result<()> {
+    let mut windows = manager.windows()?;
+    if windows.is_empty() {
+        info!("No minimized windows");
+        return Ok(());
+    }
+
+    let terminal = RawTerminal::enter()?;
+    let mut stdin = io::stdin();
+    let mut query = String::new();
+    let mut selected = 0;
+
+    loop {
+        let filtered = rank_matches(manager.menu_order(&windows), &query);
+        selected = selected.min(filtered.len().saturating_sub(1));
+        render(&terminal, &query, &filtered, selected, windows.len())?;
+        let selected_address = filtered.get(selected).map(|w| w.address.clone());
+
+        match read_key(&mut stdin)? {
+            Key::Escape => return Ok(()),
+            Key::Enter => {
+                if let Some(address) = selected_address {
+                    drop(terminal);
+                    return manager.restore(&address);
+                }
+            }
+            Key::CtrlA => {
+                drop(terminal);
+                return manager.restore_all();
+            }
+            Key::CtrlD | Key::Delete => {
+                if let Some(address) = selected_address {
+                    manager.close_minimized(&address)?;
+                    windows.retain(|w| w.address != address);
+                    if windows.is_empty() {
+                        return Ok(());
+                    }
+                }
+            }
+            Key::Up => selected = selected.saturating_sub(1),
+            Key::Down => selected += 1,
+            Key::Backspace => {
+                query.pop();
+                selected = 0;
+            }
+            Key::Char(c) if !c.is_control() => {
+                query.push(c);
+                selected = 0;
+            }
+            Key::Char(_) => {}
+        }
+    }
+}
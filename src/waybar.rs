@@ -0,0 +1,109 @@
+use std::{io::Result, process::Command};
+
+use serde_json::{json, Value};
+
+use crate::{
+    config::Config,
+    events::watch_changes,
+    manager::Manager,
+    status::format_status,
+    window::{windows_by_recency, MinimizedWindow},
+};
+
+/// Waybar custom module JSON for the given minimized windows.
+pub fn status_json(config: &Config, windows: &[MinimizedWindow], tooltip_format: &str) -> String {
+    let count = windows.len();
+
+    let status = if count > 0 {
+        // Header with the count, then one line per window
+        let mut tooltip = format!("{} minimized windows", count);
+        for window in windows {
+            tooltip.push('\n');
+            tooltip.push_str(&config.format_window(tooltip_format, window));
+        }
+
+        // A window asking for attention adds the "urgent" class for styling
+        let class = &config.waybar.class;
+        let class = if windows.iter().any(|w| w.urgent) { json!([class, "urgent"]) } else { json!(class) };
+
+        json!({
+            "text": format_status(&config.waybar.format, windows),
+            "class": class,
+            "tooltip": tooltip,
+        })
+    } else {
+        json!({
+            "text": format_status(&config.waybar.format_empty, windows),
+            "class": config.waybar.class_when_empty,
+            "tooltip": "No minimized windows",
+        })
+    };
+
+    status.to_string()
+}
+
+// One window's entry: its icon, named in the tooltip, numbered like `restore --index`
+fn window_entry(config: &Config, window: &MinimizedWindow, tooltip_format: &str, index: usize) -> Value {
+    let class = if window.urgent { json!(["window", "urgent"]) } else { json!("window") };
+    json!({
+        "text": window.icon,
+        "alt": window.class,
+        "class": class,
+        "tooltip": config.format_window(tooltip_format, window),
+        "address": window.address,
+        "index": index,
+    })
+}
+
+/// One Waybar entry per minimized window, most recent first, as a JSON array.
+pub fn per_window_json(config: &Config, windows: &[MinimizedWindow], tooltip_format: &str) -> String {
+    let entries: Vec<Value> = windows_by_recency(windows)
+        .into_iter()
+        .enumerate()
+        .map(|(i, window)| window_entry(config, window, tooltip_format, i + 1))
+        .collect();
+    Value::Array(entries).to_string()
+}
+
+/// Waybar custom module JSON for the `slot`th most recent window (from 1), so
+/// that a module per slot shows one icon each. Empty text hides the module
+/// while there are fewer windows.
+pub fn slot_json(config: &Config, windows: &[MinimizedWindow], tooltip_format: &str, slot: usize) -> String {
+    let window = slot.checked_sub(1).and_then(|i| windows_by_recency(windows).get(i).copied());
+    match window {
+        Some(window) => window_entry(config, window, tooltip_format, slot).to_string(),
+        None => json!({ "text": "", "class": "empty" }).to_string(),
+    }
+}
+
+/// Have Waybar refresh the module configured with `"signal": <signal>`; 0
+/// sends nothing.
+pub fn signal_waybar(signal: u8) {
+    if signal == 0 {
+        return;
+    }
+    Command::new("pkill")
+        .args([&format!("-RTMIN+{}", signal), "waybar"])
+        .output()
+        .ok();
+}
+
+fn print_if_changed(manager: &Manager, tooltip_format: &str, last: &mut String) -> Result<()> {
+    let status = status_json(&manager.config, &manager.windows()?, tooltip_format);
+
+    if status != *last {
+        println!("{}", status);
+        *last = status;
+    }
+
+    Ok(())
+}
+
+/// Print a new status line whenever the minimized set changes, for waybar's
+/// continuous `exec` mode. Driven by the Hyprland event socket, plus changes
+/// to the cache file made by other minhypr invocations.
+pub fn watch(manager: &Manager, tooltip_format: &str) -> Result<()> {
+    let mut last = String::new();
+
+    watch_changes(manager, || print_if_changed(manager, tooltip_format, &mut last))
+}
@@ -0,0 +1,245 @@
+use std::{
+    cmp::Reverse,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+const ICONS: &[(&str, &str)] = &[
+    ("firefox", ""),
+    ("Alacritty", ""),
+    ("kitty", ""),
+    ("discord", "󰙯"),
+    ("Steam", ""),
+    ("chromium", ""),
+    ("chrome", ""),
+    ("code", "󰨞"),
+    ("spotify", ""),
+    ("default", "󰖲"),
+];
+
+/// A window minhypr has moved to the special workspace.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MinimizedWindow {
+    pub address: String,
+    pub display_title: String,
+    pub class: String,
+    pub original_title: String,
+    pub preview_path: Option<String>,
+    // Nerd-font glyph, used where images can't be shown (waybar, plain menus)
+    pub icon: String,
+    // Icon file from the app's desktop entry, for menus that render images
+    #[serde(default)]
+    pub icon_path: Option<String>,
+    pub workspace: i32,
+    // Name of the monitor the window was on when minimized
+    #[serde(default)]
+    pub monitor: Option<String>,
+    // While that monitor is disconnected: its name, `monitor` being the one
+    // the workspace moved to
+    #[serde(default)]
+    pub home_monitor: Option<String>,
+    // Floating state, geometry and fullscreen state (0 none, 1 maximized,
+    // 2 fullscreen), reapplied on restore
+    #[serde(default)]
+    pub floating: bool,
+    #[serde(default)]
+    pub position: Option<(i32, i32)>,
+    #[serde(default)]
+    pub size: Option<(i32, i32)>,
+    #[serde(default)]
+    pub fullscreen: u8,
+    // Unix timestamp (ms) of when the window was minimized, used for LIFO ordering
+    #[serde(default)]
+    pub minimized_at: u64,
+    // Process owning the window, and whether minhypr stopped it (SIGSTOP) or
+    // muted its audio
+    #[serde(default)]
+    pub pid: i32,
+    #[serde(default)]
+    pub suspended: bool,
+    #[serde(default)]
+    pub muted: bool,
+    // Unix timestamp (ms) at which the daemon restores the window (snooze)
+    #[serde(default)]
+    pub restore_at: Option<u64>,
+    // The window asked for attention while minimized
+    #[serde(default)]
+    pub urgent: bool,
+    // The other windows of its Hyprland group when it was minimized
+    #[serde(default)]
+    pub group: Vec<String>,
+}
+
+pub fn get_app_icon(class_name: &str) -> String {
+    ICONS
+        .iter()
+        .find(|(name, _)| class_name.to_lowercase().contains(&name.to_lowercase()))
+        .map(|(_, icon)| *icon)
+        .unwrap_or(ICONS.last().unwrap().1)
+        .to_string()
+}
+
+fn placeholder(window: &MinimizedWindow, name: &str) -> Option<String> {
+    let value = match name {
+        "icon" => window.icon.clone(),
+        "class" => window.class.clone(),
+        "title" => window.original_title.clone(),
+        "workspace" => window.workspace.to_string(),
+        "monitor" => window.monitor.clone().unwrap_or_default(),
+        "address" => window.address.clone(),
+        // Enough to tell apart windows with the same class and title
+        "short_address" => {
+            let chars: Vec<char> = window.address.chars().collect();
+            chars[chars.len().saturating_sub(4)..].iter().collect()
+        }
+        "age" => window_age(window).map_or_else(|| String::from("?"), format_age),
+        _ => return None,
+    };
+    Some(value)
+}
+
+// Cut to `max` characters, the last one being an ellipsis
+fn truncate(value: String, max: usize) -> String {
+    if value.chars().count() <= max {
+        return value;
+    }
+    let mut truncated: String = value.chars().take(max.saturating_sub(1)).collect();
+    if max > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
+
+/// Render a template with `{icon}`, `{class}`, `{title}`, `{workspace}`,
+/// `{monitor}`, `{address}`, `{short_address}` and `{age}` (time since it was
+/// minimized, e.g. `12m`) placeholders. `{title:30}` cuts the value to 30
+/// characters with an ellipsis; anything else in braces is left as is.
+pub fn format_window(template: &str, window: &MinimizedWindow) -> String {
+    let mut output = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        let value = after.find('}').and_then(|end| {
+            let (name, max) = match after[..end].split_once(':') {
+                Some((name, max)) => (name, Some(max.parse::<usize>().ok()?)),
+                None => (&after[..end], None),
+            };
+            let value = placeholder(window, name)?;
+            Some((match max {
+                Some(max) => truncate(value, max),
+                None => value,
+            }, end))
+        });
+
+        match value {
+            Some((value, end)) => {
+                output.push_str(&value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                output.push('{');
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+
+    output
+}
+
+/// Seconds since the window was minimized, unknown for entries from old caches.
+pub fn window_age(window: &MinimizedWindow) -> Option<u64> {
+    (window.minimized_at > 0).then(|| unix_timestamp_ms().saturating_sub(window.minimized_at) / 1000)
+}
+
+/// A duration like `2h 5m`, `4m`, or `12s`.
+pub fn format_age(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60);
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", secs),
+        (0, 0, _) => format!("{}m", minutes),
+        (0, _, 0) => format!("{}h", hours),
+        (0, _, _) => format!("{}h {}m", hours, minutes),
+        (_, 0, _) => format!("{}d", days),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
+pub fn unix_timestamp_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// On equal timestamps (old cache entries) the cache order decides
+pub fn most_recent_window(windows: &[MinimizedWindow]) -> Option<&MinimizedWindow> {
+    windows.iter().max_by_key(|w| w.minimized_at)
+}
+
+pub fn oldest_window(windows: &[MinimizedWindow]) -> Option<&MinimizedWindow> {
+    windows.iter().min_by_key(|w| w.minimized_at)
+}
+
+/// Windows in most-recently-minimized-first order, as numbered by `--index`.
+pub fn windows_by_recency(windows: &[MinimizedWindow]) -> Vec<&MinimizedWindow> {
+    // Reversed first so that, on equal timestamps, later cache entries come first
+    let mut sorted: Vec<&MinimizedWindow> = windows.iter().rev().collect();
+    sorted.sort_by_key(|w| Reverse(w.minimized_at));
+    sorted
+}
+
+/// Fuzzy match: the query characters appear in order, ignoring case. Lower
+/// scores are tighter matches.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<usize> {
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut best = None;
+
+    // Try every start position of the first character and keep the shortest span
+    for start in 0..text.len() {
+        let mut query_chars = query.to_lowercase().chars().collect::<Vec<_>>().into_iter().peekable();
+        let mut end = None;
+        for (i, c) in text.iter().enumerate().skip(start) {
+            if query_chars.peek() == Some(c) {
+                query_chars.next();
+                if query_chars.peek().is_none() {
+                    end = Some(i);
+                    break;
+                }
+            }
+        }
+        if let Some(end) = end {
+            let span = end - start;
+            if best.is_none_or(|b| span < b) {
+                best = Some(span);
+            }
+        }
+    }
+
+    best
+}
+
+/// Windows whose class and title fuzzy-match the query, best matches first.
+pub fn search_windows<'a>(windows: &'a [MinimizedWindow], query: &str) -> Vec<&'a MinimizedWindow> {
+    rank_matches(windows_by_recency(windows), query)
+}
+
+/// Like [`search_windows`], but equal matches keep the given order (e.g. the
+/// menu order) instead of the most-recent-first one.
+pub fn rank_matches<'a>(windows: Vec<&'a MinimizedWindow>, query: &str) -> Vec<&'a MinimizedWindow> {
+    let mut matches: Vec<(usize, &MinimizedWindow)> = windows
+        .into_iter()
+        .filter_map(|w| fuzzy_score(query, &format!("{} {}", w.class, w.original_title)).map(|score| (score, w)))
+        .collect();
+    // Stable, so equal scores keep their order
+    matches.sort_by_key(|(score, _)| *score);
+    matches.into_iter().map(|(_, w)| w).collect()
+}